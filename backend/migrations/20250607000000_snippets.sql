@@ -0,0 +1,21 @@
+CREATE TABLE snippet (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    key TEXT NOT NULL UNIQUE,
+    description TEXT,
+    current_snippet_version_id INTEGER,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (current_snippet_version_id) REFERENCES snippet_version(id)
+);
+
+CREATE TABLE snippet_version (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    snippet_id INTEGER NOT NULL,
+    version_number INTEGER NOT NULL,
+    content TEXT NOT NULL,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (snippet_id) REFERENCES snippet(id),
+    UNIQUE(snippet_id, version_number)
+);
+
+CREATE INDEX idx_snippet_version_snippet ON snippet_version(snippet_id);
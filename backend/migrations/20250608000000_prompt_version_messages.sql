@@ -0,0 +1,7 @@
+-- Ordered list of message templates (system, few-shot user/assistant pairs, tool examples, final user)
+-- stored as a JSON array. When set it takes precedence over the single system/user templates.
+ALTER TABLE prompt_version
+ADD COLUMN messages TEXT;
+
+ALTER TABLE prompt_version
+ADD COLUMN messages_diff TEXT;
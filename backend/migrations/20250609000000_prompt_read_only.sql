@@ -0,0 +1,2 @@
+-- Prompts synced from a prompts directory can be locked so they're only edited through files
+ALTER TABLE prompt ADD COLUMN read_only BOOLEAN NOT NULL DEFAULT 0;
@@ -0,0 +1,9 @@
+-- Who created each version and why, plus an optional approval gate: when a prompt requires
+-- approval, new versions are drafts until a different user approves them
+ALTER TABLE prompt_version ADD COLUMN change_message TEXT;
+ALTER TABLE prompt_version ADD COLUMN created_by INTEGER REFERENCES user(id) ON DELETE SET NULL;
+ALTER TABLE prompt_version ADD COLUMN is_draft BOOLEAN NOT NULL DEFAULT 0;
+ALTER TABLE prompt_version ADD COLUMN approved_by INTEGER REFERENCES user(id) ON DELETE SET NULL;
+ALTER TABLE prompt_version ADD COLUMN approved_at DATETIME;
+
+ALTER TABLE prompt ADD COLUMN requires_approval BOOLEAN NOT NULL DEFAULT 0;
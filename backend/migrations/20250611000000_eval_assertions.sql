@@ -0,0 +1,16 @@
+-- Automated evals: an expected output and a JSON array of assertions scored on every run
+ALTER TABLE prompt_eval ADD COLUMN expected_output TEXT;
+ALTER TABLE prompt_eval ADD COLUMN assertions TEXT;
+
+CREATE TABLE prompt_eval_run_assertion (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    prompt_eval_run_id INTEGER NOT NULL,
+    position INTEGER NOT NULL,
+    assertion TEXT NOT NULL,
+    passed BOOLEAN NOT NULL,
+    message TEXT,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (prompt_eval_run_id) REFERENCES prompt_eval_run(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_prompt_eval_run_assertion_run ON prompt_eval_run_assertion(prompt_eval_run_id);
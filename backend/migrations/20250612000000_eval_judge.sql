@@ -0,0 +1,13 @@
+-- LLM-as-judge: a prompt's evals can be graded by another prompt against a rubric
+CREATE TABLE prompt_eval_judge (
+    prompt_id INTEGER NOT NULL PRIMARY KEY,
+    judge_prompt_key TEXT NOT NULL,
+    rubric TEXT NOT NULL,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (prompt_id) REFERENCES prompt(id) ON DELETE CASCADE
+);
+
+ALTER TABLE prompt_eval_run ADD COLUMN judge_score INTEGER CHECK (judge_score BETWEEN 1 AND 5);
+ALTER TABLE prompt_eval_run ADD COLUMN judge_rationale TEXT;
+ALTER TABLE prompt_eval_run ADD COLUMN judge_log_id INTEGER REFERENCES log(id) ON DELETE SET NULL;
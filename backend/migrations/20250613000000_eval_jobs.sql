@@ -0,0 +1,24 @@
+-- Eval runs submitted as background jobs, their results are stored as they complete
+CREATE TABLE eval_job (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    prompt_id INTEGER NOT NULL,
+    prompt_version_id INTEGER NOT NULL,
+    rounds INTEGER NOT NULL,
+    status TEXT NOT NULL DEFAULT 'queued' CHECK (status IN ('queued', 'running', 'completed', 'failed', 'cancelled')),
+    total INTEGER NOT NULL,
+    completed INTEGER NOT NULL DEFAULT 0,
+    failed INTEGER NOT NULL DEFAULT 0,
+    error TEXT,
+    created_by INTEGER,
+    started_at DATETIME,
+    finished_at DATETIME,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (prompt_id) REFERENCES prompt(id) ON DELETE CASCADE,
+    FOREIGN KEY (prompt_version_id) REFERENCES prompt_version(id) ON DELETE CASCADE,
+    FOREIGN KEY (created_by) REFERENCES user(id) ON DELETE SET NULL
+);
+
+CREATE INDEX idx_eval_job_prompt ON eval_job(prompt_id);
+
+ALTER TABLE prompt_eval_run ADD COLUMN eval_job_id INTEGER REFERENCES eval_job(id) ON DELETE SET NULL;
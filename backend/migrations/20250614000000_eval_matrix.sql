@@ -0,0 +1,9 @@
+-- Eval jobs can run several versions and model overrides, stored as a JSON array of cells
+ALTER TABLE eval_job ADD COLUMN matrix TEXT;
+
+-- The model, call and latency behind every eval run, for comparing cells of a matrix
+ALTER TABLE prompt_eval_run ADD COLUMN model_id INTEGER REFERENCES model(id);
+ALTER TABLE prompt_eval_run ADD COLUMN log_id INTEGER REFERENCES log(id) ON DELETE SET NULL;
+ALTER TABLE prompt_eval_run ADD COLUMN latency_ms INTEGER;
+
+CREATE INDEX idx_prompt_eval_run_eval_job ON prompt_eval_run(eval_job_id);
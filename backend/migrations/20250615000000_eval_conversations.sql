@@ -0,0 +1,3 @@
+-- Conversation evals of chat prompts, a JSON array of the messages after the system message.
+-- The next assistant turn is evaluated, `user_prompt_input` keeps the last user message.
+ALTER TABLE prompt_eval ADD COLUMN messages TEXT;
@@ -0,0 +1,21 @@
+-- Prices in USD per million tokens, a model's price at any moment is the row with the latest effective_from before it
+CREATE TABLE model_price (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    model_id INTEGER NOT NULL,
+    input_per_million REAL NOT NULL CHECK (input_per_million >= 0),
+    output_per_million REAL NOT NULL CHECK (output_per_million >= 0),
+    -- Reasoning and cached input tokens are billed at the output and input prices when unset
+    reasoning_per_million REAL CHECK (reasoning_per_million >= 0),
+    cached_input_per_million REAL CHECK (cached_input_per_million >= 0),
+    effective_from DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (model_id) REFERENCES model(id) ON DELETE CASCADE,
+    UNIQUE (model_id, effective_from)
+);
+
+-- Cost is computed when the request is logged so later price changes don't rewrite history
+ALTER TABLE log ADD COLUMN cached_input_tokens INTEGER;
+ALTER TABLE log ADD COLUMN cost REAL;
+ALTER TABLE log ADD COLUMN api_key_id INTEGER REFERENCES api_key(id) ON DELETE SET NULL;
+
+CREATE INDEX idx_log_api_key ON log(api_key_id);
@@ -0,0 +1,7 @@
+-- Timing of every attempt at a request, a retried request logs each of its attempts
+ALTER TABLE log ADD COLUMN started_at DATETIME;
+ALTER TABLE log ADD COLUMN duration_ms INTEGER;
+-- Only set for streamed responses
+ALTER TABLE log ADD COLUMN first_token_ms INTEGER;
+-- 1 for the first try, retries are the attempts after it
+ALTER TABLE log ADD COLUMN attempt INTEGER;
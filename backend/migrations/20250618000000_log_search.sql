@@ -0,0 +1,32 @@
+-- Version of the prompt the request ran against, unset for requests logged before this
+ALTER TABLE log ADD COLUMN prompt_version_id INTEGER REFERENCES prompt_version(id) ON DELETE SET NULL;
+
+CREATE INDEX idx_log_prompt_version ON log(prompt_version_id);
+CREATE INDEX idx_log_duration ON log(duration_ms);
+
+-- Full-text index over request and response bodies, kept in sync with log by the triggers below
+CREATE VIRTUAL TABLE log_fts USING fts5(
+    request_body,
+    response_data,
+    content='log',
+    content_rowid='id'
+);
+
+INSERT INTO log_fts(log_fts) VALUES ('rebuild');
+
+CREATE TRIGGER log_fts_insert AFTER INSERT ON log BEGIN
+    INSERT INTO log_fts(rowid, request_body, response_data)
+    VALUES (new.id, new.request_body, new.response_data);
+END;
+
+CREATE TRIGGER log_fts_delete AFTER DELETE ON log BEGIN
+    INSERT INTO log_fts(log_fts, rowid, request_body, response_data)
+    VALUES ('delete', old.id, old.request_body, old.response_data);
+END;
+
+CREATE TRIGGER log_fts_update AFTER UPDATE OF request_body, response_data ON log BEGIN
+    INSERT INTO log_fts(log_fts, rowid, request_body, response_data)
+    VALUES ('delete', old.id, old.request_body, old.response_data);
+    INSERT INTO log_fts(rowid, request_body, response_data)
+    VALUES (new.id, new.request_body, new.response_data);
+END;
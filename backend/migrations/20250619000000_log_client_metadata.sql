@@ -0,0 +1,8 @@
+-- Metadata the calling application sends along with a request, see ClientMetadata
+ALTER TABLE log ADD COLUMN client_user_id TEXT;
+ALTER TABLE log ADD COLUMN session_id TEXT;
+-- JSON array of strings
+ALTER TABLE log ADD COLUMN tags TEXT;
+
+CREATE INDEX idx_log_client_user ON log(client_user_id);
+CREATE INDEX idx_log_session ON log(session_id);
@@ -0,0 +1,15 @@
+-- End-user feedback on a completion, a completion keeps only its latest feedback
+CREATE TABLE feedback (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    log_id INTEGER NOT NULL UNIQUE,
+    -- Up to the caller's scale, e.g. 1 for thumbs up and 0 for thumbs down
+    score REAL,
+    label TEXT,
+    comment TEXT,
+    api_key_id INTEGER,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (log_id) REFERENCES log(id) ON DELETE CASCADE,
+    FOREIGN KEY (api_key_id) REFERENCES api_key(id) ON DELETE SET NULL,
+    CHECK (score IS NOT NULL OR label IS NOT NULL)
+);
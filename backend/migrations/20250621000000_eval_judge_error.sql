@@ -0,0 +1,2 @@
+-- Why the judge couldn't grade a run, the run itself is kept unscored
+ALTER TABLE prompt_eval_run ADD COLUMN judge_error TEXT;
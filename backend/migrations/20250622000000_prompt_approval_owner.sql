@@ -0,0 +1,2 @@
+-- Who turned a prompt's approval gate on, only they can turn it off again
+ALTER TABLE prompt ADD COLUMN approval_required_by INTEGER REFERENCES user(id) ON DELETE SET NULL;
@@ -0,0 +1,416 @@
+//! Runs a prompt's eval suite from CI and fails the build when it falls below thresholds.
+//!
+//! Runs against the database directly (`DATABASE_URL`) or a running server (`--server`),
+//! prints a summary and optionally writes JUnit XML and JSON reports. Exits with 1 when a
+//! threshold isn't met and with 2 when the suite couldn't run.
+
+use std::{collections::BTreeMap, path::PathBuf, process::ExitCode, time::Duration};
+
+use anyhow::{anyhow, Result};
+use clap::Parser;
+use serde::Deserialize;
+
+use backend::{
+    controllers::types::response::prompt_eval_run::PromptEvalRunResponse,
+    db::init::DbData,
+    services::{
+        eval_suite::{apply_thresholds, junit_xml, run_eval_suite, suite_report, DEFAULT_MIN_CASE_SCORE},
+        types::eval_suite::{EvalSuiteCase, EvalSuiteCaseStatus, EvalSuiteReport, EvalThresholds},
+    },
+};
+
+#[derive(Debug, Parser)]
+#[command(about = "Run a prompt's evals and exit non-zero below the given thresholds")]
+struct Args {
+    /// Key of the prompt to evaluate
+    #[arg(long)]
+    prompt: String,
+    /// Version number to evaluate, the active version when omitted
+    #[arg(long)]
+    version: Option<i64>,
+    #[arg(long, default_value_t = 1)]
+    rounds: i64,
+    /// SQLite database to run against
+    #[arg(long, env = "DATABASE_URL")]
+    database_url: Option<String>,
+    /// Base URL of a running server to run against instead of the database
+    #[arg(long)]
+    server: Option<String>,
+    /// Session token for `--server`, the value of the `llmkit_auth_token` cookie
+    #[arg(long, env = "LLMKIT_AUTH_TOKEN", hide_env_values = true)]
+    token: Option<String>,
+    /// Write a JUnit XML report to this file
+    #[arg(long)]
+    junit: Option<PathBuf>,
+    /// Write a JSON report to this file
+    #[arg(long)]
+    json: Option<PathBuf>,
+    /// Fail when fewer than this share of the graded cases pass, 0-1
+    #[arg(long)]
+    min_pass_rate: Option<f64>,
+    /// Fail when the average score is below this, 1-5
+    #[arg(long)]
+    min_score: Option<f64>,
+    /// Score a case graded only by the eval judge needs to pass
+    #[arg(long, default_value_t = DEFAULT_MIN_CASE_SCORE)]
+    min_case_score: i64,
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    dotenv::dotenv().ok();
+
+    let log_level = std::env::var("RUST_LOG").unwrap_or("warn".to_string());
+    tracing_subscriber::fmt().with_env_filter(log_level).with_writer(std::io::stderr).init();
+
+    let args = Args::parse();
+    match run(&args).await {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::from(1),
+        Err(e) => {
+            eprintln!("error: {:#}", e);
+            ExitCode::from(2)
+        }
+    }
+}
+
+async fn run(args: &Args) -> Result<bool> {
+    if args.rounds < 1 {
+        return Err(anyhow!("--rounds has to be at least 1"));
+    }
+
+    let mut report = match (&args.server, &args.database_url) {
+        (Some(server), _) => {
+            let token = args
+                .token
+                .as_deref()
+                .ok_or_else(|| anyhow!("--server needs a session token, pass --token or set LLMKIT_AUTH_TOKEN"))?;
+            run_on_server(server, token, args).await?
+        }
+        (None, Some(database_url)) => {
+            let db = DbData::new(database_url).await?;
+            run_eval_suite(&db, &args.prompt, args.version, args.rounds, args.min_case_score).await?
+        }
+        (None, None) => return Err(anyhow!("Pass --database-url (or set DATABASE_URL) or --server")),
+    };
+
+    let passed = apply_thresholds(
+        &mut report,
+        &EvalThresholds {
+            min_pass_rate: args.min_pass_rate,
+            min_score: args.min_score,
+        },
+    );
+
+    print_summary(&report);
+
+    if let Some(path) = &args.junit {
+        std::fs::write(path, junit_xml(&report))?;
+    }
+    if let Some(path) = &args.json {
+        std::fs::write(path, serde_json::to_string_pretty(&report)?)?;
+    }
+
+    Ok(passed)
+}
+
+#[derive(Debug, Deserialize)]
+struct ServerPrompt {
+    id: i64,
+    key: String,
+    version_id: i64,
+    version_number: i64,
+}
+
+#[derive(Debug, Deserialize)]
+struct ServerEval {
+    id: i64,
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ServerEvalJob {
+    id: i64,
+    status: String,
+    error: Option<String>,
+}
+
+const JOB_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Same suite through the server, as an eval job. A case that errors on the server has no
+/// run and is reported as errored with the job's last error.
+async fn run_on_server(server: &str, token: &str, args: &Args) -> Result<EvalSuiteReport> {
+    let server = server.trim_end_matches('/');
+    let client = reqwest::Client::new();
+    let request = |method: reqwest::Method, path: String| {
+        client
+            .request(method, format!("{}/v1{}", server, path))
+            .header("Cookie", format!("llmkit_auth_token={}", token))
+    };
+    let get = |path: String| request(reqwest::Method::GET, path);
+
+    let prompt = get("/ui/prompts".to_string())
+        .send()
+        .await?
+        .error_for_status()?
+        .json::<Vec<ServerPrompt>>()
+        .await?
+        .into_iter()
+        .find(|p| p.key == args.prompt)
+        .ok_or_else(|| anyhow!("Prompt '{}' not found", args.prompt))?;
+
+    let version = match args.version {
+        Some(number) => get(format!("/ui/prompts/{}/versions", prompt.id))
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<Vec<ServerPrompt>>()
+            .await?
+            .into_iter()
+            .find(|v| v.version_number == number)
+            .ok_or_else(|| anyhow!("Prompt '{}' has no version {}", args.prompt, number))?,
+        None => prompt,
+    };
+
+    let evals = get(format!("/ui/prompts/{}/prompt-evals", version.id))
+        .send()
+        .await?
+        .error_for_status()?
+        .json::<Vec<ServerEval>>()
+        .await?;
+
+    let response = request(
+        reqwest::Method::POST,
+        format!(
+            "/ui/prompt-eval-runs/{}/version/{}/jobs?rounds={}",
+            version.id, version.version_id, args.rounds
+        ),
+    )
+    .send()
+    .await?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(anyhow!("Eval job failed to start with {}: {}", status, response.text().await?));
+    }
+
+    let mut job = response.json::<ServerEvalJob>().await?;
+    while job.status == "queued" || job.status == "running" {
+        tokio::time::sleep(JOB_POLL_INTERVAL).await;
+        job = get(format!("/ui/eval-jobs/{}", job.id))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+    }
+
+    let runs = get(format!("/ui/eval-jobs/{}/runs", job.id))
+        .send()
+        .await?
+        .error_for_status()?
+        .json::<Vec<PromptEvalRunResponse>>()
+        .await?;
+
+    let error = match job.status.as_str() {
+        "cancelled" => "Eval job was cancelled".to_string(),
+        _ => job.error.unwrap_or_else(|| "No response".to_string()),
+    };
+    let cases = server_cases(&evals, &runs, args.rounds, &error, args.min_case_score);
+
+    Ok(suite_report(version.key, version.id, version.version_id, version.version_number, cases))
+}
+
+/// Turns a job's runs into one case per eval and round. Every round shares a run id across
+/// its evals, and a round with text and tool calls has a run for each. Runs don't say which
+/// round they're from, so rounds are numbered in the order their first run was stored, and
+/// rounds where every case errored come last with no runs at all.
+fn server_cases(
+    evals: &[ServerEval],
+    runs: &[PromptEvalRunResponse],
+    rounds: i64,
+    error: &str,
+    min_case_score: i64,
+) -> Vec<EvalSuiteCase> {
+    let mut round_runs = BTreeMap::<&str, Vec<&PromptEvalRunResponse>>::new();
+    for run in runs {
+        round_runs.entry(run.run_id.as_str()).or_default().push(run);
+    }
+    let mut round_runs = round_runs.into_values().collect::<Vec<_>>();
+    round_runs.sort_by_key(|runs| runs.iter().map(|r| r.id).min());
+
+    let mut cases = Vec::with_capacity(evals.len() * rounds.max(0) as usize);
+    for round in 1..=rounds {
+        let runs = round_runs.get(round as usize - 1).map(Vec::as_slice).unwrap_or_default();
+
+        for eval in evals {
+            let eval_runs = runs
+                .iter()
+                .filter(|r| r.prompt_eval_id == eval.id)
+                .collect::<Vec<_>>();
+
+            let case = match eval_runs.first() {
+                Some(run) => EvalSuiteCase {
+                    prompt_eval_id: eval.id,
+                    name: eval.name.clone(),
+                    round,
+                    status: EvalSuiteCaseStatus::Skipped,
+                    score: run.score,
+                    assertions_passed: run.assertions.iter().filter(|a| a.passed).count(),
+                    assertions_total: run.assertions.len(),
+                    failures: run
+                        .assertions
+                        .iter()
+                        .filter(|a| !a.passed)
+                        .map(|a| a.message.clone().unwrap_or_else(|| format!("Assertion failed: {}", a.assertion)))
+                        .collect(),
+                    output: Some(eval_runs.iter().map(|r| r.output.as_str()).collect::<Vec<_>>().join("\n")),
+                    latency_ms: run.latency_ms,
+                },
+                None => EvalSuiteCase::errored(eval.id, eval.name.clone(), round, error.to_string()),
+            };
+            cases.push(case.grade(min_case_score));
+        }
+    }
+
+    cases
+}
+
+fn print_summary(report: &EvalSuiteReport) {
+    for case in &report.cases {
+        let label = match case.status {
+            EvalSuiteCaseStatus::Passed => continue,
+            EvalSuiteCaseStatus::Failed => "FAIL",
+            EvalSuiteCaseStatus::Errored => "ERROR",
+            EvalSuiteCaseStatus::Skipped => "SKIP",
+        };
+        println!("{:5} {} (round {})", label, case.name, case.round);
+        for failure in &case.failures {
+            println!("      {}", failure);
+        }
+    }
+
+    let percent = |v: Option<f64>| v.map_or("n/a".to_string(), |v| format!("{:.1}%", v * 100.0));
+    println!(
+        "\n{} v{}: {} passed, {} failed, {} errored, {} skipped of {} | pass rate {} | avg score {}",
+        report.prompt_key,
+        report.version_number,
+        report.passed,
+        report.failed,
+        report.errored,
+        report.skipped,
+        report.total,
+        percent(report.pass_rate),
+        report.avg_score.map_or("n/a".to_string(), |s| format!("{:.2}", s)),
+    );
+
+    for failure in &report.threshold_failures {
+        println!("threshold not met: {}", failure);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::{
+        routing::{get, post},
+        Json, Router,
+    };
+    use serde_json::json;
+
+    use super::*;
+
+    fn run(id: i64, eval_id: i64, name: &str, run_id: &str, passed: bool) -> serde_json::Value {
+        json!({
+            "id": id,
+            "run_id": run_id,
+            "prompt_version_id": 10,
+            "prompt_eval_id": eval_id,
+            "prompt_eval_name": name,
+            "score": if passed { 5 } else { 1 },
+            "output": format!("Output {}", id),
+            "created_at": "2025-06-21 00:00:00",
+            "updated_at": "2025-06-21 00:00:00",
+            "assertions": [{ "assertion": { "type": "contains", "value": "x" }, "passed": passed, "message": null }],
+        })
+    }
+
+    #[tokio::test]
+    async fn test_server_run_grades_every_eval() {
+        let app = Router::new()
+            .route(
+                "/v1/ui/prompts",
+                get(|| async { Json(json!([{ "id": 1, "key": "greeter", "version_id": 10, "version_number": 2 }])) }),
+            )
+            .route(
+                "/v1/ui/prompts/1/prompt-evals",
+                get(|| async {
+                    Json(json!([{ "id": 1, "name": "first" }, { "id": 2, "name": "second" }, { "id": 3, "name": "third" }]))
+                }),
+            )
+            .route(
+                "/v1/ui/prompt-eval-runs/1/version/10/jobs",
+                post(|| async { Json(json!({ "id": 7, "status": "queued", "error": null })) }),
+            )
+            .route(
+                "/v1/ui/eval-jobs/7",
+                get(|| async { Json(json!({ "id": 7, "status": "completed", "error": "Provider timed out" })) }),
+            )
+            .route(
+                "/v1/ui/eval-jobs/7/runs",
+                get(|| async {
+                    Json(json!([
+                        run(1, 1, "first", "round-1", true),
+                        run(2, 2, "second", "round-1", false),
+                    ]))
+                }),
+            );
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let server = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let args = Args::parse_from(["eval", "--prompt", "greeter", "--server", &server, "--token", "token"]);
+        let report = run_on_server(&server, "token", &args).await.unwrap();
+
+        let statuses = report.cases.iter().map(|c| (c.name.as_str(), c.status)).collect::<Vec<_>>();
+        assert_eq!(
+            statuses,
+            vec![
+                ("first", EvalSuiteCaseStatus::Passed),
+                ("second", EvalSuiteCaseStatus::Failed),
+                ("third", EvalSuiteCaseStatus::Errored),
+            ]
+        );
+        assert_eq!(report.cases[2].failures, vec!["Provider timed out".to_string()]);
+        assert_eq!(report.version_number, 2);
+    }
+
+    #[test]
+    fn test_server_cases_group_runs_by_round() {
+        let evals = [ServerEval { id: 1, name: "first".to_string() }, ServerEval { id: 2, name: "second".to_string() }];
+        // Three rounds, one of which errored on every case, and the other two ran concurrently
+        let runs = [
+            run(1, 1, "first", "round-b", true),
+            run(2, 2, "second", "round-c", false),
+            run(3, 2, "second", "round-b", true),
+            run(4, 1, "first", "round-c", false),
+        ]
+        .map(|run| serde_json::from_value::<PromptEvalRunResponse>(run).unwrap());
+
+        let cases = server_cases(&evals, &runs, 3, "Provider timed out", 3);
+        let outcomes = cases
+            .iter()
+            .map(|c| (c.round, c.name.as_str(), c.status, c.output.as_deref()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            outcomes,
+            vec![
+                (1, "first", EvalSuiteCaseStatus::Passed, Some("Output 1")),
+                (1, "second", EvalSuiteCaseStatus::Passed, Some("Output 3")),
+                (2, "first", EvalSuiteCaseStatus::Failed, Some("Output 4")),
+                (2, "second", EvalSuiteCaseStatus::Failed, Some("Output 2")),
+                (3, "first", EvalSuiteCaseStatus::Errored, None),
+                (3, "second", EvalSuiteCaseStatus::Errored, None),
+            ]
+        );
+    }
+}
@@ -30,6 +30,23 @@ pub struct ChatCompletionRequest {
     pub max_tokens: Option<u32>,
     /// What sampling temperature to use, between 0 and 2
     pub temperature: Option<f32>,
+    /// (Optional) llmkit extension tying the request to the caller's user, session and features.
+    /// Can also be sent as `X-LLMKit-*` headers.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub llmkit: Option<ClientMetadata>,
+}
+
+/// Who and what a request was made for, as the calling application knows it. Logged with the request.
+#[derive(Debug, Default, Deserialize, Serialize, Clone, PartialEq, Eq)]
+pub struct ClientMetadata {
+    /// The caller's own id for its end user
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_id: Option<String>,
+    /// Groups the requests of a multi-turn conversation
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Eq)]
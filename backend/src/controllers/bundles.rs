@@ -0,0 +1,74 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::header,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Deserialize;
+
+use crate::{
+    services::{
+        bundles::{export_prompt, import_bundle, validate_bundle},
+        types::bundle::{BundleImportReport, PromptBundle},
+    },
+    AppError, AppState,
+};
+
+#[derive(Deserialize)]
+pub struct ExportParams {
+    /// `yaml` (default) or `json`
+    format: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct ImportParams {
+    #[serde(default)]
+    dry_run: bool,
+}
+
+pub async fn export_prompt_bundle(
+    Path(id): Path<i64>,
+    Query(params): Query<ExportParams>,
+    State(state): State<AppState>,
+) -> Result<Response, AppError> {
+    state
+        .db
+        .prompt
+        .get_prompt(id)
+        .await
+        .map_err(|_| AppError::NotFound("Prompt not found".into()))?;
+
+    let bundle = export_prompt(&state.db, id).await?;
+
+    match params.format.as_deref().unwrap_or("yaml") {
+        "json" => Ok(Json(bundle).into_response()),
+        "yaml" => {
+            let yaml = serde_yaml::to_string(&bundle)
+                .map_err(|e| AppError::InternalServerError(format!("Failed to serialize bundle: {}", e)))?;
+            Ok(([(header::CONTENT_TYPE, "application/yaml")], yaml).into_response())
+        }
+        other => Err(AppError::BadRequest(format!("Unsupported export format '{}'", other))),
+    }
+}
+
+/// Accepts a bundle as JSON or YAML (YAML being a superset of JSON, one parser handles both).
+pub async fn import_prompt_bundle(
+    Query(params): Query<ImportParams>,
+    State(state): State<AppState>,
+    body: String,
+) -> Result<Json<BundleImportReport>, AppError> {
+    let bundle: PromptBundle = serde_yaml::from_str(&body)
+        .map_err(|e| AppError::BadRequest(format!("Invalid bundle: {}", e)))?;
+
+    validate_bundle(&bundle).map_err(AppError::BadRequest)?;
+
+    let report = import_bundle(&state.db, bundle, params.dry_run).await?;
+
+    if !report.dry_run {
+        for prompt_id in report.prompts.iter().filter_map(|p| p.prompt_id) {
+            state.prompt_cache.remove(&prompt_id).await;
+        }
+    }
+
+    Ok(Json(report))
+}
@@ -0,0 +1,267 @@
+use axum::{
+    extract::{Path, Query, State},
+    response::sse::{Event, KeepAlive, Sse},
+    Extension, Json,
+};
+use futures::Stream;
+use std::{convert::Infallible, pin::Pin};
+use tokio::sync::broadcast::error::RecvError;
+
+use crate::{
+    db::types::eval_job::EvalJobRow,
+    middleware::auth::UserId,
+    services::{
+        eval_jobs::progress,
+        eval_matrix::{matrix_cells, matrix_report},
+        eval_runner::{load_eval_target, EvalTarget},
+        types::{
+            eval_job::EvalJobEvent,
+            eval_matrix::{EvalMatrixCell, EvalMatrixReport},
+        },
+    },
+    AppError, AppState,
+};
+
+use super::{
+    prompt_eval_run::EvalRunParams,
+    types::{
+        request::eval_job::CreateEvalMatrixJobRequest,
+        response::{eval_job::EvalJobResponse, prompt_eval_run::PromptEvalRunResponse},
+    },
+};
+
+const MAX_MATRIX_CELLS: usize = 25;
+
+type SseStream = Pin<Box<dyn Stream<Item = Result<Event, Infallible>> + Send>>;
+
+/// Queues every eval of the prompt against a version and returns right away, follow along
+/// with `GET /ui/eval-jobs/{id}/events`.
+pub async fn create_eval_job(
+    Path((prompt_id, prompt_version_id)): Path<(i64, i64)>,
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+    Query(params): Query<EvalRunParams>,
+) -> Result<Json<EvalJobResponse>, AppError> {
+    let target = load_eval_target(&state.db, prompt_id, prompt_version_id)
+        .await
+        .map_err(|e| AppError::InternalServerError(e.to_string()))?
+        .ok_or_else(|| AppError::NotFound(format!("Version {} not found for this prompt", prompt_version_id)))?;
+
+    let job = start_job(&state, prompt_id, vec![target], params.rounds, user_id).await?;
+    Ok(Json(job.into()))
+}
+
+/// Same as `create_eval_job`, but runs the evals against every version and model override
+/// pairing. The results are compared with `GET /ui/eval-jobs/{id}/matrix`.
+pub async fn create_eval_matrix_job(
+    Path(prompt_id): Path<i64>,
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+    Json(request): Json<CreateEvalMatrixJobRequest>,
+) -> Result<Json<EvalJobResponse>, AppError> {
+    if request.prompt_version_ids.is_empty() {
+        return Err(AppError::BadRequest("At least one prompt version is required".to_string()));
+    }
+
+    let mut versions = Vec::with_capacity(request.prompt_version_ids.len());
+    let mut targets = Vec::with_capacity(request.prompt_version_ids.len());
+    for version_id in request.prompt_version_ids {
+        let target = load_eval_target(&state.db, prompt_id, version_id)
+            .await
+            .map_err(|e| AppError::InternalServerError(e.to_string()))?
+            .ok_or_else(|| AppError::NotFound(format!("Version {} not found for this prompt", version_id)))?;
+        versions.push((version_id, target.prompt.model_id));
+        targets.push(target);
+    }
+
+    let cells = matrix_cells(&versions, &request.model_ids);
+    if cells.len() > MAX_MATRIX_CELLS {
+        return Err(AppError::BadRequest(format!(
+            "A matrix can have at most {} version and model pairs, this one has {}",
+            MAX_MATRIX_CELLS,
+            cells.len()
+        )));
+    }
+
+    // Resolve every model up front so a bad id fails the request instead of the job
+    let mut cell_targets = Vec::with_capacity(cells.len());
+    for cell in cells {
+        let target = targets
+            .iter()
+            .find(|t| t.prompt.version_id == cell.prompt_version_id)
+            .cloned()
+            .ok_or_else(|| AppError::InternalServerError("Matrix cell without a version".to_string()))?;
+
+        if cell.model_id == target.prompt.model_id {
+            cell_targets.push(target);
+            continue;
+        }
+
+        let model = state
+            .db
+            .model
+            .get_model_by_id(cell.model_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound(format!("Model {} not found", cell.model_id)))?;
+        cell_targets.push(target.with_model(model));
+    }
+
+    let job = start_job(&state, prompt_id, cell_targets, request.rounds, user_id).await?;
+    Ok(Json(job.into()))
+}
+
+async fn start_job(
+    state: &AppState,
+    prompt_id: i64,
+    targets: Vec<EvalTarget>,
+    rounds: Option<i64>,
+    user_id: UserId,
+) -> Result<EvalJobRow, AppError> {
+    let rounds = rounds.unwrap_or(1);
+    if rounds < 1 {
+        return Err(AppError::BadRequest("Rounds must be at least 1".to_string()));
+    }
+
+    let evals = state.db.prompt_eval.get_by_prompt(prompt_id).await?;
+    if evals.is_empty() {
+        return Err(AppError::BadRequest("This prompt has no evals".to_string()));
+    }
+
+    let cells = targets
+        .iter()
+        .map(|t| EvalMatrixCell {
+            prompt_version_id: t.prompt.version_id,
+            model_id: t.prompt.model_id,
+        })
+        .collect::<Vec<_>>();
+    let matrix = serde_json::to_string(&cells)
+        .map_err(|e| AppError::InternalServerError(format!("Failed to serialize matrix: {}", e)))?;
+
+    let total = evals.len() as i64 * targets.len() as i64 * rounds;
+    let job = state
+        .db
+        .eval_job
+        .create(prompt_id, cells[0].prompt_version_id, rounds, total, user_id.0, &matrix)
+        .await?;
+
+    state.eval_jobs.start(state.db.clone(), job.clone(), targets, evals);
+
+    Ok(job)
+}
+
+pub async fn get_eval_jobs_by_prompt(
+    Path(prompt_id): Path<i64>,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<EvalJobResponse>>, AppError> {
+    let jobs = state.db.eval_job.get_by_prompt(prompt_id).await?;
+    Ok(Json(jobs.into_iter().map(|j| j.into()).collect()))
+}
+
+pub async fn get_eval_job(
+    Path(id): Path<i64>,
+    State(state): State<AppState>,
+) -> Result<Json<EvalJobResponse>, AppError> {
+    Ok(Json(find_job(&state, id).await?.into()))
+}
+
+/// Results stored so far, complete once the job is done.
+pub async fn get_eval_job_runs(
+    Path(id): Path<i64>,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<PromptEvalRunResponse>>, AppError> {
+    find_job(&state, id).await?;
+
+    let runs = state.db.prompt_eval_run.get_by_eval_job(id).await?;
+
+    let mut responses = Vec::with_capacity(runs.len());
+    for run in runs {
+        let assertions = state.db.prompt_eval_run.get_assertion_results(run.id).await?;
+
+        let mut response: PromptEvalRunResponse = run.into();
+        response.assertions = assertions.into_iter().map(|a| a.into()).collect();
+
+        responses.push(response);
+    }
+
+    Ok(Json(responses))
+}
+
+/// Score, pass rate, latency and tokens per version and model of a job, so far.
+pub async fn get_eval_job_matrix(
+    Path(id): Path<i64>,
+    State(state): State<AppState>,
+) -> Result<Json<EvalMatrixReport>, AppError> {
+    let job = find_job(&state, id).await?;
+    Ok(Json(matrix_report(&state.db, &job).await?))
+}
+
+pub async fn cancel_eval_job(
+    Path(id): Path<i64>,
+    State(state): State<AppState>,
+) -> Result<Json<EvalJobResponse>, AppError> {
+    let job = find_job(&state, id).await?;
+
+    if !state.eval_jobs.cancel(id) {
+        return Err(AppError::Conflict(format!("Eval job {} is already {}", id, job.status)));
+    }
+
+    // The job may have finished in the meantime, in which case its row already says how
+    let job = match state.db.eval_job.cancel(id).await? {
+        Some(job) => job,
+        None => find_job(&state, id).await?,
+    };
+
+    Ok(Json(job.into()))
+}
+
+/// Streams a job's progress as SSE. Starts with a `progress` event for the current state,
+/// then a `case` and `progress` event per finished case, and ends with `done`.
+pub async fn stream_eval_job_events(
+    Path(id): Path<i64>,
+    State(state): State<AppState>,
+) -> Result<Sse<SseStream>, AppError> {
+    // Subscribe before reading the job so nothing happens between the snapshot and the stream
+    let receiver = state.eval_jobs.subscribe(id);
+    let job = find_job(&state, id).await?;
+
+    let stream: SseStream = Box::pin(async_stream::stream! {
+        yield Ok(event(&progress(&job)));
+
+        let Some(mut receiver) = receiver else {
+            yield Ok(event(&EvalJobEvent::Done { status: job.status.clone(), error: job.error.clone() }));
+            return;
+        };
+
+        loop {
+            match receiver.recv().await {
+                Ok(ev) => {
+                    let done = matches!(ev, EvalJobEvent::Done { .. });
+                    yield Ok(event(&ev));
+                    if done {
+                        break;
+                    }
+                }
+                // A slow client only misses events, the next progress event catches it up
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            }
+        }
+    });
+
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
+async fn find_job(state: &AppState, id: i64) -> Result<EvalJobRow, AppError> {
+    state
+        .db
+        .eval_job
+        .get_by_id(id)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("Eval job {} not found", id)))
+}
+
+fn event(ev: &EvalJobEvent) -> Event {
+    Event::default()
+        .event(ev.name())
+        .data(serde_json::to_string(ev).expect("Failed to turn eval job event into string"))
+}
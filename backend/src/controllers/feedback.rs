@@ -0,0 +1,42 @@
+use axum::{extract::State, http::Extensions, Json};
+
+use crate::{middleware::auth::ApiKeyId, AppError, AppState};
+use super::types::{request::feedback::CreateFeedbackRequest, response::feedback::FeedbackResponse};
+
+const MAX_LABEL_LEN: usize = 64;
+const MAX_COMMENT_LEN: usize = 4000;
+
+/// Records an end user's feedback on a completion. Sending feedback again for the same
+/// completion replaces it, so a thumbs up can be taken back.
+pub async fn create_feedback(
+    State(state): State<AppState>,
+    // Carries the `ApiKeyId` the feedback was sent with
+    extensions: Extensions,
+    Json(payload): Json<CreateFeedbackRequest>,
+) -> Result<Json<FeedbackResponse>, AppError> {
+    let label = payload.label.as_deref().map(str::trim).filter(|l| !l.is_empty());
+    let comment = payload.comment.as_deref().map(str::trim).filter(|c| !c.is_empty());
+
+    if payload.score.is_none() && label.is_none() {
+        return Err(AppError::BadRequest("Feedback needs a `score` or a `label`".to_string()));
+    }
+    if payload.score.is_some_and(|s| !s.is_finite()) {
+        return Err(AppError::BadRequest("`score` must be a finite number".to_string()));
+    }
+    if label.is_some_and(|l| l.len() > MAX_LABEL_LEN) {
+        return Err(AppError::BadRequest(format!("`label` can be at most {} characters", MAX_LABEL_LEN)));
+    }
+    if comment.is_some_and(|c| c.len() > MAX_COMMENT_LEN) {
+        return Err(AppError::BadRequest(format!("`comment` can be at most {} characters", MAX_COMMENT_LEN)));
+    }
+
+    let api_key_id = extensions.get::<ApiKeyId>().map(|ApiKeyId(id)| *id);
+    let feedback = state
+        .db
+        .feedback
+        .upsert_feedback(&payload.completion_id, payload.score, label, comment, api_key_id)
+        .await?
+        .ok_or(AppError::NotFound(format!("Completion '{}' not found", payload.completion_id)))?;
+
+    Ok(Json(feedback.into()))
+}
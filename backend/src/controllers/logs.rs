@@ -4,8 +4,18 @@ use axum::{
 };
 use serde::Deserialize;
 
-use crate::{AppError, AppState};
-use super::types::response::logs::{ApiLogCountResponse, ApiLogResponse};
+use crate::{
+    db::types::log::{CostGroup, LatencyGroup, LogCursor, LogFilter, LogSort, SortOrder},
+    AppError, AppState,
+};
+use super::types::response::logs::{
+    ApiLogCountResponse, ApiLogResponse, CostTotalResponse, LatencyStatsResponse, LogSearchResponse,
+    SessionResponse, SessionTraceResponse,
+};
+
+const DEFAULT_SEARCH_LIMIT: i64 = 50;
+const MAX_SEARCH_LIMIT: i64 = 200;
+const MAX_SESSION_PAGE_SIZE: i64 = 200;
 
 
 pub async fn get_log(
@@ -34,6 +44,183 @@ pub async fn list_logs(
     Ok(Json(traces.into_iter().map(|t| t.into()).collect()))
 }
 
+#[derive(Deserialize)]
+pub struct LogSearchParams {
+    prompt_key: Option<String>,
+    prompt_version: Option<i64>,
+    model: Option<String>,
+    provider: Option<String>,
+    status_code: Option<i64>,
+    api_key_id: Option<i64>,
+    user_id: Option<String>,
+    session_id: Option<String>,
+    tag: Option<String>,
+    from: Option<chrono::NaiveDateTime>,
+    to: Option<chrono::NaiveDateTime>,
+    min_tokens: Option<i64>,
+    max_tokens: Option<i64>,
+    min_duration_ms: Option<i64>,
+    max_duration_ms: Option<i64>,
+    /// Free text searched for in request and response bodies
+    q: Option<String>,
+    #[serde(default)]
+    sort: LogSort,
+    #[serde(default)]
+    order: SortOrder,
+    cursor: Option<String>,
+    limit: Option<i64>,
+}
+
+/// Logs matching every filter given, a page at a time. Pages are cursor based so logs
+/// written while paging don't shift them.
+pub async fn search_logs(
+    State(state): State<AppState>,
+    Query(params): Query<LogSearchParams>,
+) -> Result<Json<LogSearchResponse>, AppError> {
+    if params.prompt_version.is_some() && params.prompt_key.is_none() {
+        return Err(AppError::BadRequest("`prompt_version` needs a `prompt_key`".to_string()));
+    }
+    if let (Some(from), Some(to)) = (params.from, params.to) {
+        if from > to {
+            return Err(AppError::BadRequest("`from` must not be after `to`".to_string()));
+        }
+    }
+
+    let cursor = params
+        .cursor
+        .as_deref()
+        .map(str::parse::<LogCursor>)
+        .transpose()
+        .map_err(|_| AppError::BadRequest("Invalid `cursor`".to_string()))?;
+    let limit = params.limit.unwrap_or(DEFAULT_SEARCH_LIMIT).clamp(1, MAX_SEARCH_LIMIT);
+
+    let filter = LogFilter {
+        prompt_key: params.prompt_key,
+        prompt_version: params.prompt_version,
+        model: params.model,
+        provider: params.provider,
+        status_code: params.status_code,
+        api_key_id: params.api_key_id,
+        client_user_id: params.user_id,
+        session_id: params.session_id,
+        tag: params.tag,
+        from: params.from,
+        to: params.to,
+        min_tokens: params.min_tokens,
+        max_tokens: params.max_tokens,
+        min_duration_ms: params.min_duration_ms,
+        max_duration_ms: params.max_duration_ms,
+        text: params.q,
+    };
+
+    // One extra log tells whether there's a page after this one
+    let mut rows = state
+        .db
+        .log
+        .search_logs(&filter, params.sort, params.order, cursor, limit + 1)
+        .await?;
+    let has_more = rows.len() as i64 > limit;
+    rows.truncate(limit as usize);
+
+    let next_cursor = rows
+        .last()
+        .filter(|_| has_more)
+        .map(|r| LogCursor { key: r.sort_key, id: r.log.id }.to_string());
+
+    Ok(Json(LogSearchResponse {
+        logs: rows.into_iter().map(|r| r.log.into()).collect(),
+        next_cursor,
+    }))
+}
+
+#[derive(Deserialize)]
+pub struct SessionListParams {
+    user_id: Option<String>,
+    page: i64,
+    page_size: i64,
+}
+
+/// Sessions requests were tagged with, most recently active first.
+pub async fn list_sessions(
+    State(state): State<AppState>,
+    Query(params): Query<SessionListParams>,
+) -> Result<Json<Vec<SessionResponse>>, AppError> {
+    if params.page < 1 {
+        return Err(AppError::BadRequest("`page` starts at 1".to_string()));
+    }
+    if !(1..=MAX_SESSION_PAGE_SIZE).contains(&params.page_size) {
+        return Err(AppError::BadRequest(format!(
+            "`page_size` must be between 1 and {}",
+            MAX_SESSION_PAGE_SIZE
+        )));
+    }
+
+    let sessions = state
+        .db
+        .log
+        .list_sessions(params.user_id.as_deref(), params.page, params.page_size)
+        .await?;
+    Ok(Json(sessions.into_iter().map(|s| s.into()).collect()))
+}
+
+/// Every request of a session in the order it was made, to view a conversation as one trace.
+pub async fn get_session(
+    Path(session_id): Path<String>,
+    State(state): State<AppState>,
+) -> Result<Json<SessionTraceResponse>, AppError> {
+    let logs = state.db.log.list_session_logs(&session_id).await?;
+    if logs.is_empty() {
+        return Err(AppError::NotFound(format!("Session '{}' not found", session_id)));
+    }
+
+    Ok(Json(SessionTraceResponse {
+        session_id,
+        logs: logs.into_iter().map(|l| l.into()).collect(),
+    }))
+}
+
+/// Days to report on, both inclusive
+#[derive(Deserialize)]
+pub struct DateRangeParams {
+    from: Option<chrono::NaiveDate>,
+    to: Option<chrono::NaiveDate>,
+}
+
+impl DateRangeParams {
+    fn validate(&self) -> Result<(), AppError> {
+        if let (Some(from), Some(to)) = (self.from, self.to) {
+            if from > to {
+                return Err(AppError::BadRequest("`from` must not be after `to`".to_string()));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Cost totals per prompt, model, API key or day, optionally within a range of days.
+pub async fn get_cost_totals(
+    State(state): State<AppState>,
+    Path(group): Path<CostGroup>,
+    Query(params): Query<DateRangeParams>,
+) -> Result<Json<Vec<CostTotalResponse>>, AppError> {
+    params.validate()?;
+
+    let totals = state.db.log.get_cost_totals(group, params.from, params.to).await?;
+    Ok(Json(totals.into_iter().map(|t| t.into()).collect()))
+}
+
+/// Latency percentiles per prompt, model or provider, optionally within a range of days.
+pub async fn get_latency_stats(
+    State(state): State<AppState>,
+    Path(group): Path<LatencyGroup>,
+    Query(params): Query<DateRangeParams>,
+) -> Result<Json<Vec<LatencyStatsResponse>>, AppError> {
+    params.validate()?;
+
+    let stats = state.db.log.get_latency_stats(group, params.from, params.to).await?;
+    Ok(Json(stats.into_iter().map(|s| s.into()).collect()))
+}
+
 pub async fn get_logs_count(
     State(state): State<AppState>,
 ) -> Result<Json<ApiLogCountResponse>, AppError> {
@@ -0,0 +1,25 @@
+use axum::{extract::State, http::HeaderMap};
+
+use crate::{AppError, AppState};
+
+/// Prometheus scrape endpoint. It's off until `METRICS_TOKEN` is set, and scrapers have to send the
+/// token as a bearer token since the labels name every prompt.
+pub async fn render_metrics(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<String, AppError> {
+    let Some(token) = &state.metrics_token else {
+        return Err(AppError::NotFound("Not Found".to_string()));
+    };
+
+    let bearer = headers
+        .get("Authorization")
+        .and_then(|header| header.to_str().ok())
+        .and_then(|auth| auth.strip_prefix("Bearer "));
+
+    if bearer != Some(token.as_str()) {
+        return Err(AppError::Unauthorized("Invalid metrics token".to_string()));
+    }
+
+    Ok(state.metrics.render())
+}
@@ -9,3 +9,9 @@ pub mod api_keys;
 pub mod user;
 pub mod schema;
 pub mod tools;
+pub mod snippets;
+pub mod templates;
+pub mod bundles;
+pub mod eval_jobs;
+pub mod feedback;
+pub mod metrics;
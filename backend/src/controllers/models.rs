@@ -4,8 +4,8 @@ use axum::{
 };
 
 use super::types::{
-    request::models::{CreateModelRequest, UpdateModelRequest},
-    response::models::ModelResponse,
+    request::models::{CreateModelPriceRequest, CreateModelRequest, UpdateModelRequest},
+    response::models::{ModelPriceResponse, ModelResponse},
 };
 use crate::{AppError, AppState};
 
@@ -75,3 +75,80 @@ pub async fn update_model(
 
     Ok(Json(model.into()))
 }
+
+pub async fn list_model_prices(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+) -> Result<Json<Vec<ModelPriceResponse>>, AppError> {
+    if state.db.model.get_model_by_id(id).await?.is_none() {
+        return Err(AppError::NotFound("Model not found".to_string()));
+    }
+
+    let prices = state.db.model.list_model_prices(id).await?;
+    Ok(Json(prices.into_iter().map(|p| p.into()).collect()))
+}
+
+pub async fn create_model_price(
+    State(state): State<AppState>,
+    Path(id): Path<i64>,
+    Json(payload): Json<CreateModelPriceRequest>,
+) -> Result<Json<ModelPriceResponse>, AppError> {
+    if state.db.model.get_model_by_id(id).await?.is_none() {
+        return Err(AppError::NotFound("Model not found".to_string()));
+    }
+
+    let prices = [
+        Some(payload.input_per_million),
+        Some(payload.output_per_million),
+        payload.reasoning_per_million,
+        payload.cached_input_per_million,
+    ];
+    if prices.into_iter().flatten().any(|p| !p.is_finite() || p < 0.0) {
+        return Err(AppError::BadRequest("Prices must be non-negative numbers".to_string()));
+    }
+
+    if let Some(effective_from) = payload.effective_from {
+        let existing = state.db.model.list_model_prices(id).await?;
+        if existing.iter().any(|p| p.effective_from == effective_from) {
+            return Err(AppError::Conflict(format!(
+                "Model already has a price effective from {}",
+                effective_from
+            )));
+        }
+    }
+
+    let price = state
+        .db
+        .model
+        .create_model_price(
+            id,
+            payload.input_per_million,
+            payload.output_per_million,
+            payload.reasoning_per_million,
+            payload.cached_input_per_million,
+            payload.effective_from,
+        )
+        .await
+        .map_err(|e| match e.downcast_ref::<sqlx::Error>() {
+            // Two prices created without `effective_from` in the same second both start now
+            Some(sqlx::Error::Database(db_err)) if db_err.is_unique_violation() => {
+                AppError::Conflict("Model already has a price effective from now".to_string())
+            }
+            _ => e.into(),
+        })?;
+
+    Ok(Json(price.into()))
+}
+
+pub async fn delete_model_price(
+    State(state): State<AppState>,
+    Path((id, price_id)): Path<(i64, i64)>,
+) -> Result<(), AppError> {
+    let deleted = state.db.model.delete_model_price(id, price_id).await?;
+
+    if !deleted {
+        return Err(AppError::NotFound("Model price not found".to_string()));
+    }
+
+    Ok(())
+}
@@ -4,9 +4,32 @@ use axum::{
     Json,
 };
 
-use crate::{AppError, AppState};
+use crate::{
+    common::types::chat_request::ChatCompletionRequestMessage,
+    db::{init::DbData, types::prompt_eval::PromptEval},
+    services::{
+        eval_assertions::validate_assertions,
+        eval_conversation::{last_user_input, validate_conversation},
+        eval_import::{case_from_log, parse_eval_cases},
+        eval_judge::validate_judge_prompt,
+        types::{
+            eval_assertion::EvalAssertion,
+            eval_import::{EvalImportCase, EvalImportReport},
+        },
+    },
+    AppError, AppState,
+};
 
-use super::types::{request::prompt_eval::{CreateEvalTestRequest, UpdateEvalTestRequest}, response::prompt_eval::PromptEvalResponse};
+use super::{
+    prompts::editable_prompt,
+    types::{
+        request::prompt_eval::{
+            CreateEvalTestRequest, ImportEvalTestsRequest, PromoteLogRequest, SetEvalJudgeRequest,
+            UpdateEvalTestRequest,
+        },
+        response::prompt_eval::{PromptEvalJudgeResponse, PromptEvalResponse},
+    },
+};
 
 
 // Handlers
@@ -30,19 +53,37 @@ pub async fn create_eval_test(
     State(state): State<AppState>,
     Json(request): Json<CreateEvalTestRequest>,
 ) -> Result<Json<PromptEvalResponse>, AppError> {
+    editable_prompt(&state.db, request.prompt_id).await?;
+
     // For system input, serialize the JSON Value to a string if present
     let system_input = request.system_prompt_input.map(|val| val.to_string());
-    
+
+    let conversation = request.messages.filter(|m| !m.is_empty());
+    let messages = serialize_conversation(&state, request.prompt_id, conversation.as_deref()).await?;
+
     // For user input, either use it as JSON or as plain text depending on the prompt type
-    let user_input = request.user_prompt_input.to_string();
+    let user_input = match &conversation {
+        Some(conversation) => last_user_input(conversation),
+        None if request.user_prompt_input.is_null() => {
+            return Err(AppError::BadRequest("An eval needs a user input or a conversation".to_string()));
+        }
+        None => request.user_prompt_input.to_string(),
+    };
+
+    let expected_output = request.expected_output.filter(|e| !e.is_empty());
+    let assertions = serialize_assertions(request.assertions.as_deref())?;
+    let evaluation_type = evaluation_type(expected_output.as_deref(), assertions.as_deref());
     
     let sample = state.db.prompt_eval
         .create(
             request.prompt_id,
             system_input,
             user_input,
-            "human",
+            evaluation_type,
             request.name,
+            expected_output,
+            assertions,
+            messages,
         )
         .await?;
     
@@ -55,25 +96,238 @@ pub async fn update_eval_test(
     Json(request): Json<UpdateEvalTestRequest>,
 ) -> Result<Json<PromptEvalResponse>, AppError> {
     let existing = state.db.prompt_eval.get_by_id(id).await?;
-    
+    editable_prompt(&state.db, existing.prompt_id).await?;
+
     // For system input, serialize the JSON Value to a string if present
     let system_input = request.system_prompt_input.map(|val| val.to_string());
-    let user_input = request.user_prompt_input;
+
+    let messages = match request.messages {
+        Some(m) if m.is_empty() => None,
+        Some(m) => serialize_conversation(&state, existing.prompt_id, Some(&m)).await?,
+        None => existing.messages,
+    };
+    let user_input = match messages.as_deref() {
+        Some(json) => serde_json::from_str::<Vec<ChatCompletionRequestMessage>>(json)
+            .map(|m| last_user_input(&m))
+            .map_err(|e| AppError::InternalServerError(format!("Invalid stored conversation: {}", e)))?,
+        None => request.user_prompt_input,
+    };
+
+    let expected_output = match request.expected_output {
+        Some(e) => Some(e).filter(|e| !e.is_empty()),
+        None => existing.expected_output,
+    };
+    let assertions = match request.assertions {
+        Some(a) => serialize_assertions(Some(&a))?,
+        None => existing.assertions,
+    };
+    let evaluation_type = evaluation_type(expected_output.as_deref(), assertions.as_deref());
     
     let result = state.db.prompt_eval.update(
         existing.id,
         system_input,
         user_input,
-        request.name
+        request.name,
+        evaluation_type,
+        expected_output,
+        assertions,
+        messages,
     ).await?;
 
     Ok(Json(result.into()))
 }
 
+/// Creates a prompt's eval cases in bulk from CSV or JSONL. Nothing is imported when a row
+/// has an error, and `dry_run` only reports what would be.
+pub async fn import_eval_tests(
+    Path(prompt_id): Path<i64>,
+    State(state): State<AppState>,
+    Json(request): Json<ImportEvalTestsRequest>,
+) -> Result<Json<EvalImportReport>, AppError> {
+    let prompt = editable_prompt(&state.db, prompt_id).await?;
+
+    let (cases, errors) = parse_eval_cases(request.format, &request.data, &request.mapping, &prompt.prompt_type);
+
+    let mut imported = 0;
+    if errors.is_empty() && !request.dry_run {
+        // All cases or none
+        let tx = state.db.begin().await?;
+        for case in &cases {
+            if let Err(e) = create_eval_from_case(&tx.db, prompt_id, case.clone()).await {
+                tx.rollback().await?;
+                return Err(e);
+            }
+            imported += 1;
+        }
+        tx.commit().await?;
+    }
+
+    Ok(Json(EvalImportReport {
+        dry_run: request.dry_run,
+        imported,
+        cases,
+        errors,
+    }))
+}
+
+/// Turns a logged request into an eval case of its prompt, replaying the same variables.
+pub async fn promote_log_to_eval(
+    Path(log_id): Path<i64>,
+    State(state): State<AppState>,
+    Json(request): Json<PromoteLogRequest>,
+) -> Result<Json<PromptEvalResponse>, AppError> {
+    let log = state.db.log.get_log_by_id(log_id).await?
+        .ok_or_else(|| AppError::NotFound("Log not found".to_string()))?;
+    let prompt_id = log.prompt_id
+        .ok_or_else(|| AppError::BadRequest("This log isn't linked to a prompt".to_string()))?;
+    let request_body = log.request_body.as_deref()
+        .ok_or_else(|| AppError::BadRequest("This log has no request".to_string()))?;
+    editable_prompt(&state.db, prompt_id).await?;
+
+    let mut case = case_from_log(request_body, log.response_data.as_deref(), request.use_response_as_expected_output)
+        .map_err(AppError::BadRequest)?;
+    case.name = Some(request.name.unwrap_or_else(|| format!("Log {}", log_id)));
+
+    let eval = create_eval_from_case(&state.db, prompt_id, case).await?;
+    Ok(Json(eval.into()))
+}
+
+async fn create_eval_from_case(
+    db: &DbData,
+    prompt_id: i64,
+    case: EvalImportCase,
+) -> Result<PromptEval, AppError> {
+    let user_input = match case.user_prompt_input {
+        serde_json::Value::String(user) => user,
+        variables => variables.to_string(),
+    };
+    let assertions = serialize_assertions(Some(&case.assertions))?;
+    let evaluation_type = evaluation_type(case.expected_output.as_deref(), assertions.as_deref());
+
+    let eval = db.prompt_eval
+        .create(
+            prompt_id,
+            case.system_prompt_input.map(|v| v.to_string()),
+            user_input,
+            evaluation_type,
+            case.name,
+            case.expected_output,
+            assertions,
+            None,
+        )
+        .await?;
+
+    Ok(eval)
+}
+
+fn serialize_assertions(assertions: Option<&[EvalAssertion]>) -> Result<Option<String>, AppError> {
+    let Some(assertions) = assertions.filter(|a| !a.is_empty()) else {
+        return Ok(None);
+    };
+
+    validate_assertions(assertions).map_err(AppError::BadRequest)?;
+
+    let json = serde_json::to_string(assertions)
+        .map_err(|e| AppError::InternalServerError(format!("Failed to serialize assertions: {}", e)))?;
+
+    Ok(Some(json))
+}
+
+/// Validates a conversation eval's messages for a prompt. Only chat prompts take a
+/// conversation, the others always get a single user message.
+async fn serialize_conversation(
+    state: &AppState,
+    prompt_id: i64,
+    conversation: Option<&[ChatCompletionRequestMessage]>,
+) -> Result<Option<String>, AppError> {
+    let Some(conversation) = conversation else {
+        return Ok(None);
+    };
+
+    let prompt = state.db.prompt.get_prompt(prompt_id).await
+        .map_err(|_| AppError::NotFound("Prompt not found".to_string()))?;
+    if !prompt.is_chat {
+        return Err(AppError::BadRequest("Only chat prompts can have conversation evals".to_string()));
+    }
+
+    validate_conversation(conversation).map_err(AppError::BadRequest)?;
+
+    let json = serde_json::to_string(conversation)
+        .map_err(|e| AppError::InternalServerError(format!("Failed to serialize conversation: {}", e)))?;
+
+    Ok(Some(json))
+}
+
+// Anything with an expected output or assertions is scored on run instead of by hand
+fn evaluation_type(expected_output: Option<&str>, assertions: Option<&str>) -> &'static str {
+    if expected_output.is_some() || assertions.is_some() {
+        "automated"
+    } else {
+        "human"
+    }
+}
+
 pub async fn delete_eval_test(
     Path(id): Path<i64>,
     State(state): State<AppState>,
 ) -> Result<impl axum::response::IntoResponse, AppError> {
+    let existing = state.db.prompt_eval.get_by_id(id).await
+        .map_err(|_| AppError::NotFound("Eval not found".to_string()))?;
+    editable_prompt(&state.db, existing.prompt_id).await?;
+
     state.db.prompt_eval.delete(id).await?;
     Ok(StatusCode::NO_CONTENT)
 }
+
+pub async fn get_eval_judge(
+    Path(prompt_id): Path<i64>,
+    State(state): State<AppState>,
+) -> Result<Json<PromptEvalJudgeResponse>, AppError> {
+    let judge = state
+        .db
+        .prompt_eval
+        .get_judge(prompt_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("This prompt has no eval judge".to_string()))?;
+
+    Ok(Json(judge.into()))
+}
+
+pub async fn set_eval_judge(
+    Path(prompt_id): Path<i64>,
+    State(state): State<AppState>,
+    Json(request): Json<SetEvalJudgeRequest>,
+) -> Result<Json<PromptEvalJudgeResponse>, AppError> {
+    let prompt = editable_prompt(&state.db, prompt_id).await?;
+
+    if request.judge_prompt_key == prompt.key {
+        return Err(AppError::BadRequest("A prompt can't judge its own evals".to_string()));
+    }
+    if request.rubric.trim().is_empty() {
+        return Err(AppError::BadRequest("Rubric can't be empty".to_string()));
+    }
+
+    let judge_prompt = state.db.prompt.get_prompt_by_key(&request.judge_prompt_key).await
+        .map_err(|_| AppError::BadRequest(format!("Judge prompt '{}' not found", request.judge_prompt_key)))?;
+    validate_judge_prompt(&judge_prompt).map_err(AppError::BadRequest)?;
+
+    let judge = state
+        .db
+        .prompt_eval
+        .set_judge(prompt_id, &request.judge_prompt_key, &request.rubric)
+        .await?;
+
+    Ok(Json(judge.into()))
+}
+
+pub async fn delete_eval_judge(
+    Path(prompt_id): Path<i64>,
+    State(state): State<AppState>,
+) -> Result<impl axum::response::IntoResponse, AppError> {
+    editable_prompt(&state.db, prompt_id).await?;
+
+    if !state.db.prompt_eval.delete_judge(prompt_id).await? {
+        return Err(AppError::NotFound("This prompt has no eval judge".to_string()));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
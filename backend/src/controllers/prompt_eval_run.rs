@@ -3,6 +3,7 @@ use axum::{
     Json,
 };
 use serde::Deserialize;
+use std::collections::HashMap;
 use uuid::Uuid;
 
 #[derive(Deserialize)]
@@ -10,18 +11,28 @@ pub struct EvalRunParams {
     pub rounds: Option<i64>,
 }
 
+#[derive(Deserialize)]
+pub struct EvalRegressionParams {
+    pub baseline_version_id: i64,
+    pub candidate_version_id: i64,
+    /// Only compare the runs against this model
+    pub model_id: Option<i64>,
+}
+
 use super::types::{
     request::prompt_eval_run::UpdateEvalRunRequest,
     response::prompt_eval_run::{
-        PromptEvalExecutionRunResponse, PromptEvalRunResponse, PromptEvalVersionPerformanceResponse,
+        PromptEvalExecutionRunResponse, PromptEvalRunAssertionResponse, PromptEvalRunResponse,
+        PromptEvalVersionPerformanceResponse,
     },
 };
 
 use crate::{
-    common::types::chat_request::{
-        ChatCompletionRequest, ChatCompletionRequestTool, ChatCompletionRequestFunctionDescription
+    services::{
+        eval_regression::regression_report,
+        eval_runner::{load_eval_target, run_eval_case},
+        types::{eval_assertion::AssertionResult, eval_regression::EvalRegressionReport},
     },
-    services::{llm::Llm, types::llm_service::LlmServiceRequest},
     AppError, AppState,
 };
 
@@ -30,104 +41,40 @@ pub async fn execute_eval_run(
     State(state): State<AppState>,
     Query(params): Query<EvalRunParams>,
 ) -> Result<Json<serde_json::Value>, AppError> {
-    let prompt = state.db.prompt.get_prompt(prompt_id).await?;
+    let target = load_eval_target(&state.db, prompt_id, prompt_version_id)
+        .await
+        .map_err(|e| AppError::InternalServerError(e.to_string()))?
+        .ok_or_else(|| AppError::NotFound(format!("Version {} not found for this prompt", prompt_version_id)))?;
     let evals = state.db.prompt_eval.get_by_prompt(prompt_id).await?;
-    let tools_list = state.db.tool.get_tools_by_prompt_version(prompt.version_id).await?;
-    let tools = tools_list.into_iter().map(|t| {
-        ChatCompletionRequestTool::Function {
-            function: ChatCompletionRequestFunctionDescription {
-                name: t.tool_name,
-                description: Some(t.description),
-                parameters: serde_json::from_str(&t.parameters).unwrap_or_default(),
-                strict: Some(t.strict)
-            }
-        }
-    }).collect::<Vec<_>>();
 
     let rounds = params.rounds.unwrap_or(1);
     let mut all_runs: Vec<PromptEvalExecutionRunResponse> = Vec::new();
 
     for e in evals.iter() {
-        // Parse system_prompt_input if present
-        let system_content = match &e.system_prompt_input {
-            Some(system_json_str) => system_json_str.clone(),
-            None => "{}".to_string(), // Empty object if no system input
-        };
-
-        // Always use user_prompt_input
-        let user_content = e.user_prompt_input.clone();
-
-        // Create a ChatCompletionRequest with the inputs
-        let chat_request = ChatCompletionRequest {
-            model: prompt.key.clone(),
-            messages: vec![
-                crate::common::types::chat_request::ChatCompletionRequestMessage::System {
-                    content: system_content,
-                    name: None,
-                },
-                crate::common::types::chat_request::ChatCompletionRequestMessage::User {
-                    content: user_content,
-                    name: None,
-                },
-            ],
-            stream: None,
-            response_format: None,
-            tools: Some(tools.clone()),
-            provider: None,
-            models: None,
-            transforms: None,
-            max_tokens: Some(prompt.max_tokens as u32),
-            temperature: Some(prompt.temperature as f32),
-        };
-
-        let llm_props = LlmServiceRequest::new(prompt.clone(), chat_request).map_err(|e| {
-            tracing::error!("{}", e);
-            AppError::InternalServerError("An error occured processing prompt template".to_string())
-        })?;
-
-        let llm = Llm::new(llm_props, state.db.log.clone());
         let mut eval_runs = Vec::new();
+        let mut run_assertion_results: HashMap<i64, Vec<AssertionResult>> = HashMap::new();
 
         for _ in 0..rounds {
             let run_id = Uuid::new_v4().to_string();
 
-            let res = llm
-                .text()
+            let runs = run_eval_case(&state.db, &target, e, &run_id, None)
                 .await
-                .map_err(|e| {
-                    tracing::error!("LLM service error: {}", e);
-                    AppError::InternalServerError(format!("LLM service error: {}", e))
-                })?;
-
-            if let Some(c) = res.0.choices.first() {
-                if let Some(content) = &c.message.content {
-                    let eval_run = state
-                        .db
-                        .prompt_eval_run
-                        .create(&run_id, prompt_version_id, e.id, None, &content)
-                        .await?;
-
-                    eval_runs.push(eval_run);
-                }
-
-                // for not just stringify the tool calls
-                if let Some(tool_calls) = &c.message.tool_calls { 
-                    let tool_calls_string = serde_json::to_string(&tool_calls)
-                        .map_err(|e| AppError::InternalServerError(format!("Failed to serialize tool calls: {}", e)))?;
-
-                    let eval_run = state
-                        .db
-                        .prompt_eval_run
-                        .create(&run_id, prompt_version_id, e.id, None, &tool_calls_string)
-                        .await?;
-
-                    eval_runs.push(eval_run);
-                }
+                .map_err(|e| AppError::InternalServerError(e.to_string()))?;
+
+            for (eval_run, results) in runs {
+                run_assertion_results.insert(eval_run.id, results);
+                eval_runs.push(eval_run);
             }
+        }
 
+        let mut execution: PromptEvalExecutionRunResponse = eval_runs.into();
+        for run in execution.runs.iter_mut() {
+            if let Some(results) = run_assertion_results.remove(&run.id) {
+                run.assertions = results.into_iter().map(assertion_response).collect();
+            }
         }
 
-        all_runs.push(eval_runs.into());
+        all_runs.push(execution);
     }
 
     // Maintain backward compatibility: return single response when rounds=1
@@ -150,7 +97,12 @@ pub async fn get_eval_run_by_id(
     State(state): State<AppState>,
 ) -> Result<Json<PromptEvalRunResponse>, AppError> {
     let eval_run = state.db.prompt_eval_run.get_by_id(id).await?;
-    Ok(Json(eval_run.into()))
+    let assertions = state.db.prompt_eval_run.get_assertion_results(id).await?;
+
+    let mut response: PromptEvalRunResponse = eval_run.into();
+    response.assertions = assertions.into_iter().map(|a| a.into()).collect();
+
+    Ok(Json(response))
 }
 
 pub async fn get_eval_performance_by_prompt_id(
@@ -168,6 +120,32 @@ pub async fn get_eval_performance_by_prompt_id(
     Ok(Json(performance.into_iter().map(|p| p.into()).collect()))
 }
 
+/// Per-case comparison of two versions' eval results, with a sign test over the cases one
+/// version scored higher on.
+pub async fn get_eval_regression(
+    Path(prompt_id): Path<i64>,
+    State(state): State<AppState>,
+    Query(params): Query<EvalRegressionParams>,
+) -> Result<Json<EvalRegressionReport>, AppError> {
+    let versions = state.db.prompt.get_prompt_versions(prompt_id).await?;
+    for version_id in [params.baseline_version_id, params.candidate_version_id] {
+        if !versions.iter().any(|v| v.version_id == version_id) {
+            return Err(AppError::NotFound(format!("Version {} not found for this prompt", version_id)));
+        }
+    }
+
+    let report = regression_report(
+        &state.db,
+        prompt_id,
+        params.baseline_version_id,
+        params.candidate_version_id,
+        params.model_id,
+    )
+    .await?;
+
+    Ok(Json(report))
+}
+
 pub async fn get_eval_runs_by_prompt_version(
     Path((_prompt_id, prompt_version_id)): Path<(i64, i64)>,
     State(state): State<AppState>,
@@ -177,7 +155,26 @@ pub async fn get_eval_runs_by_prompt_version(
         .prompt_eval_run
         .get_by_prompt_version(prompt_version_id)
         .await?;
-    Ok(Json(eval_runs.into_iter().map(|run| run.into()).collect()))
+
+    let mut responses = Vec::new();
+    for run in eval_runs {
+        let assertions = state.db.prompt_eval_run.get_assertion_results(run.id).await?;
+
+        let mut response: PromptEvalRunResponse = run.into();
+        response.assertions = assertions.into_iter().map(|a| a.into()).collect();
+
+        responses.push(response);
+    }
+
+    Ok(Json(responses))
+}
+
+fn assertion_response(result: AssertionResult) -> PromptEvalRunAssertionResponse {
+    PromptEvalRunAssertionResponse {
+        assertion: serde_json::to_value(result.assertion).unwrap_or_default(),
+        passed: result.passed,
+        message: result.message,
+    }
 }
 
 pub async fn update_eval_run_score(
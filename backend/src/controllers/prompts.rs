@@ -1,33 +1,49 @@
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
+    Extension,
+    http::{Extensions, HeaderMap},
     response::{sse::{Event, KeepAlive, Sse}, IntoResponse, Response},
     Json,
 };
 use futures::Stream;
-use std::{convert::Infallible, pin::Pin};
+use serde::Deserialize;
+use std::{convert::Infallible, pin::Pin, time::Instant};
 use tokio::sync::mpsc;
+use tracing::{field::Empty, Instrument, Span};
 
 use crate::{
     common::types::{chat_request::{
-        ChatCompletionRequest, ChatCompletionRequestFunctionDescription, ChatCompletionRequestTool
-    }, chat_response::LlmServiceChatCompletionResponse}, 
+        ChatCompletionRequest, ChatCompletionRequestFunctionDescription, ChatCompletionRequestMessage,
+        ChatCompletionRequestTool, ClientMetadata
+    }, chat_response::{LlmServiceChatCompletionChunk, LlmServiceChatCompletionResponse}}, 
+    db::{init::DbData, logs::LogRepository, types::{prompt::PromptRowWithModel, snippet::SnippetRow}},
     services::{
         llm::Llm,
-        types::llm_service::LlmServiceRequest,
+        metrics,
+        prompt_diff::diff_versions,
+        telemetry,
+        templates::{load_snippet_dependencies, prompt_templates},
+        types::{llm_error::LlmStreamingError, llm_service::LlmServiceRequest, prompt_diff::PromptVersionDiff},
     }, 
+    middleware::auth::{ApiKeyId, UserId},
     AppError, 
     AppState
 };
 
 use super::types::{
-    request::prompts::{CreatePromptRequest, UpdatePromptRequest},
-    response::prompts::PromptResponse,
+    request::prompts::{
+        ComparePromptModelsRequest, CreatePromptRequest, SetPromptApprovalRequest, UpdatePromptRequest
+    },
+    response::prompts::{PromptModelComparisonResponse, PromptResponse},
 };
 
 pub async fn create_prompt(
     State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
     Json(payload): Json<CreatePromptRequest>,
 ) -> Result<Json<PromptResponse>, AppError> {
+    let messages = serialize_message_templates(payload.messages.as_deref())?;
+
     let id = state
         .db
         .prompt
@@ -43,6 +59,9 @@ pub async fn create_prompt(
             &payload.prompt_type,
             payload.is_chat,
             payload.reasoning_effort.as_deref(),
+            messages.as_deref(),
+            payload.change_message.as_deref(),
+            Some(user_id.0),
         )
         .await?;
     let prompt = state
@@ -71,7 +90,10 @@ pub async fn get_prompt(
     Path(id): Path<i64>,
     State(state): State<AppState>,
 ) -> Result<Json<PromptResponse>, AppError> {
-    let prompt = match state.prompt_cache.get(&id).await {
+    let cached = state.prompt_cache.get(&id).await;
+    metrics::record_prompt_cache_lookup(cached.is_some());
+
+    let prompt = match cached {
         Some(p) => p,
         None => {
             let prompt = state.db.prompt.get_prompt(id).await?;
@@ -117,20 +139,32 @@ pub async fn list_prompts(
 pub async fn update_prompt(
     Path(id): Path<i64>,
     State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
     Json(payload): Json<UpdatePromptRequest>,
 ) -> Result<Json<PromptResponse>, AppError> {
+    let messages = serialize_message_templates(payload.messages.as_deref())?;
+
     // Get the current prompt to access its version ID before update
     let current_prompt = state
         .db
         .prompt
         .get_prompt(id)
         .await?;
+    ensure_editable(&current_prompt)?;
+
+    // With the approval gate on, the new version is a draft and the active version stays as-is
+    let draft = current_prompt.requires_approval;
+    if draft && payload.key != current_prompt.key {
+        return Err(AppError::BadRequest(
+            "The key can't be changed while the prompt requires approval".into(),
+        ));
+    }
     
     // Get the tools associated with the current prompt version
     let current_tools = state.db.tool.get_tools_by_prompt_version(current_prompt.version_id).await?;
     
     // Update the prompt, which creates a new version
-    let updated = state
+    let version_id = state
         .db
         .prompt
         .update_prompt(
@@ -146,31 +180,44 @@ pub async fn update_prompt(
             &payload.prompt_type,
             payload.is_chat,
             payload.reasoning_effort.as_deref(),
+            messages.as_deref(),
+            payload.change_message.as_deref(),
+            Some(user_id.0),
+            draft,
         )
-        .await?;
-
-    if !updated {
-        return Err(AppError::NotFound("Prompt not found".into()));
-    }
-
-    // Get the updated prompt with its new version ID
-    let prompt = state
-        .db
-        .prompt
-        .get_prompt(id)
-        .await
-        .map_err(|_| AppError::NotFound("Prompt not found after update".into()))?;
-
-    // Add to cache
-    state.prompt_cache.insert(id, prompt.clone()).await;
+        .await?
+        .ok_or_else(|| AppError::NotFound("Prompt not found".into()))?;
 
     // Copy tool associations from the previous version to the new version
     for tool in current_tools {
-        state.db.tool.associate_tool_with_prompt_version(tool.id, prompt.version_id).await?;
+        state.db.tool.associate_tool_with_prompt_version(tool.id, version_id).await?;
     }
 
+    let prompt = if draft {
+        state
+            .db
+            .prompt
+            .get_prompt_versions(id)
+            .await?
+            .into_iter()
+            .find(|v| v.version_id == version_id)
+            .ok_or_else(|| AppError::NotFound("Prompt version not found after update".into()))?
+    } else {
+        // Get the updated prompt with its new version ID
+        let prompt = state
+            .db
+            .prompt
+            .get_prompt(id)
+            .await
+            .map_err(|_| AppError::NotFound("Prompt not found after update".into()))?;
+
+        // Add to cache
+        state.prompt_cache.insert(id, prompt.clone()).await;
+        prompt
+    };
+
     // Fetch associated tools for the new version
-    let tools = state.db.tool.get_tools_by_prompt_version(prompt.version_id).await?;
+    let tools = state.db.tool.get_tools_by_prompt_version(version_id).await?;
     
     // Convert prompt to PromptResponse
     let mut response: PromptResponse = prompt.into();
@@ -190,6 +237,7 @@ pub async fn delete_prompt(
         Ok(p) => p,
         Err(_) => return Err(AppError::NotFound("Prompt not found".into()))
     };
+    ensure_editable(&prompt)?;
     
     tracing::info!("Deleting prompt ID: {}, version ID: {}", id, prompt.version_id);
     
@@ -230,10 +278,60 @@ pub async fn get_prompt_versions(
     Ok(Json(responses))
 }
 
+/// Both are prompt version ids, `from` is usually the older one.
+#[derive(Deserialize)]
+pub struct VersionDiffParams {
+    from: i64,
+    to: i64,
+}
+
+pub async fn get_prompt_version_diff(
+    Path(id): Path<i64>,
+    Query(params): Query<VersionDiffParams>,
+    State(state): State<AppState>,
+) -> Result<Json<PromptVersionDiff>, AppError> {
+    let versions = state.db.prompt.get_prompt_versions(id).await?;
+
+    let find_version = |version_id: i64| {
+        versions
+            .iter()
+            .find(|v| v.version_id == version_id)
+            .ok_or_else(|| AppError::NotFound(format!("Version {} not found for this prompt", version_id)))
+    };
+    let from = find_version(params.from)?;
+    let to = find_version(params.to)?;
+
+    let from_tools = state.db.tool.get_tools_by_prompt_version(from.version_id).await?;
+    let to_tools = state.db.tool.get_tools_by_prompt_version(to.version_id).await?;
+
+    Ok(Json(diff_versions(from, &from_tools, to, &to_tools)))
+}
+
 pub async fn set_active_version(
     Path((prompt_id, version_id)): Path<(i64, i64)>,
     State(state): State<AppState>,
 ) -> Result<Json<PromptResponse>, AppError> {
+    let current_prompt = state
+        .db
+        .prompt
+        .get_prompt(prompt_id)
+        .await
+        .map_err(|_| AppError::NotFound("Prompt not found".into()))?;
+    ensure_editable(&current_prompt)?;
+
+    let is_draft = state
+        .db
+        .prompt
+        .get_prompt_versions(prompt_id)
+        .await?
+        .iter()
+        .any(|v| v.version_id == version_id && v.is_draft);
+    if is_draft {
+        return Err(AppError::Forbidden(
+            "Version is a draft and has to be approved before it can be made active".into(),
+        ));
+    }
+
     // Set the active version in the database
     let prompt = state.db.prompt.set_active_prompt_version(prompt_id, version_id).await?;
     
@@ -252,11 +350,198 @@ pub async fn set_active_version(
     Ok(Json(response))
 }
 
+pub async fn approve_prompt_version(
+    Path((prompt_id, version_id)): Path<(i64, i64)>,
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+) -> Result<Json<PromptResponse>, AppError> {
+    let version = state
+        .db
+        .prompt
+        .get_prompt_versions(prompt_id)
+        .await?
+        .into_iter()
+        .find(|v| v.version_id == version_id)
+        .ok_or_else(|| AppError::NotFound("Prompt version not found".into()))?;
+    ensure_editable(&version)?;
+
+    if !version.is_draft {
+        return Err(AppError::Conflict("Version is not a draft".into()));
+    }
+
+    // The point of the gate is a second pair of eyes
+    if version.created_by == Some(user_id.0) {
+        return Err(AppError::Forbidden(
+            "A version has to be approved by someone other than its author".into(),
+        ));
+    }
+
+    if !state.db.prompt.approve_prompt_version(prompt_id, version_id, user_id.0).await? {
+        return Err(AppError::Conflict("Version is not a draft".into()));
+    }
+
+    let version = state
+        .db
+        .prompt
+        .get_prompt_versions(prompt_id)
+        .await?
+        .into_iter()
+        .find(|v| v.version_id == version_id)
+        .ok_or_else(|| AppError::NotFound("Prompt version not found after approval".into()))?;
+
+    let tools = state.db.tool.get_tools_by_prompt_version(version_id).await?;
+    let mut response: PromptResponse = version.into();
+    response.tools = tools.into_iter().map(|t| t.into()).collect();
+
+    Ok(Json(response))
+}
+
+/// Turns the approval gate on or off. Only whoever turned it on can turn it off, otherwise anyone
+/// could switch it off, publish a version and switch it back on.
+pub async fn set_prompt_requires_approval(
+    Path(id): Path<i64>,
+    State(state): State<AppState>,
+    Extension(user_id): Extension<UserId>,
+    Json(payload): Json<SetPromptApprovalRequest>,
+) -> Result<Json<PromptResponse>, AppError> {
+    let current_prompt = state
+        .db
+        .prompt
+        .get_prompt(id)
+        .await
+        .map_err(|_| AppError::NotFound("Prompt not found".into()))?;
+    ensure_editable(&current_prompt)?;
+
+    if current_prompt.requires_approval && !payload.requires_approval {
+        let owner = state.db.prompt.get_approval_required_by(id).await?;
+        if owner.is_some_and(|owner| owner != user_id.0) {
+            return Err(AppError::Forbidden(
+                "Approval can only be turned off by the user who turned it on".into(),
+            ));
+        }
+
+        // Otherwise the author of a draft could turn the gate off and publish it themselves
+        let has_drafts = state
+            .db
+            .prompt
+            .get_prompt_versions(id)
+            .await?
+            .iter()
+            .any(|v| v.is_draft);
+        if has_drafts {
+            return Err(AppError::Conflict(
+                "Approval can't be turned off while a draft is waiting for approval".into(),
+            ));
+        }
+    }
+
+    // Turning it on again mustn't hand the gate to someone else
+    if current_prompt.requires_approval != payload.requires_approval {
+        state
+            .db
+            .prompt
+            .set_prompt_requires_approval(id, payload.requires_approval, user_id.0)
+            .await?;
+    }
+
+    let prompt = state.db.prompt.get_prompt(id).await?;
+    state.prompt_cache.insert(id, prompt.clone()).await;
+
+    let tools = state.db.tool.get_tools_by_prompt_version(prompt.version_id).await?;
+    let mut response: PromptResponse = prompt.into();
+    response.tools = tools.into_iter().map(|t| t.into()).collect();
+
+    Ok(Json(response))
+}
+
 // OpenAI compatible API endpoints
 #[axum::debug_handler]
 pub async fn api_completions(
     State(state): State<AppState>,
-    Json(payload): Json<ChatCompletionRequest>,
+    headers: HeaderMap,
+    // Carries the `ApiKeyId` on the public API, UI requests run without one
+    extensions: Extensions,
+    Json(mut payload): Json<ChatCompletionRequest>,
+) -> Result<CompletionResponse, AppError> {
+    payload.llmkit = client_metadata(&headers, payload.llmkit.take())?;
+
+    let span = tracing::info_span!(
+        "chat_completions",
+        otel.name = %format!("chat_completions {}", payload.model),
+        otel.kind = "server",
+        gen_ai.operation.name = "chat",
+        gen_ai.request.model = Empty,
+        gen_ai.response.id = Empty,
+        gen_ai.response.model = Empty,
+        gen_ai.response.finish_reasons = Empty,
+        gen_ai.usage.input_tokens = Empty,
+        gen_ai.usage.output_tokens = Empty,
+        llmkit.prompt.key = %payload.model,
+        llmkit.stream = Empty,
+        session.id = Empty,
+    );
+    if let Some(session_id) = payload.llmkit.as_ref().and_then(|m| m.session_id.as_deref()) {
+        span.record("session.id", session_id);
+    }
+    telemetry::continue_trace(&span, &headers);
+
+    complete(state, extensions, payload).instrument(span).await
+}
+
+const MAX_METADATA_VALUE_LEN: usize = 256;
+const MAX_TAGS: usize = 32;
+
+/// Client metadata from the `llmkit` field of the request body, filled in from the
+/// `X-LLMKit-User-Id`, `X-LLMKit-Session-Id` and comma separated `X-LLMKit-Tags` headers.
+/// Values in the body win over headers, tags from both are kept.
+fn client_metadata(
+    headers: &HeaderMap,
+    body: Option<ClientMetadata>,
+) -> Result<Option<ClientMetadata>, AppError> {
+    let header = |name: &str| -> Result<Option<String>, AppError> {
+        headers
+            .get(name)
+            .map(|v| {
+                v.to_str()
+                    .map(|v| v.trim().to_string())
+                    .map_err(|_| AppError::BadRequest(format!("`{}` must be visible ASCII", name)))
+            })
+            .transpose()
+            .map(|v| v.filter(|v| !v.is_empty()))
+    };
+
+    let mut metadata = body.unwrap_or_default();
+    metadata.user_id = metadata.user_id.or(header("x-llmkit-user-id")?);
+    metadata.session_id = metadata.session_id.or(header("x-llmkit-session-id")?);
+    if let Some(tags) = header("x-llmkit-tags")? {
+        metadata.tags.extend(tags.split(',').map(|t| t.trim().to_string()));
+    }
+    metadata.tags.retain(|t| !t.is_empty());
+    metadata.tags.sort();
+    metadata.tags.dedup();
+
+    let too_long = [&metadata.user_id, &metadata.session_id]
+        .into_iter()
+        .flatten()
+        .chain(&metadata.tags)
+        .any(|v| v.len() > MAX_METADATA_VALUE_LEN);
+    if too_long {
+        return Err(AppError::BadRequest(format!(
+            "Metadata values can be at most {} characters",
+            MAX_METADATA_VALUE_LEN
+        )));
+    }
+    if metadata.tags.len() > MAX_TAGS {
+        return Err(AppError::BadRequest(format!("At most {} tags are allowed", MAX_TAGS)));
+    }
+
+    Ok((metadata != ClientMetadata::default()).then_some(metadata))
+}
+
+async fn complete(
+    state: AppState,
+    extensions: Extensions,
+    payload: ChatCompletionRequest,
 ) -> Result<CompletionResponse, AppError> {
     if payload.messages.is_empty() {
         return Err(AppError::BadRequest(
@@ -291,7 +576,17 @@ pub async fn api_completions(
     let mut payload = payload.clone();
     payload.tools = Some(tools);
 
+    // Shared snippets the prompt templates include
+    let templates = prompt_templates(&prompt.system, prompt.user.as_deref(), prompt.messages.as_deref());
+    let snippets = load_snippet_dependencies(&templates, &state.db.snippet).await?;
+
     let is_stream = payload.stream.unwrap_or(false);
+    Span::current()
+        .record("gen_ai.request.model", prompt.model_name.as_str())
+        .record("llmkit.stream", is_stream);
+
+    // Completions always read the prompt fresh, the lookup only counts towards the hit rate
+    metrics::record_prompt_cache_lookup(state.prompt_cache.contains_key(&prompt.id));
 
     // Insert into cache
     state.prompt_cache.insert(prompt.id, prompt.clone()).await;
@@ -302,18 +597,17 @@ pub async fn api_completions(
         payload.stream = Some(true);
         
         // Use our unified new() method
-        let llm_props = LlmServiceRequest::new(prompt, payload)
+        let mut llm_props = LlmServiceRequest::new_with_snippets(prompt, payload, &snippets)
             .map_err(|e| {
                 tracing::error!("Error creating LlmServiceRequest: {}", e);
                 AppError::InternalServerError("Failed to process request".into())
             })?;
+        llm_props.api_key_id = extensions.get::<ApiKeyId>().map(|ApiKeyId(id)| *id);
 
         let (tx, mut rx) = mpsc::channel(100);
         let llm = Llm::new(llm_props, state.db.log);
 
-        tokio::spawn(async move {
-            let _ = llm.stream(tx).await;
-        });
+        tokio::spawn(stream_completion(llm, tx).in_current_span());
 
         let stream: SseStream = Box::pin(async_stream::stream! {
             // Process regular stream messages
@@ -340,11 +634,12 @@ pub async fn api_completions(
     } else {
         // Handle non-streaming request
         // Create LlmServiceRequest with our new unified method
-        let llm_props = LlmServiceRequest::new(prompt, payload)
+        let mut llm_props = LlmServiceRequest::new_with_snippets(prompt, payload, &snippets)
             .map_err(|e| {
                 tracing::error!("Error creating LlmServiceRequest: {}", e);
                 AppError::InternalServerError("Failed to process request".into())
             })?;
+        llm_props.api_key_id = extensions.get::<ApiKeyId>().map(|ApiKeyId(id)| *id);
 
         let llm = Llm::new(llm_props.clone(), state.db.log.clone());
 
@@ -361,11 +656,239 @@ pub async fn api_completions(
                 return error;
             })?
         };
+        telemetry::record_response(&Span::current(), &res.0);
 
         Ok(CompletionResponse::Json(Json(res.0)))
     }
 }
 
+/// Streams a completion into `tx`. The request's span outlives the handler, so it's tagged with
+/// the response once the stream finishes, same as a non-streaming completion.
+async fn stream_completion(
+    llm: Llm,
+    tx: mpsc::Sender<Result<LlmServiceChatCompletionChunk, LlmStreamingError>>,
+) {
+    if let Ok((res, _)) = llm.stream(tx).await {
+        telemetry::record_response(&Span::current(), &res);
+    }
+}
+
+/// Runs the active version of a prompt against several models at once and streams each
+/// model's result as an SSE `result` event as soon as it finishes. Every run is logged.
+pub async fn compare_prompt_models(
+    Path(id): Path<i64>,
+    State(state): State<AppState>,
+    Json(payload): Json<ComparePromptModelsRequest>,
+) -> Result<Sse<SseStream>, AppError> {
+    if payload.model_ids.is_empty() {
+        return Err(AppError::BadRequest("At least one model is required".into()));
+    }
+
+    if payload.messages.is_empty() {
+        return Err(AppError::BadRequest(
+            "Messages array cannot be empty".into(),
+        ));
+    }
+
+    let prompt = state
+        .db
+        .prompt
+        .get_prompt(id)
+        .await
+        .map_err(|_| AppError::NotFound("Prompt not found".into()))?;
+
+    let tools_list = state.db.tool.get_tools_by_prompt_version(prompt.version_id).await?;
+    let tools = tools_list.into_iter().map(|t| {
+        ChatCompletionRequestTool::Function {
+            function: ChatCompletionRequestFunctionDescription {
+                name: t.tool_name,
+                description: Some(t.description),
+                parameters: serde_json::from_str(&t.parameters).unwrap_or_default(),
+                strict: Some(t.strict)
+            }
+        }
+    }).collect::<Vec<_>>();
+
+    let templates = prompt_templates(&prompt.system, prompt.user.as_deref(), prompt.messages.as_deref());
+    let snippets = load_snippet_dependencies(&templates, &state.db.snippet).await?;
+
+    // Resolve every model up front so a bad id fails the request instead of the stream
+    let mut models = Vec::new();
+    for model_id in payload.model_ids {
+        let model = state
+            .db
+            .model
+            .get_model_by_id(model_id)
+            .await?
+            .ok_or_else(|| AppError::NotFound(format!("Model {} not found", model_id)))?;
+        models.push(model);
+    }
+
+    let (tx, mut rx) = mpsc::channel(models.len());
+
+    for model in models {
+        let tx = tx.clone();
+        let db_log = state.db.log.clone();
+        let prompt = prompt.clone().with_model(model);
+        let snippets = snippets.clone();
+        let request = ChatCompletionRequest {
+            model: prompt.key.clone(),
+            messages: payload.messages.clone(),
+            stream: None,
+            response_format: None,
+            tools: Some(tools.clone()),
+            provider: None,
+            models: None,
+            transforms: None,
+            max_tokens: None,
+            temperature: None,
+            llmkit: None,
+        };
+
+        tokio::spawn(async move {
+            let result = run_model_comparison(prompt, request, &snippets, db_log).await;
+            let _ = tx.send(result).await;
+        });
+    }
+
+    // Drop our sender so the stream ends once every model has reported back
+    drop(tx);
+
+    let stream: SseStream = Box::pin(async_stream::stream! {
+        while let Some(result) = rx.recv().await {
+            yield Ok(Event::default()
+                .event("result")
+                .data(serde_json::to_string(&result).expect("Failed to turn comparison result into string")));
+        }
+
+        yield Ok(Event::default().event("done").data("[DONE]"));
+    });
+
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
+async fn run_model_comparison(
+    prompt: PromptRowWithModel,
+    request: ChatCompletionRequest,
+    snippets: &[SnippetRow],
+    db_log: LogRepository,
+) -> PromptModelComparisonResponse {
+    let mut response = PromptModelComparisonResponse::from(&prompt);
+
+    let llm_props = match LlmServiceRequest::new_with_snippets(prompt, request, snippets) {
+        Ok(p) => p,
+        Err(e) => {
+            tracing::error!("Error creating LlmServiceRequest: {}", e);
+            response.error = Some(e.to_string());
+            return response;
+        }
+    };
+
+    // Not every model supports JSON mode, so go off what the request ended up with
+    let json_mode = llm_props.request.response_format.is_some();
+    let llm = Llm::new(llm_props, db_log.clone());
+
+    let start = Instant::now();
+    let result = if json_mode {
+        llm.json().await
+    } else {
+        llm.text().await
+    };
+    response.latency_ms = start.elapsed().as_millis() as i64;
+
+    match result {
+        Ok((res, log_id)) => {
+            response.log_id = Some(log_id);
+
+            if let Some(usage) = &res.usage {
+                response.input_tokens = Some(usage.prompt_tokens as i64);
+                response.output_tokens = Some(usage.completion_tokens as i64);
+                response.reasoning_tokens = usage
+                    .completion_tokens_details
+                    .as_ref()
+                    .and_then(|details| details.reasoning_tokens)
+                    .map(|tokens| tokens as i64);
+            }
+
+            // The cost was worked out when the call was logged
+            match db_log.get_log_by_id(log_id).await {
+                Ok(log) => response.cost = log.and_then(|l| l.cost),
+                Err(e) => tracing::error!("Failed to fetch log {} for its cost: {}", log_id, e),
+            }
+
+            if let Some(c) = res.choices.first() {
+                response.output = c.message.content.clone();
+                response.tool_calls = c
+                    .message
+                    .tool_calls
+                    .as_ref()
+                    .and_then(|tc| serde_json::to_string(tc).ok());
+            }
+        }
+        Err(e) => {
+            tracing::error!("Comparison run failed for model {}: {}", response.model, e);
+            response.error = Some(e.to_string());
+        }
+    }
+
+    response
+}
+
+/// Validates the optional message template list and turns it into the JSON stored on the version.
+fn serialize_message_templates(
+    messages: Option<&[ChatCompletionRequestMessage]>,
+) -> Result<Option<String>, AppError> {
+    let Some(messages) = messages else {
+        return Ok(None);
+    };
+
+    if messages.is_empty() {
+        return Err(AppError::BadRequest(
+            "Message templates cannot be empty, omit them to use the system/user templates".into(),
+        ));
+    }
+
+    let json = serde_json::to_string(messages)
+        .map_err(|e| AppError::InternalServerError(format!("Failed to serialize message templates: {}", e)))?;
+
+    Ok(Some(json))
+}
+
+/// Prompts synced from a prompts directory can only be changed by editing their files.
+fn ensure_editable(prompt: &PromptRowWithModel) -> Result<(), AppError> {
+    if prompt.read_only {
+        return Err(AppError::Forbidden(format!(
+            "Prompt '{}' is managed from the prompts directory and is read-only",
+            prompt.key
+        )));
+    }
+
+    Ok(())
+}
+
+/// Loads a prompt whose tools or evals are about to change, refusing read-only prompts.
+pub(crate) async fn editable_prompt(db: &DbData, prompt_id: i64) -> Result<PromptRowWithModel, AppError> {
+    let prompt = db
+        .prompt
+        .get_prompt(prompt_id)
+        .await
+        .map_err(|_| AppError::NotFound("Prompt not found".into()))?;
+    ensure_editable(&prompt)?;
+
+    Ok(prompt)
+}
+
+/// Same as [`editable_prompt`], for changes addressed to one of the prompt's versions.
+pub(crate) async fn editable_prompt_version(db: &DbData, version_id: i64) -> Result<PromptRowWithModel, AppError> {
+    let prompt_id = db
+        .prompt
+        .get_prompt_id_by_version(version_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Prompt version not found".into()))?;
+
+    editable_prompt(db, prompt_id).await
+}
+
 type SseStream = Pin<Box<dyn Stream<Item = Result<Event, Infallible>> + Send>>;
 
 pub enum CompletionResponse {
@@ -381,3 +904,253 @@ impl IntoResponse for CompletionResponse {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        sync::{Arc, Mutex},
+    };
+
+    use metrics_exporter_prometheus::PrometheusBuilder;
+    use moka::future::Cache;
+    use serde_json::json;
+    use tracing::{
+        field::{Field, Visit},
+        span::{Id, Record},
+        Subscriber,
+    };
+    use tracing_subscriber::{
+        layer::{Context, SubscriberExt},
+        registry::LookupSpan,
+        Layer,
+    };
+
+    use crate::{
+        common::types::models::LlmApiProvider,
+        services::{eval_jobs::EvalJobs, types::llm_service::TemplateVariables},
+    };
+
+    use super::*;
+
+    async fn test_state() -> AppState {
+        AppState {
+            db: DbData::in_memory().await.unwrap(),
+            prompt_cache: Cache::new(10),
+            jwt_secret: "secret".to_string(),
+            eval_jobs: EvalJobs::new(1),
+            metrics: PrometheusBuilder::new().build_recorder().handle(),
+            metrics_token: None,
+        }
+    }
+
+    async fn create_user(state: &AppState, email: &str) -> UserId {
+        let id = sqlx::query_scalar(
+            "INSERT INTO user (name, email, password_hash) VALUES ('Test', ?, 'hash') RETURNING id",
+        )
+        .bind(email)
+        .fetch_one(state.db.pool())
+        .await
+        .unwrap();
+        UserId(id)
+    }
+
+    fn edit(prompt: &PromptRowWithModel, system: &str) -> UpdatePromptRequest {
+        UpdatePromptRequest {
+            system: system.to_string(),
+            user: prompt.user.clone().unwrap_or_default(),
+            key: prompt.key.clone(),
+            model_id: prompt.model_id,
+            max_tokens: prompt.max_tokens,
+            temperature: prompt.temperature,
+            json_mode: prompt.json_mode,
+            json_schema: prompt.json_schema.clone(),
+            prompt_type: prompt.prompt_type.clone(),
+            is_chat: prompt.is_chat,
+            reasoning_effort: prompt.reasoning_effort.clone(),
+            messages: None,
+            change_message: None,
+        }
+    }
+
+    async fn set_approval(
+        state: &AppState,
+        user: &UserId,
+        id: i64,
+        requires_approval: bool,
+    ) -> Result<Json<PromptResponse>, AppError> {
+        set_prompt_requires_approval(
+            Path(id),
+            State(state.clone()),
+            Extension(user.clone()),
+            Json(SetPromptApprovalRequest { requires_approval }),
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_drafts_need_someone_else_to_approve() {
+        let state = test_state().await;
+        let author = create_user(&state, "author@test.com").await;
+        let reviewer = create_user(&state, "reviewer@test.com").await;
+        let prompt = state.db.prompt.list_prompts().await.unwrap().remove(0);
+
+        assert!(set_approval(&state, &reviewer, prompt.id, true).await.unwrap().requires_approval);
+        let Json(draft) = update_prompt(
+            Path(prompt.id),
+            State(state.clone()),
+            Extension(author.clone()),
+            Json(edit(&prompt, "Drafted")),
+        )
+        .await
+        .unwrap();
+        assert!(draft.is_draft);
+        assert_eq!(state.db.prompt.get_prompt(prompt.id).await.unwrap().version_id, prompt.version_id);
+
+        // The author can neither approve nor turn the gate off to publish
+        let approve = |user: UserId| {
+            approve_prompt_version(Path((prompt.id, draft.version_id)), State(state.clone()), Extension(user))
+        };
+        assert!(matches!(approve(author.clone()).await, Err(AppError::Forbidden(_))));
+        assert!(matches!(set_approval(&state, &author, prompt.id, false).await, Err(AppError::Forbidden(_))));
+        assert!(matches!(set_approval(&state, &reviewer, prompt.id, false).await, Err(AppError::Conflict(_))));
+        assert!(matches!(
+            set_active_version(Path((prompt.id, draft.version_id)), State(state.clone())).await,
+            Err(AppError::Forbidden(_))
+        ));
+
+        let Json(approved) = approve(reviewer.clone()).await.unwrap();
+        assert!(!approved.is_draft);
+        let Json(active) = set_active_version(Path((prompt.id, draft.version_id)), State(state.clone()))
+            .await
+            .unwrap();
+        assert_eq!(active.system, "Drafted");
+
+        // Nothing is waiting now, but the gate is still the reviewer's to turn off
+        assert!(set_approval(&state, &author, prompt.id, true).await.unwrap().requires_approval);
+        assert!(matches!(set_approval(&state, &author, prompt.id, false).await, Err(AppError::Forbidden(_))));
+        assert!(!set_approval(&state, &reviewer, prompt.id, false).await.unwrap().requires_approval);
+    }
+
+    #[tokio::test]
+    async fn test_read_only_prompts_reject_edits() {
+        let db = DbData::in_memory().await.unwrap();
+        let prompt = db.prompt.list_prompts().await.unwrap().remove(0);
+
+        assert!(editable_prompt(&db, prompt.id).await.is_ok());
+        assert!(editable_prompt_version(&db, prompt.version_id).await.is_ok());
+
+        db.prompt.set_prompt_read_only(prompt.id, true).await.unwrap();
+        assert!(matches!(editable_prompt(&db, prompt.id).await, Err(AppError::Forbidden(_))));
+        assert!(matches!(
+            editable_prompt_version(&db, prompt.version_id).await,
+            Err(AppError::Forbidden(_))
+        ));
+
+        assert!(matches!(editable_prompt_version(&db, -1).await, Err(AppError::NotFound(_))));
+    }
+
+    #[derive(Clone, Default)]
+    struct CompletionSpanFields(Arc<Mutex<HashMap<String, String>>>);
+
+    impl<S> Layer<S> for CompletionSpanFields
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+    {
+        fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+            // The attempt's own span records the same fields, only the request's span counts here
+            if ctx.span(id).is_some_and(|span| span.name() == "chat_completions") {
+                values.record(&mut FieldVisitor(&mut self.0.lock().unwrap()));
+            }
+        }
+    }
+
+    struct FieldVisitor<'a>(&'a mut HashMap<String, String>);
+
+    impl Visit for FieldVisitor<'_> {
+        fn record_str(&mut self, field: &Field, value: &str) {
+            self.0.insert(field.name().to_string(), value.to_string());
+        }
+
+        fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+            self.0.insert(field.name().to_string(), format!("{:?}", value));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_streamed_completion_tags_the_request_span() {
+        let chunks = [
+            json!({ "id": "chatcmpl-1", "object": "chat.completion.chunk", "created": 1, "model": "gpt-4o",
+                "choices": [{ "index": 0, "delta": { "role": "assistant", "content": "Hello" }, "finish_reason": null }] }),
+            json!({ "id": "chatcmpl-1", "object": "chat.completion.chunk", "created": 1, "model": "gpt-4o",
+                "choices": [], "usage": { "prompt_tokens": 12, "completion_tokens": 3, "total_tokens": 15 } }),
+        ];
+        let body = chunks
+            .iter()
+            .map(|chunk| format!("data: {}\n\n", chunk))
+            .chain(["data: [DONE]\n\n".to_string()])
+            .collect::<String>();
+        let app = axum::Router::new().route(
+            "/openai/deployments/{deployment}/chat/completions",
+            axum::routing::post(move || async move {
+                ([(axum::http::header::CONTENT_TYPE, "text/event-stream")], body)
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let db = DbData::in_memory().await.unwrap();
+        let prompt = db.prompt.list_prompts().await.unwrap().remove(0);
+        let props = LlmServiceRequest {
+            provider: LlmApiProvider::Azure,
+            base_url: Some(base_url),
+            prompt_id: prompt.id,
+            prompt_version_id: Some(prompt.version_id),
+            model_id: prompt.model_id,
+            is_reasoning: false,
+            reasoning_effort: None,
+            request: serde_json::from_value(json!({
+                "model": "gpt-4o|2024-10-21",
+                "messages": [{ "role": "user", "content": "Hi" }],
+                "stream": true
+            }))
+            .unwrap(),
+            template_variables: TemplateVariables { system: json!({}), user: None },
+            api_key_id: None,
+            prompt_key: prompt.key.clone(),
+            provider_api_key: Some("test".to_string()),
+        };
+
+        let fields = CompletionSpanFields::default();
+        let _subscriber = tracing::subscriber::set_default(tracing_subscriber::registry().with(fields.clone()));
+        let span = tracing::info_span!(
+            "chat_completions",
+            gen_ai.response.id = Empty,
+            gen_ai.response.model = Empty,
+            gen_ai.response.finish_reasons = Empty,
+            gen_ai.usage.input_tokens = Empty,
+            gen_ai.usage.output_tokens = Empty,
+        );
+
+        let (tx, mut rx) = mpsc::channel(100);
+        stream_completion(Llm::new(props, db.log.clone()), tx).instrument(span).await;
+
+        let mut content = String::new();
+        while let Ok(Ok(chunk)) = rx.try_recv() {
+            if chunk.is_done_sentinel() {
+                break;
+            }
+            if let Some(delta) = chunk.choices.first().and_then(|c| c.delta.content.as_deref()) {
+                content.push_str(delta);
+            }
+        }
+        assert_eq!(content, "Hello");
+
+        let fields = fields.0.lock().unwrap();
+        assert_eq!(fields.get("gen_ai.response.id").map(String::as_str), Some("chatcmpl-1"));
+        assert_eq!(fields.get("gen_ai.response.model").map(String::as_str), Some("gpt-4o|2024-10-21"));
+        assert_eq!(fields.get("gen_ai.usage.input_tokens").map(String::as_str), Some("12"));
+        assert_eq!(fields.get("gen_ai.usage.output_tokens").map(String::as_str), Some("3"));
+    }
+}
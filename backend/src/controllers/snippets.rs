@@ -0,0 +1,275 @@
+use axum::{
+    extract::{Path, State},
+    Json,
+};
+
+use crate::{
+    db::types::snippet::SnippetRow,
+    services::templates::{
+        build_tera, is_reserved_template_name, prompt_templates, resolve_snippet_dependencies,
+    },
+    AppError, AppState,
+};
+
+use super::types::{
+    request::snippets::{CreateSnippetRequest, UpdateSnippetRequest},
+    response::snippets::{SnippetDependentsResponse, SnippetResponse},
+};
+
+pub async fn create_snippet(
+    State(state): State<AppState>,
+    Json(payload): Json<CreateSnippetRequest>,
+) -> Result<Json<SnippetResponse>, AppError> {
+    let snippets = state.db.snippet.list_snippets().await?;
+    if snippets.iter().any(|s| s.key == payload.key) {
+        return Err(AppError::Conflict(format!("Snippet '{}' already exists", payload.key)));
+    }
+
+    validate_snippet(snippets, None, &payload.key, &payload.content)?;
+
+    let id = state
+        .db
+        .snippet
+        .create_snippet(&payload.key, payload.description.as_deref(), &payload.content)
+        .await?;
+
+    let snippet = state
+        .db
+        .snippet
+        .get_snippet(id)
+        .await
+        .map_err(|_| AppError::NotFound("Snippet not found after creation".into()))?;
+
+    Ok(Json(snippet.into()))
+}
+
+pub async fn get_snippet(
+    Path(id): Path<i64>,
+    State(state): State<AppState>,
+) -> Result<Json<SnippetResponse>, AppError> {
+    let snippet = state
+        .db
+        .snippet
+        .get_snippet(id)
+        .await
+        .map_err(|_| AppError::NotFound("Snippet not found".into()))?;
+
+    Ok(Json(snippet.into()))
+}
+
+pub async fn list_snippets(
+    State(state): State<AppState>,
+) -> Result<Json<Vec<SnippetResponse>>, AppError> {
+    let snippets = state.db.snippet.list_snippets().await?;
+    Ok(Json(snippets.into_iter().map(|s| s.into()).collect()))
+}
+
+pub async fn update_snippet(
+    Path(id): Path<i64>,
+    State(state): State<AppState>,
+    Json(payload): Json<UpdateSnippetRequest>,
+) -> Result<Json<SnippetResponse>, AppError> {
+    let snippets = state.db.snippet.list_snippets().await?;
+    if snippets.iter().any(|s| s.key == payload.key && s.id != id) {
+        return Err(AppError::Conflict(format!("Snippet '{}' already exists", payload.key)));
+    }
+
+    // Templates include a snippet by key, renaming it would leave them including nothing
+    let current = snippets
+        .iter()
+        .find(|s| s.id == id)
+        .ok_or(AppError::NotFound("Snippet not found".into()))?;
+    if current.key != payload.key {
+        let dependents = find_dependents(&state, current).await?;
+        if !dependents.snippets.is_empty() || !dependents.prompt_versions.is_empty() {
+            return Err(AppError::Conflict(format!(
+                "Snippet '{}' can't be renamed while {} snippet(s) and {} prompt version(s) use it",
+                current.key,
+                dependents.snippets.len(),
+                dependents.prompt_versions.len()
+            )));
+        }
+    }
+
+    validate_snippet(snippets, Some(id), &payload.key, &payload.content)?;
+
+    let updated = state
+        .db
+        .snippet
+        .update_snippet(id, &payload.key, payload.description.as_deref(), &payload.content)
+        .await?;
+
+    if !updated {
+        return Err(AppError::NotFound("Snippet not found".into()));
+    }
+
+    let snippet = state
+        .db
+        .snippet
+        .get_snippet(id)
+        .await
+        .map_err(|_| AppError::NotFound("Snippet not found after update".into()))?;
+
+    Ok(Json(snippet.into()))
+}
+
+/// Deletes a snippet nothing uses anymore, the prompts and snippets that include it would fail
+/// to render without it.
+pub async fn delete_snippet(
+    Path(id): Path<i64>,
+    State(state): State<AppState>,
+) -> Result<(), AppError> {
+    let snippet = state
+        .db
+        .snippet
+        .get_snippet(id)
+        .await
+        .map_err(|_| AppError::NotFound("Snippet not found".into()))?;
+
+    let dependents = find_dependents(&state, &snippet).await?;
+    if !dependents.snippets.is_empty() || !dependents.prompt_versions.is_empty() {
+        return Err(AppError::Conflict(format!(
+            "Snippet '{}' is still used by {} snippet(s) and {} prompt version(s)",
+            snippet.key,
+            dependents.snippets.len(),
+            dependents.prompt_versions.len()
+        )));
+    }
+
+    let deleted = state.db.snippet.delete_snippet(id).await?;
+
+    if !deleted {
+        return Err(AppError::NotFound("Snippet not found".into()));
+    }
+
+    Ok(())
+}
+
+pub async fn get_snippet_versions(
+    Path(id): Path<i64>,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<SnippetResponse>>, AppError> {
+    let versions = state.db.snippet.get_snippet_versions(id).await?;
+
+    if versions.is_empty() {
+        return Err(AppError::NotFound("Snippet not found".into()));
+    }
+
+    Ok(Json(versions.into_iter().map(|s| s.into()).collect()))
+}
+
+/// Makes one of a snippet's versions active, as long as every snippet still builds with it.
+pub async fn set_active_snippet_version(
+    Path((snippet_id, version_id)): Path<(i64, i64)>,
+    State(state): State<AppState>,
+) -> Result<Json<SnippetResponse>, AppError> {
+    let version = state
+        .db
+        .snippet
+        .get_snippet_versions(snippet_id)
+        .await?
+        .into_iter()
+        .find(|v| v.version_id == version_id)
+        .ok_or(AppError::NotFound("Version not found or does not belong to this snippet".into()))?;
+
+    let snippets = state.db.snippet.list_snippets().await?;
+    validate_snippet(snippets, Some(snippet_id), &version.key, &version.content)?;
+
+    let snippet = state
+        .db
+        .snippet
+        .set_active_snippet_version(snippet_id, version_id)
+        .await
+        .map_err(|e| AppError::NotFound(e.to_string()))?;
+
+    Ok(Json(snippet.into()))
+}
+
+/// Lists the snippets and prompt versions that use a snippet, either directly or through
+/// another snippet.
+pub async fn get_snippet_dependents(
+    Path(id): Path<i64>,
+    State(state): State<AppState>,
+) -> Result<Json<SnippetDependentsResponse>, AppError> {
+    let snippet = state
+        .db
+        .snippet
+        .get_snippet(id)
+        .await
+        .map_err(|_| AppError::NotFound("Snippet not found".into()))?;
+
+    Ok(Json(find_dependents(&state, &snippet).await?))
+}
+
+async fn find_dependents(
+    state: &AppState,
+    snippet: &SnippetRow,
+) -> Result<SnippetDependentsResponse, AppError> {
+    let snippets = state.db.snippet.list_snippets().await?;
+
+    let mut dependent_snippets = Vec::new();
+    for other in snippets.iter().filter(|s| s.id != snippet.id) {
+        match resolve_snippet_dependencies(&other.content, &snippets) {
+            Ok(dependencies) if dependencies.contains(&snippet.key) => {
+                dependent_snippets.push(other.key.clone())
+            }
+            Ok(_) => {}
+            Err(e) => tracing::warn!("Failed to parse snippet '{}': {}", other.key, e),
+        }
+    }
+
+    let prompt_versions = state.db.snippet.get_prompt_version_templates().await?;
+
+    let mut dependent_versions = Vec::new();
+    for version in prompt_versions {
+        let templates = prompt_templates(&version.system, version.user.as_deref(), version.messages.as_deref());
+
+        let mut uses_snippet = false;
+        for template in &templates {
+            match resolve_snippet_dependencies(template, &snippets) {
+                Ok(dependencies) => uses_snippet |= dependencies.contains(&snippet.key),
+                Err(e) => tracing::warn!(
+                    "Failed to parse prompt '{}' version {}: {}",
+                    version.prompt_key,
+                    version.version_number,
+                    e
+                ),
+            }
+        }
+
+        if uses_snippet {
+            dependent_versions.push(version.into());
+        }
+    }
+
+    Ok(SnippetDependentsResponse {
+        snippets: dependent_snippets,
+        prompt_versions: dependent_versions,
+    })
+}
+
+/// Makes sure the snippet parses and that every snippet still builds with it in place.
+fn validate_snippet(
+    mut snippets: Vec<SnippetRow>,
+    id: Option<i64>,
+    key: &str,
+    content: &str,
+) -> Result<(), AppError> {
+    if key.trim().is_empty() {
+        return Err(AppError::BadRequest("Snippet key cannot be empty".into()));
+    }
+
+    if is_reserved_template_name(key) {
+        return Err(AppError::BadRequest(format!(
+            "'{}' is reserved for prompt templates, pick another snippet key",
+            key
+        )));
+    }
+
+    snippets.retain(|s| Some(s.id) != id);
+
+    build_tera(&snippets, &[(key, content)], "")
+        .map_err(|e| AppError::BadRequest(format!("Invalid snippet template: {:#}", anyhow::Error::from(e))))?;
+
+    Ok(())
+}
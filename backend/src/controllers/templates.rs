@@ -0,0 +1,10 @@
+use axum::Json;
+
+use crate::{services::template_helpers::TEMPLATE_HELPERS, AppError};
+
+use super::types::response::templates::TemplateHelperResponse;
+
+/// Lists the custom filters and functions prompt templates can use, for the editor.
+pub async fn list_template_filters() -> Result<Json<Vec<TemplateHelperResponse>>, AppError> {
+    Ok(Json(TEMPLATE_HELPERS.iter().map(|h| h.into()).collect()))
+}
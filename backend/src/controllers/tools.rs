@@ -6,9 +6,12 @@ use axum::{
 use crate::AppError;
 use crate::AppState;
 
-use super::types::{
-    request::tools::{AssociateToolPromptVersionRequest, CreateToolRequest, UpdateToolRequest},
-    response::tools::ToolResponse,
+use super::{
+    prompts::editable_prompt_version,
+    types::{
+        request::tools::{AssociateToolPromptVersionRequest, CreateToolRequest, UpdateToolRequest},
+        response::tools::ToolResponse,
+    },
 };
 
 pub async fn create_tool(
@@ -101,6 +104,8 @@ pub async fn associate_tool_with_prompt_version(
     State(state): State<AppState>,
     Json(payload): Json<AssociateToolPromptVersionRequest>,
 ) -> Result<(), AppError> {
+    editable_prompt_version(&state.db, payload.prompt_version_id).await?;
+
     state
         .db
         .tool
@@ -113,6 +118,8 @@ pub async fn remove_tool_prompt_version_association(
     State(state): State<AppState>,
     Json(payload): Json<AssociateToolPromptVersionRequest>,
 ) -> Result<(), AppError> {
+    editable_prompt_version(&state.db, payload.prompt_version_id).await?;
+
     let removed = state
         .db
         .tool
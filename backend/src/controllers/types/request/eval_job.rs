@@ -0,0 +1,10 @@
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+pub struct CreateEvalMatrixJobRequest {
+    pub prompt_version_ids: Vec<i64>,
+    /// Every version runs against each of these models, or only its own model when empty
+    #[serde(default)]
+    pub model_ids: Vec<i64>,
+    pub rounds: Option<i64>,
+}
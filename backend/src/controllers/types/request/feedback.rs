@@ -0,0 +1,13 @@
+use serde::Deserialize;
+
+
+/// Feedback from an end user on a completion, needs a `score`, a `label` or both
+#[derive(Debug, Deserialize)]
+pub struct CreateFeedbackRequest {
+    /// `id` of the chat completion response
+    pub completion_id: String,
+    /// Up to the caller's scale, e.g. 1 for thumbs up and 0 for thumbs down
+    pub score: Option<f64>,
+    pub label: Option<String>,
+    pub comment: Option<String>,
+}
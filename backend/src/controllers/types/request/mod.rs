@@ -6,3 +6,6 @@ pub mod providers;
 pub mod schema;
 pub mod user;
 pub mod tools;
+pub mod snippets;
+pub mod eval_job;
+pub mod feedback;
@@ -20,3 +20,16 @@ pub struct UpdateModelRequest {
     pub supports_tools: bool,
     pub is_reasoning: bool,
 }
+
+/// Prices are in USD per million tokens
+#[derive(Debug, Deserialize)]
+pub struct CreateModelPriceRequest {
+    pub input_per_million: f64,
+    pub output_per_million: f64,
+    /// Reasoning tokens are billed as output when unset
+    pub reasoning_per_million: Option<f64>,
+    /// Cached input tokens are billed as input when unset
+    pub cached_input_per_million: Option<f64>,
+    /// Takes effect immediately when unset
+    pub effective_from: Option<chrono::NaiveDateTime>,
+}
@@ -1,17 +1,64 @@
 use serde::Deserialize;
 use serde_json::Value;
 
+use crate::{
+    common::types::chat_request::ChatCompletionRequestMessage,
+    services::types::{
+        eval_assertion::EvalAssertion,
+        eval_import::{EvalImportFormat, EvalImportMapping},
+    },
+};
+
 #[derive(Debug, Deserialize)]
 pub struct CreateEvalTestRequest {
     pub prompt_id: i64,
     pub system_prompt_input: Option<Value>,
+    /// Not needed for conversation evals, which take it from their last user message
+    #[serde(default)]
     pub user_prompt_input: Value,
     pub name: Option<String>,
+    /// Evals with an expected output or assertions are scored automatically
+    pub expected_output: Option<String>,
+    pub assertions: Option<Vec<EvalAssertion>>,
+    /// Conversation after the system message, evaluated on the next assistant turn
+    pub messages: Option<Vec<ChatCompletionRequestMessage>>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct UpdateEvalTestRequest {
     pub system_prompt_input: Option<Value>,
+    #[serde(default)]
     pub user_prompt_input: String,
     pub name: String,
+    /// Left as-is when omitted, an empty value clears it
+    pub expected_output: Option<String>,
+    pub assertions: Option<Vec<EvalAssertion>>,
+    /// Left as-is when omitted, an empty list makes it a single-turn eval again
+    pub messages: Option<Vec<ChatCompletionRequestMessage>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetEvalJudgeRequest {
+    /// Key of the prompt that grades this prompt's eval outputs
+    pub judge_prompt_key: String,
+    pub rubric: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ImportEvalTestsRequest {
+    pub format: EvalImportFormat,
+    /// The CSV (with a header row) or JSONL file contents
+    pub data: String,
+    #[serde(default)]
+    pub mapping: EvalImportMapping,
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PromoteLogRequest {
+    pub name: Option<String>,
+    /// Use the logged response as the eval's expected output
+    #[serde(default)]
+    pub use_response_as_expected_output: bool,
 }
@@ -15,7 +15,11 @@ pub struct CreatePromptRequest {
     pub json_schema: Option<String>,
     pub prompt_type: String,
     pub is_chat: bool,
-    pub reasoning_effort: Option<String>
+    pub reasoning_effort: Option<String>,
+    /// Ordered message templates (system, few-shot examples, final user). Replaces `system`/`user` when set
+    pub messages: Option<Vec<ChatCompletionRequestMessage>>,
+    /// Why this version was created, like a commit message
+    pub change_message: Option<String>
 }
 
 #[derive(Debug, Deserialize)]
@@ -30,7 +34,16 @@ pub struct UpdatePromptRequest {
     pub json_schema: Option<String>,
     pub prompt_type: String,
     pub is_chat: bool,
-    pub reasoning_effort: Option<String>
+    pub reasoning_effort: Option<String>,
+    /// Ordered message templates (system, few-shot examples, final user). Replaces `system`/`user` when set
+    pub messages: Option<Vec<ChatCompletionRequestMessage>>,
+    /// Why this version was created, like a commit message
+    pub change_message: Option<String>
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetPromptApprovalRequest {
+    pub requires_approval: bool
 }
 
 #[derive(Debug, Serialize)]
@@ -43,3 +56,12 @@ pub struct ChatExecuteRequest {
     /// The first message should always be from the user
     pub messages: Vec<ChatCompletionRequestMessage>
 }
+
+#[derive(Debug, Deserialize)]
+pub struct ComparePromptModelsRequest {
+    /// Models to run the prompt against. The prompt's own model is only included if listed
+    pub model_ids: Vec<i64>,
+
+    /// Same message shape as `/chat/completions` (system message holds the template context)
+    pub messages: Vec<ChatCompletionRequestMessage>
+}
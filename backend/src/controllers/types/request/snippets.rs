@@ -0,0 +1,15 @@
+use serde::Deserialize;
+
+#[derive(Debug, Deserialize)]
+pub struct CreateSnippetRequest {
+    pub key: String,
+    pub description: Option<String>,
+    pub content: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateSnippetRequest {
+    pub key: String,
+    pub description: Option<String>,
+    pub content: String,
+}
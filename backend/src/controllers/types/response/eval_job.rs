@@ -0,0 +1,48 @@
+use serde::Serialize;
+
+use crate::{db::types::eval_job::EvalJobRow, services::types::eval_matrix::EvalMatrixCell};
+
+#[derive(Debug, Serialize)]
+pub struct EvalJobResponse {
+    pub id: i64,
+    pub prompt_id: i64,
+    pub prompt_version_id: i64,
+    pub rounds: i64,
+    pub status: String,
+    pub total: i64,
+    pub completed: i64,
+    pub failed: i64,
+    pub error: Option<String>,
+    pub created_by: Option<i64>,
+    /// Version and model pairs the job runs
+    pub matrix: Vec<EvalMatrixCell>,
+    pub started_at: Option<String>,
+    pub finished_at: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl From<EvalJobRow> for EvalJobResponse {
+    fn from(job: EvalJobRow) -> Self {
+        EvalJobResponse {
+            id: job.id,
+            prompt_id: job.prompt_id,
+            prompt_version_id: job.prompt_version_id,
+            rounds: job.rounds,
+            status: job.status,
+            total: job.total,
+            completed: job.completed,
+            failed: job.failed,
+            error: job.error,
+            created_by: job.created_by,
+            matrix: job
+                .matrix
+                .and_then(|m| serde_json::from_str(&m).ok())
+                .unwrap_or_default(),
+            started_at: job.started_at.map(|t| t.to_string()),
+            finished_at: job.finished_at.map(|t| t.to_string()),
+            created_at: job.created_at.to_string(),
+            updated_at: job.updated_at.to_string(),
+        }
+    }
+}
@@ -0,0 +1,29 @@
+use serde::Serialize;
+
+use crate::db::types::feedback::FeedbackRow;
+
+
+#[derive(Debug, Serialize)]
+pub struct FeedbackResponse {
+    pub id: i64,
+    pub log_id: i64,
+    pub score: Option<f64>,
+    pub label: Option<String>,
+    pub comment: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl From<FeedbackRow> for FeedbackResponse {
+    fn from(row: FeedbackRow) -> Self {
+        FeedbackResponse {
+            id: row.id,
+            log_id: row.log_id,
+            score: row.score,
+            label: row.label,
+            comment: row.comment,
+            created_at: row.created_at.to_string(),
+            updated_at: row.updated_at.to_string(),
+        }
+    }
+}
@@ -1,5 +1,5 @@
 use serde::Serialize;
-use crate::db::types::log::LogRowModel;
+use crate::db::types::log::{CostTotalRow, LatencyStatsRow, LogRowModel, SessionRow};
 
 #[derive(Debug, Serialize)]
 pub struct ApiLogResponse {
@@ -11,6 +11,19 @@ pub struct ApiLogResponse {
     pub status_code: Option<i64>,
     pub input_tokens: Option<i64>,
     pub output_tokens: Option<i64>,
+    pub reasoning_tokens: Option<i64>,
+    pub cached_input_tokens: Option<i64>,
+    /// USD, `None` when the model had no price when the request was made
+    pub cost: Option<f64>,
+    pub started_at: Option<String>,
+    pub duration_ms: Option<i64>,
+    pub first_token_ms: Option<i64>,
+    /// 1 for the first try, a request that was retried has a log for every attempt
+    pub attempt: Option<i64>,
+    pub retry_count: Option<i64>,
+    pub user_id: Option<String>,
+    pub session_id: Option<String>,
+    pub tags: Vec<String>,
     pub request_body: Option<String>,
     pub provider_response_id: String,
     pub created_at: String
@@ -27,6 +40,20 @@ impl From<LogRowModel> for ApiLogResponse {
             status_code: log.status_code,
             input_tokens: log.input_tokens,
             output_tokens: log.output_tokens,
+            reasoning_tokens: log.reasoning_tokens,
+            cached_input_tokens: log.cached_input_tokens,
+            cost: log.cost,
+            started_at: log.started_at.map(|v| v.to_string()),
+            duration_ms: log.duration_ms,
+            first_token_ms: log.first_token_ms,
+            attempt: log.attempt,
+            retry_count: log.attempt.map(|a| a - 1),
+            user_id: log.client_user_id,
+            session_id: log.session_id,
+            tags: log
+                .tags
+                .and_then(|t| serde_json::from_str(&t).ok())
+                .unwrap_or_default(),
             request_body: log.request_body,
             provider_response_id: log.provider_response_id,
             created_at: log.created_at.map(|v| v.to_string()).unwrap_or_default()
@@ -34,8 +61,124 @@ impl From<LogRowModel> for ApiLogResponse {
     }
 }
 
+#[derive(Debug, Serialize)]
+pub struct LogSearchResponse {
+    pub logs: Vec<ApiLogResponse>,
+    /// Pass back as `cursor` for the next page, `None` on the last page
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SessionResponse {
+    pub session_id: String,
+    pub user_id: Option<String>,
+    pub request_count: i64,
+    pub error_count: i64,
+    pub input_tokens: i64,
+    pub output_tokens: i64,
+    /// USD
+    pub cost: f64,
+    pub started_at: Option<String>,
+    pub last_request_at: Option<String>,
+}
+
+impl From<SessionRow> for SessionResponse {
+    fn from(row: SessionRow) -> Self {
+        SessionResponse {
+            session_id: row.session_id,
+            user_id: row.client_user_id,
+            request_count: row.request_count,
+            error_count: row.error_count,
+            input_tokens: row.input_tokens,
+            output_tokens: row.output_tokens,
+            cost: row.cost,
+            started_at: row.started_at.map(|v| v.to_string()),
+            last_request_at: row.last_request_at.map(|v| v.to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct SessionTraceResponse {
+    pub session_id: String,
+    /// Oldest first
+    pub logs: Vec<ApiLogResponse>,
+}
+
 #[derive(Serialize)]
 pub struct ApiLogCountResponse {
     pub count: i64,
 }
 
+
+#[derive(Debug, Serialize)]
+pub struct CostTotalResponse {
+    pub key: Option<String>,
+    pub name: Option<String>,
+    pub request_count: i64,
+    pub input_tokens: i64,
+    pub cached_input_tokens: i64,
+    pub output_tokens: i64,
+    pub reasoning_tokens: i64,
+    /// USD
+    pub cost: f64,
+    pub unpriced_count: i64,
+}
+
+impl From<CostTotalRow> for CostTotalResponse {
+    fn from(row: CostTotalRow) -> Self {
+        CostTotalResponse {
+            key: row.key,
+            name: row.name,
+            request_count: row.request_count,
+            input_tokens: row.input_tokens,
+            cached_input_tokens: row.cached_input_tokens,
+            output_tokens: row.output_tokens,
+            reasoning_tokens: row.reasoning_tokens,
+            cost: row.cost,
+            unpriced_count: row.unpriced_count,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct LatencyStatsResponse {
+    pub key: Option<String>,
+    pub name: Option<String>,
+    pub request_count: i64,
+    pub error_count: i64,
+    pub retry_count: i64,
+    pub avg_duration_ms: Option<f64>,
+    pub p50_duration_ms: Option<i64>,
+    pub p90_duration_ms: Option<i64>,
+    pub p95_duration_ms: Option<i64>,
+    pub p99_duration_ms: Option<i64>,
+    /// Streamed requests, the only ones with a time to first token
+    pub stream_count: i64,
+    pub p50_first_token_ms: Option<i64>,
+    pub p90_first_token_ms: Option<i64>,
+    pub p95_first_token_ms: Option<i64>,
+    pub p99_first_token_ms: Option<i64>,
+}
+
+impl From<LatencyStatsRow> for LatencyStatsResponse {
+    fn from(row: LatencyStatsRow) -> Self {
+        LatencyStatsResponse {
+            key: row.key,
+            name: row.name,
+            request_count: row.request_count,
+            error_count: row.error_count,
+            retry_count: row.retry_count,
+            avg_duration_ms: row.avg_duration_ms,
+            p50_duration_ms: row.p50_duration_ms,
+            p90_duration_ms: row.p90_duration_ms,
+            p95_duration_ms: row.p95_duration_ms,
+            p99_duration_ms: row.p99_duration_ms,
+            stream_count: row.stream_count,
+            p50_first_token_ms: row.p50_first_token_ms,
+            p90_first_token_ms: row.p90_first_token_ms,
+            p95_first_token_ms: row.p95_first_token_ms,
+            p99_first_token_ms: row.p99_first_token_ms,
+        }
+    }
+}
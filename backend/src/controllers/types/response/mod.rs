@@ -7,3 +7,7 @@ pub mod prompt_eval_run;
 pub mod schema;
 pub mod user;
 pub mod tools;
+pub mod snippets;
+pub mod templates;
+pub mod eval_job;
+pub mod feedback;
@@ -1,6 +1,6 @@
 use serde::Serialize;
 
-use crate::db::types::models::ModelProviderRow;
+use crate::db::types::models::{ModelPriceRow, ModelProviderRow};
 
 #[derive(Debug, Serialize)]
 pub struct ModelResponse {
@@ -31,3 +31,29 @@ impl From<ModelProviderRow> for ModelResponse {
     }
 }
 
+#[derive(Debug, Serialize)]
+pub struct ModelPriceResponse {
+    pub id: i64,
+    pub model_id: i64,
+    pub input_per_million: f64,
+    pub output_per_million: f64,
+    pub reasoning_per_million: Option<f64>,
+    pub cached_input_per_million: Option<f64>,
+    pub effective_from: String,
+    pub created_at: String,
+}
+
+impl From<ModelPriceRow> for ModelPriceResponse {
+    fn from(row: ModelPriceRow) -> Self {
+        Self {
+            id: row.id,
+            model_id: row.model_id,
+            input_per_million: row.input_per_million,
+            output_per_million: row.output_per_million,
+            reasoning_per_million: row.reasoning_per_million,
+            cached_input_per_million: row.cached_input_per_million,
+            effective_from: row.effective_from.to_string(),
+            created_at: row.created_at.to_string(),
+        }
+    }
+}
@@ -1,5 +1,7 @@
 use serde::Serialize;
-use crate::db::types::prompt_eval::PromptEval;
+use crate::common::types::chat_request::ChatCompletionRequestMessage;
+use crate::db::types::prompt_eval::{PromptEval, PromptEvalJudgeRow};
+use crate::services::types::eval_assertion::EvalAssertion;
 
 
 #[derive(Debug, Serialize)]
@@ -9,6 +11,11 @@ pub struct PromptEvalResponse {
     pub system_prompt_input: Option<String>,
     pub user_prompt_input: String,
     pub name: String,
+    pub evaluation_type: String,
+    pub expected_output: Option<String>,
+    pub assertions: Vec<EvalAssertion>,
+    /// Empty for single-turn evals
+    pub messages: Vec<ChatCompletionRequestMessage>,
     pub created_at: String,
     pub updated_at: String,
 }
@@ -21,8 +28,39 @@ impl From<PromptEval> for PromptEvalResponse {
             system_prompt_input: prompt.system_prompt_input,
             user_prompt_input: prompt.user_prompt_input,
             name: prompt.name,
+            evaluation_type: prompt.evaluation_type,
+            expected_output: prompt.expected_output,
+            assertions: prompt
+                .assertions
+                .and_then(|a| serde_json::from_str(&a).ok())
+                .unwrap_or_default(),
+            messages: prompt
+                .messages
+                .and_then(|m| serde_json::from_str(&m).ok())
+                .unwrap_or_default(),
             created_at: prompt.created_at.to_string(),
             updated_at: prompt.updated_at.to_string()
         }
     }
 }
+
+#[derive(Debug, Serialize)]
+pub struct PromptEvalJudgeResponse {
+    pub prompt_id: i64,
+    pub judge_prompt_key: String,
+    pub rubric: String,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl From<PromptEvalJudgeRow> for PromptEvalJudgeResponse {
+    fn from(judge: PromptEvalJudgeRow) -> Self {
+        PromptEvalJudgeResponse {
+            prompt_id: judge.prompt_id,
+            judge_prompt_key: judge.judge_prompt_key,
+            rubric: judge.rubric,
+            created_at: judge.created_at.to_string(),
+            updated_at: judge.updated_at.to_string(),
+        }
+    }
+}
@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 
-use crate::db::types::prompt_eval_run::{PromptEvalRun, PromptEvalVersionPerformance};
+use crate::db::types::prompt_eval_run::{PromptEvalRun, PromptEvalRunAssertionRow, PromptEvalVersionPerformance};
 
 // GET EVAL RESPONSE
 #[derive(Debug, Serialize, Deserialize)]
@@ -14,6 +14,26 @@ pub struct PromptEvalRunResponse {
     pub output: String,
     pub created_at: String,
     pub updated_at: String,
+    /// Set when the prompt has an eval judge
+    pub judge_score: Option<i64>,
+    pub judge_rationale: Option<String>,
+    pub judge_log_id: Option<i64>,
+    /// Why the judge couldn't grade this run
+    pub judge_error: Option<String>,
+    pub eval_job_id: Option<i64>,
+    /// Model the run executed against
+    pub model_id: Option<i64>,
+    /// Log row of the run's call
+    pub log_id: Option<i64>,
+    pub latency_ms: Option<i64>,
+    pub input_tokens: Option<i64>,
+    pub output_tokens: Option<i64>,
+    pub reasoning_tokens: Option<i64>,
+    /// USD, `None` when the model had no price
+    pub cost: Option<f64>,
+    /// Results of the eval's assertions, empty for human-scored evals
+    #[serde(default)]
+    pub assertions: Vec<PromptEvalRunAssertionResponse>,
 }
 
 impl From<PromptEvalRun> for PromptEvalRunResponse {
@@ -28,6 +48,36 @@ impl From<PromptEvalRun> for PromptEvalRunResponse {
             output: run.output,
             created_at: run.created_at.to_string(),
             updated_at: run.updated_at.to_string(),
+            judge_score: run.judge_score,
+            judge_rationale: run.judge_rationale,
+            judge_log_id: run.judge_log_id,
+            judge_error: run.judge_error,
+            eval_job_id: run.eval_job_id,
+            model_id: run.model_id,
+            log_id: run.log_id,
+            latency_ms: run.latency_ms,
+            input_tokens: run.input_tokens,
+            output_tokens: run.output_tokens,
+            reasoning_tokens: run.reasoning_tokens,
+            cost: run.cost,
+            assertions: Vec::new(), // filled separately
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PromptEvalRunAssertionResponse {
+    pub assertion: serde_json::Value,
+    pub passed: bool,
+    pub message: Option<String>,
+}
+
+impl From<PromptEvalRunAssertionRow> for PromptEvalRunAssertionResponse {
+    fn from(row: PromptEvalRunAssertionRow) -> Self {
+        PromptEvalRunAssertionResponse {
+            assertion: serde_json::from_str(&row.assertion).unwrap_or_default(),
+            passed: row.passed,
+            message: row.message,
         }
     }
 }
@@ -62,6 +112,18 @@ pub struct PromptEvalVersionPerformanceResponse {
     pub version_date: String,
     pub avg_score: Option<f64>,
     pub run_count: i64,
+    pub call_count: i64,
+    /// Averages per eval call
+    pub avg_latency_ms: Option<f64>,
+    pub avg_input_tokens: Option<f64>,
+    pub avg_output_tokens: Option<f64>,
+    pub avg_reasoning_tokens: Option<f64>,
+    pub avg_cost: Option<f64>,
+    /// USD spent on the version's eval calls that were priced
+    pub total_cost: Option<f64>,
+    /// Feedback end users sent on the version's completions
+    pub feedback_count: i64,
+    pub avg_feedback_score: Option<f64>,
 }
 
 impl From<PromptEvalVersionPerformance> for PromptEvalVersionPerformanceResponse {
@@ -71,7 +133,16 @@ impl From<PromptEvalVersionPerformance> for PromptEvalVersionPerformanceResponse
             version_number: res.version_number,
             version_date: res.version_date.to_string(),
             avg_score: res.avg_score,
-            run_count: res.run_count
+            run_count: res.run_count,
+            call_count: res.call_count,
+            avg_latency_ms: res.avg_latency_ms,
+            avg_input_tokens: res.avg_input_tokens,
+            avg_output_tokens: res.avg_output_tokens,
+            avg_reasoning_tokens: res.avg_reasoning_tokens,
+            avg_cost: res.avg_cost,
+            total_cost: res.total_cost,
+            feedback_count: res.feedback_count,
+            avg_feedback_score: res.avg_feedback_score,
         }
     }
 }
@@ -1,5 +1,6 @@
 use serde::Serialize;
 
+use crate::common::types::chat_request::ChatCompletionRequestMessage;
 use crate::db::types::prompt::PromptRowWithModel;
 use crate::controllers::types::response::tools::ToolResponse;
 
@@ -30,7 +31,16 @@ pub struct PromptResponse {
     pub supports_json_schema: bool,
     pub supports_tools: bool,
     pub is_reasoning: bool,
-    pub reasoning_effort: Option<String>
+    pub reasoning_effort: Option<String>,
+    pub messages: Option<Vec<ChatCompletionRequestMessage>>,
+    pub messages_version_diff: Option<String>,
+    pub read_only: bool,
+    pub change_message: Option<String>,
+    pub created_by: Option<i64>,
+    pub is_draft: bool,
+    pub approved_by: Option<i64>,
+    pub approved_at: Option<String>,
+    pub requires_approval: bool
 }
 
 
@@ -60,7 +70,54 @@ impl From<PromptRowWithModel> for PromptResponse {
             supports_json_schema: prompt.supports_json_schema,
             supports_tools: prompt.supports_tools,
             is_reasoning: prompt.is_reasoning,
-            reasoning_effort: prompt.reasoning_effort
+            reasoning_effort: prompt.reasoning_effort,
+            messages: prompt.messages.and_then(|m| serde_json::from_str(&m).ok()),
+            messages_version_diff: prompt.messages_diff,
+            read_only: prompt.read_only,
+            change_message: prompt.change_message,
+            created_by: prompt.created_by,
+            is_draft: prompt.is_draft,
+            approved_by: prompt.approved_by,
+            approved_at: prompt.approved_at.map(|a| a.to_string()),
+            requires_approval: prompt.requires_approval
+        }
+    }
+}
+
+
+// MODEL COMPARISON RESPONSE
+#[derive(Debug, Serialize)]
+pub struct PromptModelComparisonResponse {
+    pub model_id: i64,
+    pub model: String,
+    pub provider: String,
+    pub log_id: Option<i64>,
+    pub output: Option<String>,
+    pub tool_calls: Option<String>,
+    pub latency_ms: i64,
+    pub input_tokens: Option<i64>,
+    pub output_tokens: Option<i64>,
+    pub reasoning_tokens: Option<i64>,
+    /// USD, `None` when the model has no price
+    pub cost: Option<f64>,
+    pub error: Option<String>,
+}
+
+impl From<&PromptRowWithModel> for PromptModelComparisonResponse {
+    fn from(prompt: &PromptRowWithModel) -> Self {
+        PromptModelComparisonResponse {
+            model_id: prompt.model_id,
+            model: prompt.model_name.clone(),
+            provider: prompt.provider_name.clone(),
+            log_id: None,
+            output: None,
+            tool_calls: None,
+            latency_ms: 0,
+            input_tokens: None,
+            output_tokens: None,
+            reasoning_tokens: None,
+            cost: None,
+            error: None,
         }
     }
 }
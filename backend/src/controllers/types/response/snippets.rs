@@ -0,0 +1,62 @@
+use serde::Serialize;
+
+use crate::db::types::snippet::{PromptVersionTemplateRow, SnippetRow};
+
+
+// GET SNIPPET RESPONSE
+#[derive(Debug, Serialize)]
+pub struct SnippetResponse {
+    pub id: i64,
+    pub key: String,
+    pub description: Option<String>,
+    pub content: String,
+    pub version_id: i64,
+    pub version_number: i64,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl From<SnippetRow> for SnippetResponse {
+    fn from(snippet: SnippetRow) -> Self {
+        SnippetResponse {
+            id: snippet.id,
+            key: snippet.key,
+            description: snippet.description,
+            content: snippet.content,
+            version_id: snippet.version_id,
+            version_number: snippet.version_number,
+            created_at: snippet.created_at.to_string(),
+            updated_at: snippet.updated_at.to_string(),
+        }
+    }
+}
+
+
+// SNIPPET DEPENDENTS RESPONSE
+#[derive(Debug, Serialize)]
+pub struct SnippetDependentsResponse {
+    /// Keys of other snippets that pull this snippet in
+    pub snippets: Vec<String>,
+    pub prompt_versions: Vec<SnippetPromptVersionResponse>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SnippetPromptVersionResponse {
+    pub prompt_id: i64,
+    pub prompt_key: String,
+    pub version_id: i64,
+    pub version_number: i64,
+    pub is_active: bool,
+}
+
+impl From<PromptVersionTemplateRow> for SnippetPromptVersionResponse {
+    fn from(row: PromptVersionTemplateRow) -> Self {
+        SnippetPromptVersionResponse {
+            prompt_id: row.prompt_id,
+            prompt_key: row.prompt_key,
+            version_id: row.version_id,
+            version_number: row.version_number,
+            is_active: row.is_active,
+        }
+    }
+}
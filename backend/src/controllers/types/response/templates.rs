@@ -0,0 +1,26 @@
+use serde::Serialize;
+
+use crate::services::template_helpers::TemplateHelper;
+
+
+// TEMPLATE HELPER RESPONSE
+#[derive(Debug, Serialize)]
+pub struct TemplateHelperResponse {
+    pub name: String,
+    pub kind: String,
+    pub signature: String,
+    pub description: String,
+    pub example: String,
+}
+
+impl From<&TemplateHelper> for TemplateHelperResponse {
+    fn from(helper: &TemplateHelper) -> Self {
+        TemplateHelperResponse {
+            name: helper.name.to_string(),
+            kind: helper.kind.to_string(),
+            signature: helper.signature.to_string(),
+            description: helper.description.to_string(),
+            example: helper.example.to_string(),
+        }
+    }
+}
@@ -0,0 +1,227 @@
+use anyhow::Result;
+
+use super::types::eval_job::EvalJobRow;
+
+#[derive(Clone, Debug)]
+pub struct EvalJobRepository {
+    pool: sqlx::SqlitePool,
+}
+
+impl EvalJobRepository {
+    pub async fn new(pool: sqlx::SqlitePool) -> Result<Self> {
+        Ok(EvalJobRepository { pool })
+    }
+
+    pub async fn create(
+        &self,
+        prompt_id: i64,
+        prompt_version_id: i64,
+        rounds: i64,
+        total: i64,
+        created_by: i64,
+        matrix: &str,
+    ) -> Result<EvalJobRow> {
+        sqlx::query_as!(
+            EvalJobRow,
+            r#"
+            INSERT INTO eval_job (prompt_id, prompt_version_id, rounds, total, created_by, matrix)
+            VALUES (?, ?, ?, ?, ?, ?)
+            RETURNING *
+            "#,
+            prompt_id,
+            prompt_version_id,
+            rounds,
+            total,
+            created_by,
+            matrix
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(Into::into)
+    }
+
+    pub async fn get_by_id(&self, id: i64) -> Result<Option<EvalJobRow>> {
+        sqlx::query_as!(
+            EvalJobRow,
+            r#"
+            SELECT *
+            FROM eval_job
+            WHERE id = ?
+            "#,
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(Into::into)
+    }
+
+    pub async fn get_by_prompt(&self, prompt_id: i64) -> Result<Vec<EvalJobRow>> {
+        sqlx::query_as!(
+            EvalJobRow,
+            r#"
+            SELECT *
+            FROM eval_job
+            WHERE prompt_id = ?
+            ORDER BY id DESC
+            "#,
+            prompt_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(Into::into)
+    }
+
+    pub async fn set_running(&self, id: i64) -> Result<EvalJobRow> {
+        sqlx::query_as!(
+            EvalJobRow,
+            r#"
+            UPDATE eval_job
+            SET
+                -- a job cancelled while queued stays cancelled
+                status = CASE WHEN status = 'queued' THEN 'running' ELSE status END,
+                started_at = CURRENT_TIMESTAMP,
+                updated_at = CURRENT_TIMESTAMP
+            WHERE id = ?
+            RETURNING *
+            "#,
+            id
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(Into::into)
+    }
+
+    /// Counts one finished case, `error` is set when the case failed.
+    pub async fn record_case(&self, id: i64, error: Option<&str>) -> Result<EvalJobRow> {
+        let failed = error.is_some();
+        sqlx::query_as!(
+            EvalJobRow,
+            r#"
+            UPDATE eval_job
+            SET
+                completed = completed + 1,
+                failed = failed + ?,
+                error = COALESCE(?, error),
+                updated_at = CURRENT_TIMESTAMP
+            WHERE id = ?
+            RETURNING *
+            "#,
+            failed,
+            error,
+            id
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(Into::into)
+    }
+
+    /// Marks an unfinished job as cancelled, `None` when it already finished. Cases that are
+    /// already running still get recorded, and `finish` has the final word on the status.
+    pub async fn cancel(&self, id: i64) -> Result<Option<EvalJobRow>> {
+        sqlx::query_as!(
+            EvalJobRow,
+            r#"
+            UPDATE eval_job
+            SET status = 'cancelled', updated_at = CURRENT_TIMESTAMP
+            WHERE id = ? AND status IN ('queued', 'running')
+            RETURNING *
+            "#,
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(Into::into)
+    }
+
+    pub async fn finish(&self, id: i64, status: &str, error: Option<&str>) -> Result<EvalJobRow> {
+        sqlx::query_as!(
+            EvalJobRow,
+            r#"
+            UPDATE eval_job
+            SET
+                status = ?,
+                error = COALESCE(?, error),
+                finished_at = CURRENT_TIMESTAMP,
+                updated_at = CURRENT_TIMESTAMP
+            WHERE id = ?
+            RETURNING *
+            "#,
+            status,
+            error,
+            id
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(Into::into)
+    }
+
+    /// Jobs only run in the process that started them, so anything still unfinished at
+    /// startup was cut off by a restart.
+    pub async fn fail_interrupted(&self) -> Result<u64> {
+        let rows_affected = sqlx::query!(
+            r#"
+            UPDATE eval_job
+            SET
+                status = 'failed',
+                error = 'Interrupted by a server restart',
+                finished_at = CURRENT_TIMESTAMP,
+                updated_at = CURRENT_TIMESTAMP
+            WHERE status IN ('queued', 'running')
+            "#
+        )
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        Ok(rows_affected)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::db::init::DbData;
+
+    async fn create_job(db: &DbData, total: i64) -> i64 {
+        let prompt = db.prompt.list_prompts().await.unwrap().remove(0);
+        let user_id = db.user.create("Test", "test@test.com", "hash").await.unwrap();
+        db.eval_job
+            .create(prompt.id, prompt.version_id, 1, total, user_id, "[]")
+            .await
+            .unwrap()
+            .id
+    }
+
+    #[tokio::test]
+    async fn test_progress_is_counted_per_case() {
+        let db = DbData::in_memory().await.unwrap();
+        let id = create_job(&db, 2).await;
+
+        let job = db.eval_job.set_running(id).await.unwrap();
+        assert_eq!((job.status.as_str(), job.completed), ("running", 0));
+
+        db.eval_job.record_case(id, None).await.unwrap();
+        let job = db.eval_job.record_case(id, Some("Provider timed out")).await.unwrap();
+        assert_eq!((job.completed, job.failed), (2, 1));
+        assert_eq!(job.error.as_deref(), Some("Provider timed out"));
+
+        let job = db.eval_job.finish(id, "completed", None).await.unwrap();
+        assert_eq!(job.status, "completed");
+        assert_eq!(job.error.as_deref(), Some("Provider timed out"));
+        assert!(job.finished_at.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_cancel_returns_the_cancelled_row() {
+        let db = DbData::in_memory().await.unwrap();
+        let id = create_job(&db, 3).await;
+
+        let job = db.eval_job.cancel(id).await.unwrap().unwrap();
+        assert_eq!(job.status, "cancelled");
+
+        // A job cancelled before it started doesn't go back to running
+        assert_eq!(db.eval_job.set_running(id).await.unwrap().status, "cancelled");
+
+        db.eval_job.finish(id, "cancelled", None).await.unwrap();
+        assert!(db.eval_job.cancel(id).await.unwrap().is_none());
+    }
+}
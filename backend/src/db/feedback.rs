@@ -0,0 +1,150 @@
+use anyhow::Result;
+
+use super::types::feedback::FeedbackRow;
+
+#[derive(Clone, Debug)]
+pub struct FeedbackRepository {
+    pool: sqlx::SqlitePool,
+}
+
+impl FeedbackRepository {
+    pub async fn new(pool: sqlx::SqlitePool) -> Result<Self> {
+        Ok(FeedbackRepository { pool })
+    }
+
+    /// Saves feedback on the completion with the given provider response id, replacing any the
+    /// completion already had. `None` when no completion has that id or it was made with another
+    /// API key, so callers can only rate their own completions.
+    pub async fn upsert_feedback(
+        &self,
+        provider_response_id: &str,
+        score: Option<f64>,
+        label: Option<&str>,
+        comment: Option<&str>,
+        api_key_id: Option<i64>,
+    ) -> Result<Option<FeedbackRow>> {
+        // A retried request logs every attempt, the completion the caller got is the last one
+        let feedback = sqlx::query_as::<_, FeedbackRow>(
+            r#"
+            INSERT INTO feedback (log_id, score, label, comment, api_key_id)
+            SELECT id, ?, ?, ?, ?
+            FROM log
+            WHERE provider_response_id = ? AND api_key_id IS ?
+            ORDER BY id DESC
+            LIMIT 1
+            ON CONFLICT (log_id) DO UPDATE SET
+                score = excluded.score,
+                label = excluded.label,
+                comment = excluded.comment,
+                api_key_id = excluded.api_key_id,
+                updated_at = CURRENT_TIMESTAMP
+            RETURNING *
+            "#,
+        )
+        .bind(score)
+        .bind(label)
+        .bind(comment)
+        .bind(api_key_id)
+        .bind(provider_response_id)
+        .bind(api_key_id)
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(feedback)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::db::{logs::LogRepository, prompt_eval_run::PromptEvalTestRunRepository, types::log::NewLog};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_feedback_replaces_and_aggregates_per_version() {
+        let pool = sqlx::SqlitePool::connect(":memory:").await.unwrap();
+        sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+        let logs = LogRepository::in_memory(pool.clone()).await.unwrap();
+        let feedback = FeedbackRepository::new(pool.clone()).await.unwrap();
+
+        let (prompt_id, version_id, model_id): (i64, i64, i64) =
+            sqlx::query_as("SELECT prompt_id, id, model_id FROM prompt_version LIMIT 1")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+
+        for id in ["chatcmpl-1", "chatcmpl-2"] {
+            logs.create_log(&NewLog {
+                prompt_id: Some(prompt_id),
+                prompt_version_id: Some(version_id),
+                model_id,
+                status_code: Some(200),
+                provider_response_id: id,
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        }
+
+        let first = feedback
+            .upsert_feedback("chatcmpl-1", Some(1.0), Some("thumbs_up"), None, None)
+            .await
+            .unwrap()
+            .unwrap();
+        let changed = feedback
+            .upsert_feedback("chatcmpl-1", Some(0.0), Some("thumbs_down"), Some("Wrong total"), None)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(changed.id, first.id);
+        assert_eq!((changed.score, changed.comment.as_deref()), (Some(0.0), Some("Wrong total")));
+
+        feedback
+            .upsert_feedback("chatcmpl-2", Some(1.0), None, None, None)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(feedback
+            .upsert_feedback("chatcmpl-missing", Some(1.0), None, None, None)
+            .await
+            .unwrap()
+            .is_none());
+
+        let performance = PromptEvalTestRunRepository::new(pool.clone())
+            .await
+            .unwrap()
+            .get_prompt_version_performance(prompt_id)
+            .await
+            .unwrap();
+        assert_eq!(performance.len(), 1);
+        let version = &performance[0];
+        assert_eq!(version.version_id, version_id);
+        assert_eq!((version.run_count, version.avg_score), (0, None));
+        assert_eq!((version.feedback_count, version.avg_feedback_score), (2, Some(0.5)));
+
+        let api_key_id: i64 =
+            sqlx::query_scalar("INSERT INTO api_key (name, key_hash) VALUES ('Test', 'hash') RETURNING id")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        logs.create_log(&NewLog {
+            model_id,
+            api_key_id: Some(api_key_id),
+            provider_response_id: "chatcmpl-keyed",
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+        for other_key in [None, Some(api_key_id + 1)] {
+            assert!(feedback
+                .upsert_feedback("chatcmpl-keyed", Some(1.0), None, None, other_key)
+                .await
+                .unwrap()
+                .is_none());
+        }
+        assert!(feedback
+            .upsert_feedback("chatcmpl-keyed", Some(1.0), None, None, Some(api_key_id))
+            .await
+            .unwrap()
+            .is_some());
+    }
+}
@@ -1,12 +1,12 @@
 use anyhow::Result;
-use sqlx::migrate::Migrator;
+use sqlx::{migrate::Migrator, sqlite::SqlitePoolOptions, SqlitePool};
 
 use std::str::FromStr;
 
 use super::{
-    api_keys::ApiKeyRepository, logs::LogRepository, models::ModelRepository, prompt_eval::PromptEvalTestRepository, 
+    api_keys::ApiKeyRepository, eval_jobs::EvalJobRepository, feedback::FeedbackRepository, logs::LogRepository, models::{model_price_cache, ModelRepository}, prompt_eval::PromptEvalTestRepository, 
     prompt_eval_run::PromptEvalTestRunRepository, prompts::PromptRepository, providers::ProviderRepository, 
-    tools::ToolRepository, users::UserRepository
+    snippets::SnippetRepository, tools::ToolRepository, users::UserRepository
 };
 
 
@@ -25,6 +25,17 @@ pub struct DbData {
     pub api_key: ApiKeyRepository,
     pub user: UserRepository,
     pub tool: ToolRepository,
+    pub snippet: SnippetRepository,
+    pub eval_job: EvalJobRepository,
+    pub feedback: FeedbackRepository,
+    pool: SqlitePool,
+}
+
+/// A set of repositories that all run on one connection holding an open transaction, for
+/// writes that have to land together. Nothing is kept unless it's committed.
+pub struct DbTransaction {
+    pub db: DbData,
+    pool: SqlitePool,
 }
 
 impl DbData {
@@ -38,15 +49,35 @@ impl DbData {
 
         MIGRATOR.run(&pool).await?;
 
+        Self::from_pool(pool).await
+    }
+
+    #[cfg(test)]
+    pub async fn in_memory() -> Result<Self> {
+        let pool = SqlitePool::connect(":memory:").await?;
+        MIGRATOR.run(&pool).await?;
+        Self::from_pool(pool).await
+    }
+
+    #[cfg(test)]
+    pub fn pool(&self) -> &SqlitePool {
+        &self.pool
+    }
+
+    async fn from_pool(pool: SqlitePool) -> Result<Self> {
         let prompt = PromptRepository::new(pool.clone()).await?;
         let prompt_eval_run = PromptEvalTestRunRepository::new(pool.clone()).await?;
         let prompt_eval = PromptEvalTestRepository::new(pool.clone()).await?;
         let provider = ProviderRepository::new(pool.clone()).await?;
-        let log = LogRepository::new(pool.clone()).await?;
-        let model = ModelRepository::new(pool.clone()).await?;
+        let price_cache = model_price_cache();
+        let log = LogRepository::new(pool.clone(), price_cache.clone()).await?;
+        let model = ModelRepository::new(pool.clone(), price_cache).await?;
         let api_key = ApiKeyRepository::new(pool.clone()).await?;
         let user = UserRepository::new(pool.clone()).await?;
         let tool = ToolRepository::new(pool.clone()).await?;
+        let snippet = SnippetRepository::new(pool.clone()).await?;
+        let eval_job = EvalJobRepository::new(pool.clone()).await?;
+        let feedback = FeedbackRepository::new(pool.clone()).await?;
 
         Ok(DbData {
             log,
@@ -57,7 +88,42 @@ impl DbData {
             provider,
             api_key,
             user,
-            tool
+            tool,
+            snippet,
+            eval_job,
+            feedback,
+            pool,
         })
     }
+
+    /// Starts a transaction on a dedicated connection. SQLite has a single writer, so it's taken
+    /// up front (`BEGIN IMMEDIATE`) rather than upgraded halfway through.
+    pub async fn begin(&self) -> Result<DbTransaction> {
+        let options = (*self.pool.connect_options()).clone();
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect_with(options)
+            .await?;
+
+        sqlx::query("BEGIN IMMEDIATE").execute(&pool).await?;
+
+        Ok(DbTransaction {
+            db: Self::from_pool(pool.clone()).await?,
+            pool,
+        })
+    }
+}
+
+impl DbTransaction {
+    pub async fn commit(self) -> Result<()> {
+        sqlx::query("COMMIT").execute(&self.pool).await?;
+        self.pool.close().await;
+        Ok(())
+    }
+
+    pub async fn rollback(self) -> Result<()> {
+        sqlx::query("ROLLBACK").execute(&self.pool).await?;
+        self.pool.close().await;
+        Ok(())
+    }
 }
@@ -1,58 +1,77 @@
 use anyhow::Result;
-use crate::db::types::log::{LogRow, LogRowModel};
+use crate::db::types::{
+    log::{
+        CostGroup, CostTotalRow, LatencyGroup, LatencyStatsRow, LogCursor, LogFilter, LogRow,
+        LogRowModel, LogSearchRow, LogSort, NewLog, SessionRow, SortOrder,
+    },
+    models::ModelPriceRow,
+};
+use crate::db::models::ModelPriceCache;
 
 #[derive(Clone, Debug)]
 pub struct LogRepository {
     pool: sqlx::SqlitePool,
+    price_cache: ModelPriceCache,
 }
 
 impl LogRepository {
-    pub async fn new(pool: sqlx::SqlitePool) -> Result<Self> {
-        Ok(LogRepository { pool })
+    pub async fn new(pool: sqlx::SqlitePool, price_cache: ModelPriceCache) -> Result<Self> {
+        Ok(LogRepository { pool, price_cache })
     }
 
     #[cfg(test)]
     pub async fn in_memory(pool: sqlx::SqlitePool) -> Result<Self> {
-        Self::new(pool.clone()).await
+        Self::new(pool.clone(), crate::db::models::model_price_cache()).await
     }
 
-    pub async fn create_log(
-        &self,
-        prompt_id: Option<i64>,
-        model_id: i64,
-        response_data: Option<&str>,
-        status_code: Option<i64>,
-        input_tokens: Option<i64>,
-        output_tokens: Option<i64>,
-        reasoning_tokens: Option<i64>,
-        request_body: Option<&str>,
-        provider_response_id: &str
-    ) -> Result<i64> {
+    pub async fn create_log(&self, log: &NewLog<'_>) -> Result<i64> {
         let mut conn = self.pool.acquire().await?;
         let id = sqlx::query!(
             r#"
             INSERT INTO log (
                 prompt_id,
+                prompt_version_id,
                 model_id,
                 response_data,
                 status_code,
                 input_tokens,
                 output_tokens,
                 reasoning_tokens,
+                cached_input_tokens,
+                cost,
+                api_key_id,
+                started_at,
+                duration_ms,
+                first_token_ms,
+                attempt,
+                client_user_id,
+                session_id,
+                tags,
                 request_body,
                 provider_response_id,
                 created_at
-            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, CURRENT_TIMESTAMP)
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, CURRENT_TIMESTAMP)
             "#,
-            prompt_id,
-            model_id,
-            response_data,
-            status_code,
-            input_tokens,
-            output_tokens,
-            reasoning_tokens,
-            request_body,
-            provider_response_id,
+            log.prompt_id,
+            log.prompt_version_id,
+            log.model_id,
+            log.response_data,
+            log.status_code,
+            log.input_tokens,
+            log.output_tokens,
+            log.reasoning_tokens,
+            log.cached_input_tokens,
+            log.cost,
+            log.api_key_id,
+            log.started_at,
+            log.duration_ms,
+            log.first_token_ms,
+            log.attempt,
+            log.client_user_id,
+            log.session_id,
+            log.tags,
+            log.request_body,
+            log.provider_response_id,
         )
         .execute(&mut *conn)
         .await?
@@ -60,6 +79,37 @@ impl LogRepository {
         Ok(id)
     }
 
+    /// The price a request to the model is costed at when it's logged, the latest one in effect
+    pub async fn get_model_price(&self, model_id: i64) -> Result<Option<ModelPriceRow>> {
+        if let Some(price) = self.price_cache.get(&model_id).await {
+            return Ok(price);
+        }
+
+        let price = sqlx::query_as!(
+            ModelPriceRow,
+            r#"
+            SELECT 
+                id,
+                model_id,
+                input_per_million,
+                output_per_million,
+                reasoning_per_million,
+                cached_input_per_million,
+                effective_from,
+                created_at
+            FROM model_price
+            WHERE model_id = ? AND effective_from <= CURRENT_TIMESTAMP
+            ORDER BY effective_from DESC
+            LIMIT 1
+            "#,
+            model_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+        self.price_cache.insert(model_id, price.clone()).await;
+        Ok(price)
+    }
+
     pub async fn update_log_response(
         &self,
         id: i64,
@@ -108,6 +158,15 @@ impl LogRepository {
                 l.input_tokens,
                 l.output_tokens,
                 l.reasoning_tokens,
+                l.cached_input_tokens,
+                l.cost,
+                l.started_at,
+                l.duration_ms,
+                l.first_token_ms,
+                l.attempt,
+                l.client_user_id,
+                l.session_id,
+                l.tags,
                 l.created_at,
                 l.request_body,
                 l.provider_response_id
@@ -140,6 +199,15 @@ impl LogRepository {
                     l.input_tokens,
                     l.output_tokens,
                     l.reasoning_tokens,
+                    l.cached_input_tokens,
+                    l.cost,
+                    l.started_at,
+                    l.duration_ms,
+                    l.first_token_ms,
+                    l.attempt,
+                    l.client_user_id,
+                    l.session_id,
+                    l.tags,
                     l.created_at,
                     l.request_body,
                     l.provider_response_id
@@ -171,6 +239,12 @@ impl LogRepository {
                 input_tokens,
                 output_tokens,
                 reasoning_tokens,
+                cached_input_tokens,
+                cost,
+                started_at,
+                duration_ms,
+                first_token_ms,
+                attempt,
                 created_at,
                 request_body,
                 provider_response_id
@@ -211,6 +285,15 @@ impl LogRepository {
                 l.input_tokens,
                 l.output_tokens,
                 l.reasoning_tokens,
+                l.cached_input_tokens,
+                l.cost,
+                l.started_at,
+                l.duration_ms,
+                l.first_token_ms,
+                l.attempt,
+                l.client_user_id,
+                l.session_id,
+                l.tags,
                 l.created_at,
                 l.request_body,
                 l.provider_response_id
@@ -225,5 +308,523 @@ impl LogRepository {
         .await?;
         Ok(log)
     }
+
+    /// Logs matching `filter` in `sort` order, starting after `cursor`. Fetches up to `limit` logs.
+    pub async fn search_logs(
+        &self,
+        filter: &LogFilter,
+        sort: LogSort,
+        order: SortOrder,
+        cursor: Option<LogCursor>,
+        limit: i64,
+    ) -> Result<Vec<LogSearchRow>> {
+        let key = sort.key_expression();
+        let (direction, after) = match order {
+            SortOrder::Asc => ("ASC", ">"),
+            SortOrder::Desc => ("DESC", "<"),
+        };
+        let text = filter.text.as_deref().and_then(fts_match_query);
+
+        let query = format!(
+            r#"
+            SELECT
+                l.id,
+                l.prompt_id,
+                l.model_id,
+                m.name as model_name,
+                p.name as provider_name,
+                l.response_data,
+                l.status_code,
+                l.input_tokens,
+                l.output_tokens,
+                l.reasoning_tokens,
+                l.cached_input_tokens,
+                l.cost,
+                l.started_at,
+                l.duration_ms,
+                l.first_token_ms,
+                l.attempt,
+                l.client_user_id,
+                l.session_id,
+                l.tags,
+                l.created_at,
+                l.request_body,
+                l.provider_response_id,
+                CAST({key} AS REAL) AS sort_key
+            FROM log l
+            JOIN model m ON m.id = l.model_id
+            JOIN provider p ON p.id = m.provider_id
+            LEFT JOIN prompt pr ON pr.id = l.prompt_id
+            LEFT JOIN prompt_version pv ON pv.id = l.prompt_version_id
+            WHERE (? IS NULL OR pr.key = ?)
+                AND (? IS NULL OR pv.version_number = ?)
+                AND (? IS NULL OR m.name = ?)
+                AND (? IS NULL OR p.name = ?)
+                AND (? IS NULL OR l.status_code = ?)
+                AND (? IS NULL OR l.api_key_id = ?)
+                AND (? IS NULL OR l.client_user_id = ?)
+                AND (? IS NULL OR l.session_id = ?)
+                AND (? IS NULL OR EXISTS (SELECT 1 FROM json_each(l.tags) WHERE json_each.value = ?))
+                AND (? IS NULL OR l.created_at >= ?)
+                AND (? IS NULL OR l.created_at <= ?)
+                AND (? IS NULL OR COALESCE(l.input_tokens, 0) + COALESCE(l.output_tokens, 0) >= ?)
+                AND (? IS NULL OR COALESCE(l.input_tokens, 0) + COALESCE(l.output_tokens, 0) <= ?)
+                AND (? IS NULL OR l.duration_ms >= ?)
+                AND (? IS NULL OR l.duration_ms <= ?)
+                AND (? IS NULL OR l.id IN (SELECT rowid FROM log_fts WHERE log_fts MATCH ?))
+                AND (? IS NULL OR {key} {after} ? OR ({key} = ? AND l.id {after} ?))
+            ORDER BY {key} {direction}, l.id {direction}
+            LIMIT ?
+            "#
+        );
+
+        let logs = sqlx::query_as::<_, LogSearchRow>(&query)
+            .bind(&filter.prompt_key)
+            .bind(&filter.prompt_key)
+            .bind(filter.prompt_version)
+            .bind(filter.prompt_version)
+            .bind(&filter.model)
+            .bind(&filter.model)
+            .bind(&filter.provider)
+            .bind(&filter.provider)
+            .bind(filter.status_code)
+            .bind(filter.status_code)
+            .bind(filter.api_key_id)
+            .bind(filter.api_key_id)
+            .bind(&filter.client_user_id)
+            .bind(&filter.client_user_id)
+            .bind(&filter.session_id)
+            .bind(&filter.session_id)
+            .bind(&filter.tag)
+            .bind(&filter.tag)
+            .bind(filter.from)
+            .bind(filter.from)
+            .bind(filter.to)
+            .bind(filter.to)
+            .bind(filter.min_tokens)
+            .bind(filter.min_tokens)
+            .bind(filter.max_tokens)
+            .bind(filter.max_tokens)
+            .bind(filter.min_duration_ms)
+            .bind(filter.min_duration_ms)
+            .bind(filter.max_duration_ms)
+            .bind(filter.max_duration_ms)
+            .bind(&text)
+            .bind(&text)
+            .bind(cursor.map(|c| c.id))
+            .bind(cursor.map(|c| c.key))
+            .bind(cursor.map(|c| c.key))
+            .bind(cursor.map(|c| c.id))
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(logs)
+    }
+
+    /// Sessions with their request counts and totals, most recently active first.
+    pub async fn list_sessions(
+        &self,
+        client_user_id: Option<&str>,
+        page: i64,
+        page_size: i64,
+    ) -> Result<Vec<SessionRow>> {
+        let offset = (page - 1) * page_size;
+
+        let sessions = sqlx::query_as::<_, SessionRow>(
+            r#"
+            SELECT
+                l.session_id,
+                (
+                    SELECT latest.client_user_id
+                    FROM log latest
+                    WHERE latest.session_id = l.session_id
+                    ORDER BY latest.id DESC
+                    LIMIT 1
+                ) AS client_user_id,
+                COUNT(*) AS request_count,
+                COALESCE(SUM(CASE WHEN l.status_code IS NULL OR l.status_code >= 400 THEN 1 ELSE 0 END), 0) AS error_count,
+                COALESCE(SUM(l.input_tokens), 0) AS input_tokens,
+                COALESCE(SUM(l.output_tokens), 0) AS output_tokens,
+                COALESCE(SUM(l.cost), 0.0) AS cost,
+                MIN(l.created_at) AS started_at,
+                MAX(l.created_at) AS last_request_at
+            FROM log l
+            WHERE l.session_id IS NOT NULL
+            GROUP BY l.session_id
+            HAVING ? IS NULL OR SUM(CASE WHEN l.client_user_id = ? THEN 1 ELSE 0 END) > 0
+            ORDER BY MAX(l.id) DESC
+            LIMIT ? OFFSET ?
+            "#,
+        )
+        .bind(client_user_id)
+        .bind(client_user_id)
+        .bind(page_size)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(sessions)
+    }
+
+    /// A session's requests in the order they were made, so a conversation reads top to bottom.
+    pub async fn list_session_logs(&self, session_id: &str) -> Result<Vec<LogRowModel>> {
+        let filter = LogFilter {
+            session_id: Some(session_id.to_string()),
+            ..Default::default()
+        };
+        let logs = self
+            .search_logs(&filter, LogSort::CreatedAt, SortOrder::Asc, None, i64::MAX)
+            .await?;
+        Ok(logs.into_iter().map(|r| r.log).collect())
+    }
+
+    /// Cost and token totals per group of requests, within the days from `from` to `to` inclusive.
+    pub async fn get_cost_totals(
+        &self,
+        group: CostGroup,
+        from: Option<chrono::NaiveDate>,
+        to: Option<chrono::NaiveDate>,
+    ) -> Result<Vec<CostTotalRow>> {
+        let (key, name, join, order) = match group {
+            CostGroup::Prompt => (
+                "CAST(l.prompt_id AS TEXT)",
+                "pr.key",
+                "LEFT JOIN prompt pr ON pr.id = l.prompt_id",
+                "cost DESC",
+            ),
+            CostGroup::Model => (
+                "CAST(l.model_id AS TEXT)",
+                "m.name",
+                "LEFT JOIN model m ON m.id = l.model_id",
+                "cost DESC",
+            ),
+            CostGroup::ApiKey => (
+                "CAST(l.api_key_id AS TEXT)",
+                "k.name",
+                "LEFT JOIN api_key k ON k.id = l.api_key_id",
+                "cost DESC",
+            ),
+            CostGroup::Day => ("DATE(l.created_at)", "NULL", "", "key ASC"),
+        };
+
+        let query = format!(
+            r#"
+            SELECT
+                {key} AS key,
+                {name} AS name,
+                COUNT(*) AS request_count,
+                COALESCE(SUM(l.input_tokens), 0) AS input_tokens,
+                COALESCE(SUM(l.cached_input_tokens), 0) AS cached_input_tokens,
+                COALESCE(SUM(l.output_tokens), 0) AS output_tokens,
+                COALESCE(SUM(l.reasoning_tokens), 0) AS reasoning_tokens,
+                COALESCE(SUM(l.cost), 0.0) AS cost,
+                COALESCE(SUM(CASE WHEN l.cost IS NULL THEN 1 ELSE 0 END), 0) AS unpriced_count
+            FROM log l
+            {join}
+            WHERE (? IS NULL OR DATE(l.created_at) >= ?)
+                AND (? IS NULL OR DATE(l.created_at) <= ?)
+            GROUP BY {key}
+            ORDER BY {order}
+            "#
+        );
+
+        let totals = sqlx::query_as::<_, CostTotalRow>(&query)
+            .bind(from)
+            .bind(from)
+            .bind(to)
+            .bind(to)
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(totals)
+    }
+
+    /// Duration and time-to-first-token percentiles per group, over the attempts made within the
+    /// days from `from` to `to` inclusive. Logs from before timings were recorded are left out.
+    pub async fn get_latency_stats(
+        &self,
+        group: LatencyGroup,
+        from: Option<chrono::NaiveDate>,
+        to: Option<chrono::NaiveDate>,
+    ) -> Result<Vec<LatencyStatsRow>> {
+        let (key, name) = match group {
+            LatencyGroup::Prompt => ("CAST(l.prompt_id AS TEXT)", "pr.key"),
+            LatencyGroup::Model => ("CAST(l.model_id AS TEXT)", "m.name"),
+            LatencyGroup::Provider => ("CAST(p.id AS TEXT)", "p.name"),
+        };
+
+        // The nearest-rank percentile is the first value whose rank reaches the percentile
+        let query = format!(
+            r#"
+            WITH scoped AS (
+                SELECT
+                    {key} AS key,
+                    {name} AS name,
+                    l.duration_ms,
+                    l.first_token_ms,
+                    l.status_code,
+                    l.attempt
+                FROM log l
+                JOIN model m ON m.id = l.model_id
+                JOIN provider p ON p.id = m.provider_id
+                LEFT JOIN prompt pr ON pr.id = l.prompt_id
+                WHERE l.duration_ms IS NOT NULL
+                    AND (? IS NULL OR DATE(l.created_at) >= ?)
+                    AND (? IS NULL OR DATE(l.created_at) <= ?)
+            ),
+            totals AS (
+                SELECT
+                    key,
+                    MAX(name) AS name,
+                    COUNT(*) AS request_count,
+                    SUM(CASE WHEN status_code IS NULL OR status_code >= 400 THEN 1 ELSE 0 END) AS error_count,
+                    SUM(CASE WHEN attempt > 1 THEN 1 ELSE 0 END) AS retry_count,
+                    AVG(CAST(duration_ms AS FLOAT)) AS avg_duration_ms
+                FROM scoped
+                GROUP BY key
+            ),
+            durations AS (
+                SELECT
+                    key,
+                    duration_ms,
+                    ROW_NUMBER() OVER (PARTITION BY key ORDER BY duration_ms) AS rn,
+                    COUNT(*) OVER (PARTITION BY key) AS n
+                FROM scoped
+            ),
+            duration_percentiles AS (
+                SELECT
+                    key,
+                    MIN(CASE WHEN rn * 100 >= n * 50 THEN duration_ms END) AS p50,
+                    MIN(CASE WHEN rn * 100 >= n * 90 THEN duration_ms END) AS p90,
+                    MIN(CASE WHEN rn * 100 >= n * 95 THEN duration_ms END) AS p95,
+                    MIN(CASE WHEN rn * 100 >= n * 99 THEN duration_ms END) AS p99
+                FROM durations
+                GROUP BY key
+            ),
+            first_tokens AS (
+                SELECT
+                    key,
+                    first_token_ms,
+                    ROW_NUMBER() OVER (PARTITION BY key ORDER BY first_token_ms) AS rn,
+                    COUNT(*) OVER (PARTITION BY key) AS n
+                FROM scoped
+                WHERE first_token_ms IS NOT NULL
+            ),
+            first_token_percentiles AS (
+                SELECT
+                    key,
+                    COUNT(*) AS stream_count,
+                    MIN(CASE WHEN rn * 100 >= n * 50 THEN first_token_ms END) AS p50,
+                    MIN(CASE WHEN rn * 100 >= n * 90 THEN first_token_ms END) AS p90,
+                    MIN(CASE WHEN rn * 100 >= n * 95 THEN first_token_ms END) AS p95,
+                    MIN(CASE WHEN rn * 100 >= n * 99 THEN first_token_ms END) AS p99
+                FROM first_tokens
+                GROUP BY key
+            )
+            SELECT
+                t.key,
+                t.name,
+                t.request_count,
+                t.error_count,
+                t.retry_count,
+                t.avg_duration_ms,
+                dp.p50 AS p50_duration_ms,
+                dp.p90 AS p90_duration_ms,
+                dp.p95 AS p95_duration_ms,
+                dp.p99 AS p99_duration_ms,
+                COALESCE(fp.stream_count, 0) AS stream_count,
+                fp.p50 AS p50_first_token_ms,
+                fp.p90 AS p90_first_token_ms,
+                fp.p95 AS p95_first_token_ms,
+                fp.p99 AS p99_first_token_ms
+            FROM totals t
+            LEFT JOIN duration_percentiles dp ON dp.key IS t.key
+            LEFT JOIN first_token_percentiles fp ON fp.key IS t.key
+            ORDER BY dp.p95 DESC
+            "#
+        );
+
+        let stats = sqlx::query_as::<_, LatencyStatsRow>(&query)
+            .bind(from)
+            .bind(from)
+            .bind(to)
+            .bind(to)
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(stats)
+    }
+}
+
+/// Turns free text into an FTS5 query matching logs that contain every word, quoting each word so
+/// punctuation in ids and JSON isn't read as query syntax.
+fn fts_match_query(text: &str) -> Option<String> {
+    let terms = text
+        .split_whitespace()
+        .map(|term| format!("\"{}\"", term.replace('"', "\"\"")))
+        .collect::<Vec<_>>();
+    (!terms.is_empty()).then(|| terms.join(" "))
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_latency_percentiles() {
+        let pool = sqlx::SqlitePool::connect(":memory:").await.unwrap();
+        sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+        let logs = LogRepository::in_memory(pool.clone()).await.unwrap();
+
+        let model_id: i64 = sqlx::query_scalar("SELECT id FROM model LIMIT 1")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+
+        // 1..=20 ms, the second half streamed with a first token at half the duration
+        for duration_ms in 1..=20 {
+            let first_token_ms = (duration_ms > 10).then_some(duration_ms / 2);
+            let attempt = if duration_ms == 20 { 2 } else { 1 };
+            logs.create_log(&NewLog {
+                model_id,
+                status_code: Some(200),
+                duration_ms: Some(duration_ms),
+                first_token_ms,
+                attempt: Some(attempt),
+                provider_response_id: &format!("resp-{}", duration_ms),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        }
+
+        let stats = logs.get_latency_stats(LatencyGroup::Model, None, None).await.unwrap();
+        assert_eq!(stats.len(), 1);
+        let s = &stats[0];
+        assert_eq!(s.request_count, 20);
+        assert_eq!(s.retry_count, 1);
+        assert_eq!(s.error_count, 0);
+        assert_eq!(
+            (s.p50_duration_ms, s.p90_duration_ms, s.p95_duration_ms, s.p99_duration_ms),
+            (Some(10), Some(18), Some(19), Some(20))
+        );
+        assert_eq!(s.stream_count, 10);
+        assert_eq!((s.p50_first_token_ms, s.p99_first_token_ms), (Some(7), Some(10)));
+    }
+
+    #[tokio::test]
+    async fn test_search_logs() {
+        let pool = sqlx::SqlitePool::connect(":memory:").await.unwrap();
+        sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+        let logs = LogRepository::in_memory(pool.clone()).await.unwrap();
+
+        let model_id: i64 = sqlx::query_scalar("SELECT id FROM model LIMIT 1")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+
+        for i in 1..=5 {
+            let status = if i == 3 { 500 } else { 200 };
+            let response = format!(r#"{{"content": "answer {} for order-{}"}}"#, i, i * 100);
+            logs.create_log(&NewLog {
+                model_id,
+                response_data: Some(&response),
+                status_code: Some(status),
+                input_tokens: Some(i * 10),
+                output_tokens: Some(i),
+                duration_ms: Some(i * 100),
+                attempt: Some(1),
+                request_body: Some("{}"),
+                provider_response_id: &format!("resp-{}", i),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        }
+
+        let search = |filter: LogFilter, sort, order, cursor| {
+            let logs = logs.clone();
+            async move {
+                logs.search_logs(&filter, sort, order, cursor, 2)
+                    .await
+                    .unwrap()
+                    .into_iter()
+                    .map(|r| (r.log.provider_response_id, r.sort_key, r.log.id))
+                    .collect::<Vec<_>>()
+            }
+        };
+
+        // Newest first, paged through with the cursor of the last log
+        let first = search(LogFilter::default(), LogSort::CreatedAt, SortOrder::Desc, None).await;
+        assert_eq!(first.iter().map(|r| r.0.as_str()).collect::<Vec<_>>(), ["resp-5", "resp-4"]);
+        let (_, key, id) = first[1];
+        let second = search(LogFilter::default(), LogSort::CreatedAt, SortOrder::Desc, Some(LogCursor { key, id })).await;
+        assert_eq!(second.iter().map(|r| r.0.as_str()).collect::<Vec<_>>(), ["resp-3", "resp-2"]);
+
+        let slow_successes = LogFilter {
+            status_code: Some(200),
+            min_duration_ms: Some(200),
+            ..Default::default()
+        };
+        let found = search(slow_successes, LogSort::Tokens, SortOrder::Asc, None).await;
+        assert_eq!(found.iter().map(|r| r.0.as_str()).collect::<Vec<_>>(), ["resp-2", "resp-4"]);
+        assert_eq!(found[0].1, 22.0);
+
+        let text = LogFilter { text: Some("order-400".to_string()), ..Default::default() };
+        let found = search(text, LogSort::CreatedAt, SortOrder::Desc, None).await;
+        assert_eq!(found.iter().map(|r| r.0.as_str()).collect::<Vec<_>>(), ["resp-4"]);
+    }
+
+    #[tokio::test]
+    async fn test_sessions() {
+        let pool = sqlx::SqlitePool::connect(":memory:").await.unwrap();
+        sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+        let logs = LogRepository::in_memory(pool.clone()).await.unwrap();
+
+        let model_id: i64 = sqlx::query_scalar("SELECT id FROM model LIMIT 1")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+
+        let requests = [
+            ("alice", "chat-1", r#"["support"]"#, 200),
+            ("bob", "chat-2", r#"["billing","support"]"#, 200),
+            ("alice", "chat-1", r#"["support"]"#, 500),
+        ];
+        for (i, (user_id, session_id, tags, status)) in requests.into_iter().enumerate() {
+            logs.create_log(&NewLog {
+                model_id,
+                status_code: Some(status),
+                input_tokens: Some(10),
+                output_tokens: Some(5),
+                client_user_id: Some(user_id),
+                session_id: Some(session_id),
+                tags: Some(tags),
+                provider_response_id: &format!("resp-{}", i),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+        }
+
+        let sessions = logs.list_sessions(None, 1, 10).await.unwrap();
+        assert_eq!(sessions.iter().map(|s| s.session_id.as_str()).collect::<Vec<_>>(), ["chat-1", "chat-2"]);
+        assert_eq!((sessions[0].request_count, sessions[0].error_count, sessions[0].input_tokens), (2, 1, 20));
+
+        let bobs = logs.list_sessions(Some("bob"), 1, 10).await.unwrap();
+        assert_eq!(bobs.len(), 1);
+        assert_eq!(bobs[0].client_user_id.as_deref(), Some("bob"));
+
+        let trace = logs.list_session_logs("chat-1").await.unwrap();
+        assert_eq!(trace.iter().map(|l| l.provider_response_id.as_str()).collect::<Vec<_>>(), ["resp-0", "resp-2"]);
+
+        let billing = LogFilter { tag: Some("billing".to_string()), ..Default::default() };
+        let found = logs.search_logs(&billing, LogSort::CreatedAt, SortOrder::Desc, None, 10).await.unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].log.session_id.as_deref(), Some("chat-2"));
+    }
+
+    #[test]
+    fn test_log_cursor_round_trip() {
+        let cursor = LogCursor { key: 0.00125, id: 42 };
+        assert_eq!(cursor.to_string().parse::<LogCursor>().unwrap(), cursor);
+        assert!("42".parse::<LogCursor>().is_err());
+    }
+}
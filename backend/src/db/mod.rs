@@ -10,3 +10,6 @@ pub mod users;
 pub mod tools;
 
 pub mod init;
+pub mod snippets;
+pub mod eval_jobs;
+pub mod feedback;
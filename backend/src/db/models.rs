@@ -1,14 +1,28 @@
 use anyhow::Result;
-use crate::db::types::models::ModelProviderRow;
+use moka::future::Cache;
+use std::time::Duration;
+use crate::db::types::models::{ModelPriceRow, ModelProviderRow};
 
 #[derive(Clone, Debug)]
 pub struct ModelRepository {
     pool: sqlx::SqlitePool,
+    price_cache: ModelPriceCache,
+}
+
+/// Prices in effect per model, shared with the log repository so every logged request doesn't
+/// go back to the database. Entries expire so prices scheduled for later take effect on time.
+pub type ModelPriceCache = Cache<i64, Option<ModelPriceRow>>;
+
+pub fn model_price_cache() -> ModelPriceCache {
+    Cache::builder()
+        .max_capacity(500)
+        .time_to_live(Duration::from_secs(60))
+        .build()
 }
 
 impl ModelRepository {
-    pub async fn new(pool: sqlx::SqlitePool) -> Result<Self> {
-        Ok(ModelRepository { pool })
+    pub async fn new(pool: sqlx::SqlitePool, price_cache: ModelPriceCache) -> Result<Self> {
+        Ok(ModelRepository { pool, price_cache })
     }
 
     pub async fn create_model(
@@ -159,4 +173,134 @@ impl ModelRepository {
         .await?;
         Ok(model)
     }
+
+    pub async fn list_model_prices(&self, model_id: i64) -> Result<Vec<ModelPriceRow>> {
+        let prices = sqlx::query_as!(
+            ModelPriceRow,
+            r#"
+            SELECT 
+                id,
+                model_id,
+                input_per_million,
+                output_per_million,
+                reasoning_per_million,
+                cached_input_per_million,
+                effective_from,
+                created_at
+            FROM model_price
+            WHERE model_id = ?
+            ORDER BY effective_from DESC
+            "#,
+            model_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(prices)
+    }
+
+    /// Adds a price taking effect at `effective_from`, or immediately when it's not given
+    pub async fn create_model_price(
+        &self,
+        model_id: i64,
+        input_per_million: f64,
+        output_per_million: f64,
+        reasoning_per_million: Option<f64>,
+        cached_input_per_million: Option<f64>,
+        effective_from: Option<chrono::NaiveDateTime>,
+    ) -> Result<ModelPriceRow> {
+        let price = sqlx::query_as!(
+            ModelPriceRow,
+            r#"
+            INSERT INTO model_price (
+                model_id,
+                input_per_million,
+                output_per_million,
+                reasoning_per_million,
+                cached_input_per_million,
+                effective_from
+            ) VALUES (?, ?, ?, ?, ?, COALESCE(?, CURRENT_TIMESTAMP))
+            RETURNING
+                id,
+                model_id,
+                input_per_million,
+                output_per_million,
+                reasoning_per_million,
+                cached_input_per_million,
+                effective_from as "effective_from!: chrono::NaiveDateTime",
+                created_at
+            "#,
+            model_id,
+            input_per_million,
+            output_per_million,
+            reasoning_per_million,
+            cached_input_per_million,
+            effective_from
+        )
+        .fetch_one(&self.pool)
+        .await?;
+        self.price_cache.invalidate(&model_id).await;
+        Ok(price)
+    }
+
+    pub async fn delete_model_price(&self, model_id: i64, price_id: i64) -> Result<bool> {
+        let rows_affected = sqlx::query!(
+            r#"
+            DELETE FROM model_price
+            WHERE id = ? AND model_id = ?
+            "#,
+            price_id,
+            model_id
+        )
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+        self.price_cache.invalidate(&model_id).await;
+        Ok(rows_affected > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::db::init::DbData;
+
+    #[tokio::test]
+    async fn test_price_changes_reach_the_log_price_cache() {
+        let db = DbData::in_memory().await.unwrap();
+        let model_id = db.model.list_models().await.unwrap()[0].id;
+        let effective_from = chrono::NaiveDate::from_ymd_opt(2025, 1, 1)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap();
+
+        let first = db
+            .model
+            .create_model_price(model_id, 1.0, 2.0, None, None, Some(effective_from))
+            .await
+            .unwrap();
+        let cached = db.log.get_model_price(model_id).await.unwrap().unwrap();
+        assert_eq!(cached.id, first.id);
+
+        // Same effective_from is rejected by the unique index, which the controller turns into a 409
+        let err = db
+            .model
+            .create_model_price(model_id, 3.0, 4.0, None, None, Some(effective_from))
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<sqlx::Error>(),
+            Some(sqlx::Error::Database(e)) if e.is_unique_violation()
+        ));
+
+        let second = db
+            .model
+            .create_model_price(model_id, 3.0, 4.0, None, None, None)
+            .await
+            .unwrap();
+        let cached = db.log.get_model_price(model_id).await.unwrap().unwrap();
+        assert_eq!(cached.id, second.id);
+
+        db.model.delete_model_price(model_id, second.id).await.unwrap();
+        let cached = db.log.get_model_price(model_id).await.unwrap().unwrap();
+        assert_eq!(cached.id, first.id);
+    }
 }
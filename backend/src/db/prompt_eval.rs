@@ -1,6 +1,6 @@
 use anyhow::Result;
 
-use super::types::prompt_eval::PromptEval;
+use super::types::prompt_eval::{PromptEval, PromptEvalJudgeRow};
 
 #[derive(Clone, Debug)]
 pub struct PromptEvalTestRepository {
@@ -49,26 +49,42 @@ impl PromptEvalTestRepository {
         user_prompt_input: String,
         evaluation_type: &str,
         name: Option<String>,
+        expected_output: Option<String>,
+        assertions: Option<String>,
+        messages: Option<String>,
     ) -> Result<PromptEval> {
         sqlx::query_as!(
             PromptEval,
             r#"
-            INSERT INTO prompt_eval (prompt_id, system_prompt_input, user_prompt_input, name, evaluation_type)
-            VALUES (?, ?, ?, ?, ?)
+            INSERT INTO prompt_eval (prompt_id, system_prompt_input, user_prompt_input, name, evaluation_type, expected_output, assertions, messages)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
             RETURNING *
             "#,
             prompt_id,
             system_prompt_input,
             user_prompt_input,
             name,
-            evaluation_type
+            evaluation_type,
+            expected_output,
+            assertions,
+            messages
         )
         .fetch_one(&self.pool)
         .await
         .map_err(Into::into)
     }
 
-    pub async fn update(&self, id: i64, system_prompt_input: Option<String>, user_prompt_input: String, name: String) -> Result<PromptEval> {
+    pub async fn update(
+        &self,
+        id: i64,
+        system_prompt_input: Option<String>,
+        user_prompt_input: String,
+        name: String,
+        evaluation_type: &str,
+        expected_output: Option<String>,
+        assertions: Option<String>,
+        messages: Option<String>,
+    ) -> Result<PromptEval> {
         sqlx::query_as!(
             PromptEval,
             r#"
@@ -77,6 +93,10 @@ impl PromptEvalTestRepository {
                 system_prompt_input = ?,
                 user_prompt_input = ?,
                 name = ?,
+                evaluation_type = ?,
+                expected_output = ?,
+                assertions = ?,
+                messages = ?,
                 updated_at = CURRENT_TIMESTAMP
             WHERE id = ?
             RETURNING *
@@ -84,6 +104,10 @@ impl PromptEvalTestRepository {
             system_prompt_input,
             user_prompt_input,
             name,
+            evaluation_type,
+            expected_output,
+            assertions,
+            messages,
             id
         )
         .fetch_one(&self.pool)
@@ -91,6 +115,61 @@ impl PromptEvalTestRepository {
         .map_err(Into::into)
     }
 
+    pub async fn get_judge(&self, prompt_id: i64) -> Result<Option<PromptEvalJudgeRow>> {
+        sqlx::query_as!(
+            PromptEvalJudgeRow,
+            r#"
+            SELECT *
+            FROM prompt_eval_judge
+            WHERE prompt_id = ?
+            "#,
+            prompt_id
+        )
+        .fetch_optional(&self.pool)
+        .await
+        .map_err(Into::into)
+    }
+
+    pub async fn set_judge(
+        &self,
+        prompt_id: i64,
+        judge_prompt_key: &str,
+        rubric: &str,
+    ) -> Result<PromptEvalJudgeRow> {
+        sqlx::query_as!(
+            PromptEvalJudgeRow,
+            r#"
+            INSERT INTO prompt_eval_judge (prompt_id, judge_prompt_key, rubric)
+            VALUES (?, ?, ?)
+            ON CONFLICT (prompt_id) DO UPDATE SET
+                judge_prompt_key = excluded.judge_prompt_key,
+                rubric = excluded.rubric,
+                updated_at = CURRENT_TIMESTAMP
+            RETURNING *
+            "#,
+            prompt_id,
+            judge_prompt_key,
+            rubric
+        )
+        .fetch_one(&self.pool)
+        .await
+        .map_err(Into::into)
+    }
+
+    pub async fn delete_judge(&self, prompt_id: i64) -> Result<bool> {
+        let result = sqlx::query!(
+            r#"
+            DELETE FROM prompt_eval_judge
+            WHERE prompt_id = ?
+            "#,
+            prompt_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
     pub async fn delete(&self, id: i64) -> Result<()> {
         sqlx::query!(
             r#"
@@ -1,6 +1,9 @@
 use anyhow::Result;
 
-use super::types::prompt_eval_run::{PromptEvalRun, PromptEvalVersionPerformance};
+use super::types::prompt_eval_run::{
+    EvalCaseTotals, EvalMatrixCellTotals, PromptEvalRun, PromptEvalRunAssertionRow,
+    PromptEvalVersionPerformance,
+};
 
 #[derive(Clone, Debug)]
 pub struct PromptEvalTestRunRepository {
@@ -18,9 +21,14 @@ impl PromptEvalTestRunRepository {
             r#"
             SELECT
                 per.*,
-                pe.name AS prompt_eval_name
+                pe.name AS prompt_eval_name,
+                l.input_tokens,
+                l.output_tokens,
+                l.reasoning_tokens,
+                l.cost
             FROM prompt_eval_run per
             JOIN prompt_eval pe ON per.prompt_eval_id = pe.id
+            LEFT JOIN log l ON l.id = per.log_id
             WHERE per.id = ?
             "#,
             id
@@ -39,9 +47,14 @@ impl PromptEvalTestRunRepository {
             r#"
             SELECT
                 per.*,
-                pe.name AS prompt_eval_name
+                pe.name AS prompt_eval_name,
+                l.input_tokens,
+                l.output_tokens,
+                l.reasoning_tokens,
+                l.cost
             FROM prompt_eval_run per
             JOIN prompt_eval pe ON per.prompt_eval_id = pe.id
+            LEFT JOIN log l ON l.id = per.log_id
             WHERE per.prompt_version_id = ?
             "#,
             prompt_version_id
@@ -51,6 +64,154 @@ impl PromptEvalTestRunRepository {
         .map_err(Into::into)
     }
 
+    pub async fn get_by_eval_job(&self, eval_job_id: i64) -> Result<Vec<PromptEvalRun>> {
+        sqlx::query_as!(
+            PromptEvalRun,
+            r#"
+            SELECT
+                per.*,
+                pe.name AS prompt_eval_name,
+                l.input_tokens,
+                l.output_tokens,
+                l.reasoning_tokens,
+                l.cost
+            FROM prompt_eval_run per
+            JOIN prompt_eval pe ON per.prompt_eval_id = pe.id
+            LEFT JOIN log l ON l.id = per.log_id
+            WHERE per.eval_job_id = ?
+            ORDER BY per.id ASC
+            "#,
+            eval_job_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(Into::into)
+    }
+
+    /// Totals per (version, model) cell of an eval job. Tokens and latency are counted once
+    /// per LLM call even when the call was stored as several runs.
+    pub async fn get_eval_job_matrix(&self, eval_job_id: i64) -> Result<Vec<EvalMatrixCellTotals>> {
+        let query = r#"
+            WITH runs AS (
+                SELECT id, prompt_version_id, model_id, score, log_id, latency_ms
+                FROM prompt_eval_run
+                WHERE eval_job_id = ?
+            ),
+            calls AS (
+                SELECT DISTINCT prompt_version_id, model_id, log_id, latency_ms
+                FROM runs
+                WHERE log_id IS NOT NULL
+            ),
+            call_totals AS (
+                SELECT
+                    c.prompt_version_id,
+                    c.model_id,
+                    COUNT(*) AS call_count,
+                    AVG(CAST(c.latency_ms AS FLOAT)) AS avg_latency_ms,
+                    SUM(l.input_tokens) AS input_tokens,
+                    SUM(l.output_tokens) AS output_tokens,
+                    SUM(l.reasoning_tokens) AS reasoning_tokens,
+                    SUM(l.cost) AS cost
+                FROM calls c
+                JOIN log l ON l.id = c.log_id
+                GROUP BY c.prompt_version_id, c.model_id
+            ),
+            assertion_totals AS (
+                SELECT
+                    r.prompt_version_id,
+                    r.model_id,
+                    SUM(a.passed) AS assertions_passed,
+                    COUNT(a.id) AS assertions_total
+                FROM runs r
+                JOIN prompt_eval_run_assertion a ON a.prompt_eval_run_id = r.id
+                GROUP BY r.prompt_version_id, r.model_id
+            )
+            SELECT
+                r.prompt_version_id,
+                r.model_id,
+                COUNT(r.id) AS run_count,
+                COUNT(r.score) AS scored_count,
+                AVG(CAST(r.score AS FLOAT)) AS avg_score,
+                at.assertions_passed,
+                at.assertions_total,
+                ct.call_count,
+                ct.avg_latency_ms,
+                ct.input_tokens,
+                ct.output_tokens,
+                ct.reasoning_tokens,
+                ct.cost
+            FROM runs r
+            LEFT JOIN call_totals ct
+                ON ct.prompt_version_id = r.prompt_version_id AND ct.model_id IS r.model_id
+            LEFT JOIN assertion_totals at
+                ON at.prompt_version_id = r.prompt_version_id AND at.model_id IS r.model_id
+            GROUP BY r.prompt_version_id, r.model_id
+        "#;
+
+        let rows = sqlx::query_as::<_, EvalMatrixCellTotals>(query)
+            .bind(eval_job_id)
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(rows)
+    }
+
+    /// Score and assertion totals per eval case of a version, optionally only the runs
+    /// against one model.
+    pub async fn get_eval_case_totals(
+        &self,
+        prompt_version_id: i64,
+        model_id: Option<i64>,
+    ) -> Result<Vec<EvalCaseTotals>> {
+        let query = r#"
+            WITH runs AS (
+                SELECT id, prompt_eval_id, score, output
+                FROM prompt_eval_run
+                WHERE prompt_version_id = ?
+                  AND (? IS NULL OR model_id = ?)
+            ),
+            latest AS (
+                SELECT prompt_eval_id, MAX(id) AS run_id
+                FROM runs
+                GROUP BY prompt_eval_id
+            ),
+            assertion_totals AS (
+                SELECT
+                    r.prompt_eval_id,
+                    SUM(a.passed) AS assertions_passed,
+                    COUNT(a.id) AS assertions_total
+                FROM runs r
+                JOIN prompt_eval_run_assertion a ON a.prompt_eval_run_id = r.id
+                GROUP BY r.prompt_eval_id
+            )
+            SELECT
+                r.prompt_eval_id,
+                pe.name AS prompt_eval_name,
+                COUNT(r.id) AS run_count,
+                COUNT(r.score) AS scored_count,
+                AVG(CAST(r.score AS FLOAT)) AS avg_score,
+                at.assertions_passed,
+                at.assertions_total,
+                lr.output AS latest_output
+            FROM runs r
+            JOIN prompt_eval pe ON pe.id = r.prompt_eval_id
+            JOIN latest l ON l.prompt_eval_id = r.prompt_eval_id
+            JOIN runs lr ON lr.id = l.run_id
+            LEFT JOIN assertion_totals at ON at.prompt_eval_id = r.prompt_eval_id
+            GROUP BY r.prompt_eval_id
+            ORDER BY r.prompt_eval_id ASC
+        "#;
+
+        let rows = sqlx::query_as::<_, EvalCaseTotals>(query)
+            .bind(prompt_version_id)
+            .bind(model_id)
+            .bind(model_id)
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(rows)
+    }
+
+    /// Average score per version, with the latency and tokens of its eval calls and the feedback
+    /// end users gave its completions. A call stored as several runs is counted once.
     pub async fn get_prompt_version_performance(
         &self,
         prompt_id: i64,
@@ -69,18 +230,70 @@ impl PromptEvalTestRunRepository {
                 WHERE per.score IS NOT NULL
                   AND pv.prompt_id = ?
                 GROUP BY pv.id, pv.version_number, pv.created_at
+            ),
+            calls AS (
+                SELECT DISTINCT per.prompt_version_id, per.log_id, per.latency_ms
+                FROM prompt_eval_run per
+                JOIN prompt_version pv ON pv.id = per.prompt_version_id
+                WHERE pv.prompt_id = ?
+                  AND per.log_id IS NOT NULL
+            ),
+            call_totals AS (
+                SELECT
+                    c.prompt_version_id,
+                    COUNT(*) AS call_count,
+                    AVG(CAST(c.latency_ms AS FLOAT)) AS avg_latency_ms,
+                    AVG(CAST(l.input_tokens AS FLOAT)) AS avg_input_tokens,
+                    AVG(CAST(l.output_tokens AS FLOAT)) AS avg_output_tokens,
+                    AVG(CAST(l.reasoning_tokens AS FLOAT)) AS avg_reasoning_tokens,
+                    AVG(l.cost) AS avg_cost,
+                    SUM(l.cost) AS total_cost
+                FROM calls c
+                JOIN log l ON l.id = c.log_id
+                GROUP BY c.prompt_version_id
+            ),
+            feedback_totals AS (
+                SELECT
+                    l.prompt_version_id,
+                    COUNT(*) AS feedback_count,
+                    AVG(f.score) AS avg_feedback_score
+                FROM feedback f
+                JOIN log l ON l.id = f.log_id
+                JOIN prompt_version pv ON pv.id = l.prompt_version_id
+                WHERE pv.prompt_id = ?
+                GROUP BY l.prompt_version_id
+            ),
+            versions AS (
+                SELECT pv.id, pv.version_number, pv.created_at
+                FROM prompt_version pv
+                WHERE pv.id IN (SELECT version_id FROM avg_scores)
+                   OR pv.id IN (SELECT prompt_version_id FROM feedback_totals)
             )
             SELECT 
-                version_id,
-                version_number,
-                version_date,
-                COALESCE(avg_score, 0.0) AS avg_score,
-                run_count
-            FROM avg_scores
-            ORDER BY version_number ASC
+                v.id AS version_id,
+                v.version_number,
+                v.created_at AS version_date,
+                s.avg_score,
+                COALESCE(s.run_count, 0) AS run_count,
+                COALESCE(ct.call_count, 0) AS call_count,
+                ct.avg_latency_ms,
+                ct.avg_input_tokens,
+                ct.avg_output_tokens,
+                ct.avg_reasoning_tokens,
+                ct.avg_cost,
+                ct.total_cost,
+                COALESCE(ft.feedback_count, 0) AS feedback_count,
+                ft.avg_feedback_score
+            FROM versions v
+            LEFT JOIN avg_scores s ON s.version_id = v.id
+            LEFT JOIN call_totals ct ON ct.prompt_version_id = v.id
+            LEFT JOIN feedback_totals ft ON ft.prompt_version_id = v.id
+            ORDER BY v.version_number ASC
         "#;
 
         let rows = sqlx::query_as::<_, PromptEvalVersionPerformance>(query)
+            .bind(prompt_id)
+            .bind(prompt_id)
             .bind(prompt_id)
             .fetch_all(&self.pool)
             .await?;
@@ -95,17 +308,27 @@ impl PromptEvalTestRunRepository {
         prompt_eval_id: i64,
         score: Option<i64>,
         output: &str,
+        eval_job_id: Option<i64>,
+        model_id: i64,
+        log_id: i64,
+        latency_ms: i64,
     ) -> Result<PromptEvalRun> {
         let result = sqlx::query!(
             r#"
-            INSERT INTO prompt_eval_run (run_id, prompt_version_id, prompt_eval_id, score, output)
-            VALUES (?, ?, ?, ?, ?)
+            INSERT INTO prompt_eval_run (
+                run_id, prompt_version_id, prompt_eval_id, score, output, eval_job_id, model_id, log_id, latency_ms
+            )
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
             "#,
             run_id,
             prompt_version_id,
             prompt_eval_id,
             score,
-            output
+            output,
+            eval_job_id,
+            model_id,
+            log_id,
+            latency_ms
         )
         .execute(&self.pool)
         .await?;
@@ -135,6 +358,91 @@ impl PromptEvalTestRunRepository {
         self.get_by_id(id).await
     }
 
+    pub async fn set_judge_result(
+        &self,
+        id: i64,
+        judge_score: i64,
+        judge_rationale: &str,
+        judge_log_id: i64,
+    ) -> Result<PromptEvalRun> {
+        sqlx::query!(
+            r#"
+            UPDATE prompt_eval_run
+            SET judge_score = ?, judge_rationale = ?, judge_log_id = ?, updated_at = CURRENT_TIMESTAMP
+            WHERE id = ?
+            "#,
+            judge_score,
+            judge_rationale,
+            judge_log_id,
+            id,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        self.get_by_id(id).await
+    }
+
+    pub async fn set_judge_error(&self, id: i64, judge_error: &str) -> Result<PromptEvalRun> {
+        sqlx::query!(
+            r#"
+            UPDATE prompt_eval_run
+            SET judge_error = ?, updated_at = CURRENT_TIMESTAMP
+            WHERE id = ?
+            "#,
+            judge_error,
+            id,
+        )
+        .execute(&self.pool)
+        .await?;
+
+        self.get_by_id(id).await
+    }
+
+    /// Stores per-assertion results of a run, `assertions` is (assertion JSON, passed, message).
+    pub async fn create_assertion_results(
+        &self,
+        prompt_eval_run_id: i64,
+        assertions: &[(String, bool, Option<String>)],
+    ) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        for (position, (assertion, passed, message)) in assertions.iter().enumerate() {
+            let position = position as i64;
+            sqlx::query!(
+                r#"
+                INSERT INTO prompt_eval_run_assertion (prompt_eval_run_id, position, assertion, passed, message)
+                VALUES (?, ?, ?, ?, ?)
+                "#,
+                prompt_eval_run_id,
+                position,
+                assertion,
+                passed,
+                message
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    pub async fn get_assertion_results(&self, prompt_eval_run_id: i64) -> Result<Vec<PromptEvalRunAssertionRow>> {
+        sqlx::query_as!(
+            PromptEvalRunAssertionRow,
+            r#"
+            SELECT *
+            FROM prompt_eval_run_assertion
+            WHERE prompt_eval_run_id = ?
+            ORDER BY position ASC
+            "#,
+            prompt_eval_run_id
+        )
+        .fetch_all(&self.pool)
+        .await
+        .map_err(Into::into)
+    }
+
     pub async fn delete(&self, id: i64) -> Result<()> {
         sqlx::query!(
             r#"
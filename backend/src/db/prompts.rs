@@ -30,6 +30,9 @@ impl PromptRepository {
         prompt_type: &str,
         is_chat: bool,
         reasoning_effort: Option<&str>,
+        messages: Option<&str>,
+        change_message: Option<&str>,
+        created_by: Option<i64>,
     ) -> Result<i64> {
         let mut conn = self.pool.acquire().await?;
 
@@ -66,9 +69,13 @@ impl PromptRepository {
                 json_schema,
                 prompt_type,
                 is_chat,
-                reasoning_effort
+                reasoning_effort,
+                messages,
+                messages_diff,
+                change_message,
+                created_by
             )
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             "#,
             prompt_id,
             next_version,
@@ -83,7 +90,11 @@ impl PromptRepository {
             json_schema,
             prompt_type,
             is_chat,
-            reasoning_effort
+            reasoning_effort,
+            messages,
+            "", // initial messages_diff is empty
+            change_message,
+            created_by
         )
         .execute(&mut *conn)
         .await?;
@@ -134,7 +145,16 @@ impl PromptRepository {
                 pv.version_number,
                 pv.id as version_id,
                 pv.created_at,
-                pv.updated_at
+                pv.updated_at,
+                pv.messages,
+                pv.messages_diff,
+                p.read_only,
+                pv.change_message,
+                pv.created_by,
+                pv.is_draft,
+                pv.approved_by,
+                pv.approved_at,
+                p.requires_approval
             FROM prompt p
             JOIN prompt_version pv ON p.current_prompt_version_id = pv.id
             JOIN model m ON pv.model_id = m.id
@@ -177,7 +197,16 @@ impl PromptRepository {
                 pv.version_number,
                 pv.id as version_id,
                 pv.created_at,
-                pv.updated_at
+                pv.updated_at,
+                pv.messages,
+                pv.messages_diff,
+                p.read_only,
+                pv.change_message,
+                pv.created_by,
+                pv.is_draft,
+                pv.approved_by,
+                pv.approved_at,
+                p.requires_approval
             FROM prompt p
             JOIN prompt_version pv ON p.current_prompt_version_id = pv.id
             JOIN model m ON pv.model_id = m.id
@@ -204,15 +233,23 @@ impl PromptRepository {
         prompt_type: &str,
         is_chat: bool,
         reasoning_effort: Option<&str>,
-    ) -> Result<bool> {
-        let mut conn = self.pool.acquire().await?;
-
+        messages: Option<&str>,
+        change_message: Option<&str>,
+        created_by: Option<i64>,
+        draft: bool,
+    ) -> Result<Option<i64>> {
         // 1. fetch current prompt to compute diffs
         let current_prompt = self.get_prompt(id).await?;
         let current_user_prompt = current_prompt.user.unwrap_or("".to_string());
 
         let system_diff = generate_diff(&current_prompt.system, system_prompt);
         let user_diff = generate_diff(&current_user_prompt, user_prompt);
+        let messages_diff = generate_diff(
+            &messages_diff_text(current_prompt.messages.as_deref()),
+            &messages_diff_text(messages),
+        );
+
+        let mut conn = self.pool.acquire().await?;
 
         // 2. get the latest version number for THIS prompt (using prompt_id)
         let latest_version: Option<i64> = sqlx::query!(
@@ -245,9 +282,14 @@ impl PromptRepository {
                 json_schema,
                 prompt_type,
                 is_chat,
-                reasoning_effort
+                reasoning_effort,
+                messages,
+                messages_diff,
+                change_message,
+                created_by,
+                is_draft
             )
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             "#,
             id,
             next_version,
@@ -262,12 +304,22 @@ impl PromptRepository {
             json_schema,
             prompt_type,
             is_chat,
-            reasoning_effort
+            reasoning_effort,
+            messages,
+            messages_diff,
+            change_message,
+            created_by,
+            draft
         )
         .execute(&mut *conn)
         .await?;
         let prompt_version_id = prompt_version.last_insert_rowid();
 
+        // drafts wait for approval, the prompt keeps pointing at its active version
+        if draft {
+            return Ok(Some(prompt_version_id));
+        }
+
         // 4. update the prompt row with the new key and new current_prompt_version_id
         let affected = sqlx::query!(
             r#"
@@ -284,7 +336,7 @@ impl PromptRepository {
         .await?
         .rows_affected();
 
-        Ok(affected > 0)
+        Ok((affected > 0).then_some(prompt_version_id))
     }
 
     pub async fn delete_prompt(&self, id: i64) -> Result<bool> {
@@ -459,7 +511,16 @@ impl PromptRepository {
                 pv.version_number,
                 pv.id as version_id,
                 pv.created_at,
-                pv.updated_at
+                pv.updated_at,
+                pv.messages,
+                pv.messages_diff,
+                p.read_only,
+                pv.change_message,
+                pv.created_by,
+                pv.is_draft,
+                pv.approved_by,
+                pv.approved_at,
+                p.requires_approval
             FROM prompt p
             JOIN prompt_version pv ON p.current_prompt_version_id = pv.id
             JOIN model m ON pv.model_id = m.id
@@ -502,7 +563,16 @@ impl PromptRepository {
                 pv.version_number,
                 pv.id as version_id,
                 pv.created_at,
-                pv.updated_at
+                pv.updated_at,
+                pv.messages,
+                pv.messages_diff,
+                p.read_only,
+                pv.change_message,
+                pv.created_by,
+                pv.is_draft,
+                pv.approved_by,
+                pv.approved_at,
+                p.requires_approval
             FROM prompt_version pv
             JOIN prompt p ON pv.prompt_id = p.id
             JOIN model m ON pv.model_id = m.id
@@ -517,6 +587,21 @@ impl PromptRepository {
         Ok(versions)
     }
 
+    pub async fn get_prompt_id_by_version(&self, version_id: i64) -> Result<Option<i64>> {
+        let prompt_id = sqlx::query_scalar!(
+            r#"
+            SELECT prompt_id
+            FROM prompt_version
+            WHERE id = ?
+            "#,
+            version_id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(prompt_id)
+    }
+
     pub async fn set_active_prompt_version(&self, prompt_id: i64, version_id: i64) -> Result<PromptRowWithModel> {
         // Verify the version belongs to this prompt
         let version_count = sqlx::query!(
@@ -557,6 +642,87 @@ impl PromptRepository {
         // Return the updated prompt
         self.get_prompt(prompt_id).await
     }
+
+    /// Approves a draft version. Returns false when the version isn't a draft of this prompt.
+    pub async fn approve_prompt_version(&self, prompt_id: i64, version_id: i64, user_id: i64) -> Result<bool> {
+        let affected = sqlx::query!(
+            r#"
+            UPDATE prompt_version
+            SET is_draft = 0,
+                approved_by = ?,
+                approved_at = CURRENT_TIMESTAMP
+            WHERE id = ? AND prompt_id = ? AND is_draft = 1
+            "#,
+            user_id,
+            version_id,
+            prompt_id
+        )
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        Ok(affected > 0)
+    }
+
+    /// Turns the approval gate on or off, remembering who turned it on.
+    pub async fn set_prompt_requires_approval(
+        &self,
+        prompt_id: i64,
+        requires_approval: bool,
+        user_id: i64,
+    ) -> Result<bool> {
+        let affected = sqlx::query!(
+            r#"
+            UPDATE prompt
+            SET requires_approval = ?,
+                approval_required_by = CASE WHEN ? THEN ? ELSE NULL END
+            WHERE id = ?
+            "#,
+            requires_approval,
+            requires_approval,
+            user_id,
+            prompt_id
+        )
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        Ok(affected > 0)
+    }
+
+    /// The user who turned the prompt's approval gate on, `None` when it's off or they're gone.
+    pub async fn get_approval_required_by(&self, prompt_id: i64) -> Result<Option<i64>> {
+        let user_id = sqlx::query_scalar!(
+            r#"
+            SELECT approval_required_by
+            FROM prompt
+            WHERE id = ?
+            "#,
+            prompt_id
+        )
+        .fetch_optional(&self.pool)
+        .await?
+        .flatten();
+
+        Ok(user_id)
+    }
+
+    pub async fn set_prompt_read_only(&self, prompt_id: i64, read_only: bool) -> Result<bool> {
+        let affected = sqlx::query!(
+            r#"
+            UPDATE prompt
+            SET read_only = ?
+            WHERE id = ?
+            "#,
+            read_only,
+            prompt_id
+        )
+        .execute(&self.pool)
+        .await?
+        .rows_affected();
+
+        Ok(affected > 0)
+    }
 }
 
 fn generate_diff(text1: &str, text2: &str) -> String {
@@ -574,3 +740,10 @@ fn generate_diff(text1: &str, text2: &str) -> String {
     diff_string
 }
 
+// Message templates are diffed as one document, one pretty-printed JSON line at a time
+fn messages_diff_text(messages: Option<&str>) -> String {
+    messages
+        .and_then(|m| serde_json::from_str::<serde_json::Value>(m).ok())
+        .and_then(|m| serde_json::to_string_pretty(&m).ok())
+        .unwrap_or_default()
+}
@@ -0,0 +1,342 @@
+use anyhow::Result;
+use crate::db::types::snippet::{PromptVersionTemplateRow, SnippetRow};
+
+#[derive(Clone, Debug)]
+pub struct SnippetRepository {
+    pool: sqlx::SqlitePool,
+}
+
+impl SnippetRepository {
+    pub async fn new(pool: sqlx::SqlitePool) -> Result<Self> {
+        Ok(SnippetRepository { pool })
+    }
+
+    #[cfg(test)]
+    pub async fn in_memory(pool: sqlx::SqlitePool) -> Result<Self> {
+        Self::new(pool.clone()).await
+    }
+
+    pub async fn create_snippet(
+        &self,
+        key: &str,
+        description: Option<&str>,
+        content: &str,
+    ) -> Result<i64> {
+        let mut tx = self.pool.begin().await?;
+
+        // 1. insert the snippet row with a null current_snippet_version_id
+        let snippet = sqlx::query!(
+            r#"
+            INSERT INTO snippet (key, description, current_snippet_version_id)
+            VALUES (?, ?, ?)
+            "#,
+            key,
+            description,
+            Option::<i64>::None,
+        )
+        .execute(&mut *tx)
+        .await?;
+        let snippet_id = snippet.last_insert_rowid();
+
+        // 2. new snippet -> version 1
+        let snippet_version = sqlx::query!(
+            r#"
+            INSERT INTO snippet_version (snippet_id, version_number, content)
+            VALUES (?, 1, ?)
+            "#,
+            snippet_id,
+            content
+        )
+        .execute(&mut *tx)
+        .await?;
+        let snippet_version_id = snippet_version.last_insert_rowid();
+
+        // 3. point the snippet at its first version
+        sqlx::query!(
+            r#"
+            UPDATE snippet
+            SET current_snippet_version_id = ?
+            WHERE id = ?
+            "#,
+            snippet_version_id,
+            snippet_id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+
+        Ok(snippet_id)
+    }
+
+    pub async fn get_snippet(&self, id: i64) -> Result<SnippetRow> {
+        let snippet = sqlx::query_as!(
+            SnippetRow,
+            r#"
+            SELECT
+                s.id,
+                s.key,
+                s.description,
+                sv.content,
+                sv.id as version_id,
+                sv.version_number,
+                s.created_at,
+                s.updated_at
+            FROM snippet s
+            JOIN snippet_version sv ON s.current_snippet_version_id = sv.id
+            WHERE s.id = ?
+            "#,
+            id
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        match snippet {
+            Some(s) => Ok(s),
+            None => anyhow::bail!("Snippet not found"),
+        }
+    }
+
+    /// Returns every snippet at its active version.
+    pub async fn list_snippets(&self) -> Result<Vec<SnippetRow>> {
+        let snippets = sqlx::query_as!(
+            SnippetRow,
+            r#"
+            SELECT
+                s.id,
+                s.key,
+                s.description,
+                sv.content,
+                sv.id as version_id,
+                sv.version_number,
+                s.created_at,
+                s.updated_at
+            FROM snippet s
+            JOIN snippet_version sv ON s.current_snippet_version_id = sv.id
+            ORDER BY s.key
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(snippets)
+    }
+
+    /// Returns the snippets with the given keys at their active version, keys without a snippet are skipped.
+    pub async fn get_snippets_by_keys(&self, keys: &[String]) -> Result<Vec<SnippetRow>> {
+        let keys = serde_json::to_string(keys)?;
+        let snippets = sqlx::query_as!(
+            SnippetRow,
+            r#"
+            SELECT
+                s.id,
+                s.key,
+                s.description,
+                sv.content,
+                sv.id as version_id,
+                sv.version_number,
+                s.created_at,
+                s.updated_at
+            FROM snippet s
+            JOIN snippet_version sv ON s.current_snippet_version_id = sv.id
+            WHERE s.key IN (SELECT value FROM json_each(?))
+            ORDER BY s.key
+            "#,
+            keys
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(snippets)
+    }
+
+    /// Updates the key/description and stores the content as a new active version.
+    pub async fn update_snippet(
+        &self,
+        id: i64,
+        key: &str,
+        description: Option<&str>,
+        content: &str,
+    ) -> Result<bool> {
+        let mut tx = self.pool.begin().await?;
+
+        let latest_version: Option<i64> = sqlx::query!(
+            r#"
+            SELECT MAX(version_number) as "version_number: i64"
+            FROM snippet_version
+            WHERE snippet_id = ?
+            "#,
+            id
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        .and_then(|row| row.version_number);
+
+        let Some(latest_version) = latest_version else {
+            return Ok(false);
+        };
+        let next_version = latest_version + 1;
+
+        let snippet_version = sqlx::query!(
+            r#"
+            INSERT INTO snippet_version (snippet_id, version_number, content)
+            VALUES (?, ?, ?)
+            "#,
+            id,
+            next_version,
+            content
+        )
+        .execute(&mut *tx)
+        .await?;
+        let snippet_version_id = snippet_version.last_insert_rowid();
+
+        let affected = sqlx::query!(
+            r#"
+            UPDATE snippet
+            SET key = ?,
+                description = ?,
+                current_snippet_version_id = ?,
+                updated_at = CURRENT_TIMESTAMP
+            WHERE id = ?
+            "#,
+            key,
+            description,
+            snippet_version_id,
+            id
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        tx.commit().await?;
+
+        Ok(affected > 0)
+    }
+
+    pub async fn delete_snippet(&self, id: i64) -> Result<bool> {
+        let mut tx = self.pool.begin().await?;
+
+        // Drop the version reference first so the versions can be removed
+        sqlx::query!(
+            r#"
+            UPDATE snippet
+            SET current_snippet_version_id = NULL
+            WHERE id = ?
+            "#,
+            id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            r#"
+            DELETE FROM snippet_version
+            WHERE snippet_id = ?
+            "#,
+            id
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let rows_affected = sqlx::query!(
+            r#"
+            DELETE FROM snippet
+            WHERE id = ?
+            "#,
+            id
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        tx.commit().await?;
+
+        Ok(rows_affected > 0)
+    }
+
+    pub async fn get_snippet_versions(&self, snippet_id: i64) -> Result<Vec<SnippetRow>> {
+        let versions = sqlx::query_as!(
+            SnippetRow,
+            r#"
+            SELECT
+                s.id,
+                s.key,
+                s.description,
+                sv.content,
+                sv.id as version_id,
+                sv.version_number,
+                sv.created_at,
+                s.updated_at
+            FROM snippet s
+            JOIN snippet_version sv ON sv.snippet_id = s.id
+            WHERE s.id = ?
+            ORDER BY sv.version_number DESC
+            "#,
+            snippet_id
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(versions)
+    }
+
+    pub async fn set_active_snippet_version(&self, snippet_id: i64, version_id: i64) -> Result<SnippetRow> {
+        // Verify the version belongs to this snippet
+        let version_count = sqlx::query!(
+            r#"
+            SELECT COUNT(*) as count
+            FROM snippet_version
+            WHERE id = ? AND snippet_id = ?
+            "#,
+            version_id,
+            snippet_id
+        )
+        .fetch_one(&self.pool)
+        .await?
+        .count;
+
+        if version_count == 0 {
+            anyhow::bail!("Version not found or does not belong to this snippet");
+        }
+
+        sqlx::query!(
+            r#"
+            UPDATE snippet
+            SET current_snippet_version_id = ?,
+                updated_at = CURRENT_TIMESTAMP
+            WHERE id = ?
+            "#,
+            version_id,
+            snippet_id
+        )
+        .execute(&self.pool)
+        .await?;
+
+        self.get_snippet(snippet_id).await
+    }
+
+    /// Returns the templates of every prompt version so callers can scan them for snippet usage.
+    pub async fn get_prompt_version_templates(&self) -> Result<Vec<PromptVersionTemplateRow>> {
+        let rows = sqlx::query_as!(
+            PromptVersionTemplateRow,
+            r#"
+            SELECT
+                p.id as prompt_id,
+                p.key as prompt_key,
+                pv.id as version_id,
+                pv.version_number,
+                COALESCE(p.current_prompt_version_id = pv.id, 0) as "is_active!: bool",
+                pv.system,
+                pv.user,
+                pv.messages
+            FROM prompt_version pv
+            JOIN prompt p ON pv.prompt_id = p.id
+            ORDER BY p.key, pv.version_number DESC
+            "#
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows)
+    }
+}
@@ -0,0 +1,22 @@
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct EvalJobRow {
+    pub id: i64,
+    pub prompt_id: i64,
+    pub prompt_version_id: i64,
+    pub rounds: i64,
+    /// `queued`, `running`, `completed`, `failed` or `cancelled`
+    pub status: String,
+    /// Number of cases, every eval once per round
+    pub total: i64,
+    pub completed: i64,
+    pub failed: i64,
+    /// Last case error, or why the job failed
+    pub error: Option<String>,
+    pub created_by: Option<i64>,
+    pub started_at: Option<chrono::NaiveDateTime>,
+    pub finished_at: Option<chrono::NaiveDateTime>,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+    /// JSON array of `EvalMatrixCell`s the job runs, `prompt_version_id` is the first cell's
+    pub matrix: Option<String>,
+}
@@ -0,0 +1,12 @@
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct FeedbackRow {
+    pub id: i64,
+    pub log_id: i64,
+    pub score: Option<f64>,
+    pub label: Option<String>,
+    pub comment: Option<String>,
+    /// API key the feedback was sent with
+    pub api_key_id: Option<i64>,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+}
@@ -1,3 +1,4 @@
+use serde::Deserialize;
 use sqlx::prelude::FromRow;
 
 #[derive(Debug, Clone, FromRow)]
@@ -9,6 +10,15 @@ pub struct LogRow {
     pub input_tokens: Option<i64>,
     pub output_tokens: Option<i64>,
     pub reasoning_tokens: Option<i64>,
+    pub cached_input_tokens: Option<i64>,
+    /// USD, `None` when the model had no price or the provider reported no usage
+    pub cost: Option<f64>,
+    pub started_at: Option<chrono::NaiveDateTime>,
+    pub duration_ms: Option<i64>,
+    /// Only set for streamed responses
+    pub first_token_ms: Option<i64>,
+    /// 1 for the first try at the request
+    pub attempt: Option<i64>,
     pub response_data: Option<String>,
     pub request_body: Option<String>,
     pub provider_response_id: String,
@@ -26,8 +36,211 @@ pub struct LogRowModel {
     pub input_tokens: Option<i64>,
     pub output_tokens: Option<i64>,
     pub reasoning_tokens: Option<i64>,
+    pub cached_input_tokens: Option<i64>,
+    /// USD, `None` when the model had no price or the provider reported no usage
+    pub cost: Option<f64>,
+    pub started_at: Option<chrono::NaiveDateTime>,
+    pub duration_ms: Option<i64>,
+    /// Only set for streamed responses
+    pub first_token_ms: Option<i64>,
+    /// 1 for the first try at the request
+    pub attempt: Option<i64>,
+    /// The caller's id for its end user, see `ClientMetadata`
+    pub client_user_id: Option<String>,
+    pub session_id: Option<String>,
+    /// JSON array of strings
+    pub tags: Option<String>,
     pub response_data: Option<String>,
     pub request_body: Option<String>,
     pub provider_response_id: String,
     pub created_at: Option<chrono::NaiveDateTime>,
 }
+
+/// An attempt at a request to be logged. Only the model is required, failed attempts log what
+/// they got as far as.
+#[derive(Debug, Clone, Default)]
+pub struct NewLog<'a> {
+    pub prompt_id: Option<i64>,
+    pub prompt_version_id: Option<i64>,
+    pub model_id: i64,
+    pub response_data: Option<&'a str>,
+    pub status_code: Option<i64>,
+    pub input_tokens: Option<i64>,
+    pub output_tokens: Option<i64>,
+    pub reasoning_tokens: Option<i64>,
+    pub cached_input_tokens: Option<i64>,
+    pub cost: Option<f64>,
+    pub api_key_id: Option<i64>,
+    pub started_at: Option<chrono::NaiveDateTime>,
+    pub duration_ms: Option<i64>,
+    pub first_token_ms: Option<i64>,
+    pub attempt: Option<i64>,
+    pub client_user_id: Option<&'a str>,
+    pub session_id: Option<&'a str>,
+    /// JSON array of strings
+    pub tags: Option<&'a str>,
+    pub request_body: Option<&'a str>,
+    pub provider_response_id: &'a str,
+}
+
+/// What cost totals are broken down by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CostGroup {
+    Prompt,
+    Model,
+    ApiKey,
+    Day,
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct CostTotalRow {
+    /// Id of the prompt, model or API key, or the `YYYY-MM-DD` day. `None` for requests made
+    /// without an API key
+    pub key: Option<String>,
+    pub name: Option<String>,
+    pub request_count: i64,
+    pub input_tokens: i64,
+    pub cached_input_tokens: i64,
+    pub output_tokens: i64,
+    pub reasoning_tokens: i64,
+    pub cost: f64,
+    /// Requests without a cost, made against a model with no price at the time
+    pub unpriced_count: i64,
+}
+
+/// What latency percentiles are broken down by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LatencyGroup {
+    Prompt,
+    Model,
+    Provider,
+}
+
+/// Latency of a group's request attempts. Percentiles are nearest-rank, in milliseconds.
+#[derive(Debug, Clone, FromRow)]
+pub struct LatencyStatsRow {
+    /// Id of the prompt, model or provider
+    pub key: Option<String>,
+    pub name: Option<String>,
+    pub request_count: i64,
+    pub error_count: i64,
+    /// Attempts that were retries of an earlier failed one
+    pub retry_count: i64,
+    pub avg_duration_ms: Option<f64>,
+    pub p50_duration_ms: Option<i64>,
+    pub p90_duration_ms: Option<i64>,
+    pub p95_duration_ms: Option<i64>,
+    pub p99_duration_ms: Option<i64>,
+    pub stream_count: i64,
+    pub p50_first_token_ms: Option<i64>,
+    pub p90_first_token_ms: Option<i64>,
+    pub p95_first_token_ms: Option<i64>,
+    pub p99_first_token_ms: Option<i64>,
+}
+
+/// Conditions a log has to meet to be found by a search, unset ones match every log.
+#[derive(Debug, Clone, Default)]
+pub struct LogFilter {
+    pub prompt_key: Option<String>,
+    /// Version number of the prompt, only meaningful along with `prompt_key`
+    pub prompt_version: Option<i64>,
+    pub model: Option<String>,
+    pub provider: Option<String>,
+    pub status_code: Option<i64>,
+    pub api_key_id: Option<i64>,
+    pub client_user_id: Option<String>,
+    pub session_id: Option<String>,
+    /// A tag the request has to carry
+    pub tag: Option<String>,
+    /// Inclusive bounds on when the request was logged, in UTC
+    pub from: Option<chrono::NaiveDateTime>,
+    pub to: Option<chrono::NaiveDateTime>,
+    /// Input plus output tokens
+    pub min_tokens: Option<i64>,
+    pub max_tokens: Option<i64>,
+    pub min_duration_ms: Option<i64>,
+    pub max_duration_ms: Option<i64>,
+    /// Words that all have to appear in the request or response body
+    pub text: Option<String>,
+}
+
+/// What searched logs are ordered by, ties are broken by id.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LogSort {
+    #[default]
+    CreatedAt,
+    Duration,
+    Tokens,
+    Cost,
+}
+
+impl LogSort {
+    /// Logs without a duration or cost sort as if it were -1, so they can still be paged through.
+    pub fn key_expression(self) -> &'static str {
+        match self {
+            // Ids grow with the time logs were written in
+            LogSort::CreatedAt => "l.id",
+            LogSort::Duration => "COALESCE(l.duration_ms, -1)",
+            LogSort::Tokens => "COALESCE(l.input_tokens, 0) + COALESCE(l.output_tokens, 0)",
+            LogSort::Cost => "COALESCE(l.cost, -1)",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    Asc,
+    #[default]
+    Desc,
+}
+
+/// Position after the last log of a page, as its sort key and id.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LogCursor {
+    pub key: f64,
+    pub id: i64,
+}
+
+impl std::fmt::Display for LogCursor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}_{}", self.key, self.id)
+    }
+}
+
+impl std::str::FromStr for LogCursor {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (key, id) = s
+            .rsplit_once('_')
+            .ok_or_else(|| anyhow::anyhow!("Malformed cursor '{}'", s))?;
+        Ok(LogCursor { key: key.parse()?, id: id.parse()? })
+    }
+}
+
+/// A log found by a search, with its sort key so the next page can start after it.
+#[derive(Debug, Clone, FromRow)]
+pub struct LogSearchRow {
+    #[sqlx(flatten)]
+    pub log: LogRowModel,
+    pub sort_key: f64,
+}
+
+/// The requests logged under one session id, newest activity first.
+#[derive(Debug, Clone, FromRow)]
+pub struct SessionRow {
+    pub session_id: String,
+    /// Latest user id the session's requests came with
+    pub client_user_id: Option<String>,
+    pub request_count: i64,
+    pub error_count: i64,
+    pub input_tokens: i64,
+    pub output_tokens: i64,
+    pub cost: f64,
+    pub started_at: Option<chrono::NaiveDateTime>,
+    pub last_request_at: Option<chrono::NaiveDateTime>,
+}
@@ -7,3 +7,6 @@ pub mod providers;
 pub mod api_key;
 pub mod user;
 pub mod tool;
+pub mod snippet;
+pub mod eval_job;
+pub mod feedback;
@@ -13,3 +13,15 @@ pub struct ModelProviderRow {
     pub is_reasoning: bool,
     pub created_at: chrono::NaiveDateTime
 }
+
+#[derive(sqlx::FromRow, Debug, Clone)]
+pub struct ModelPriceRow {
+    pub id: i64,
+    pub model_id: i64,
+    pub input_per_million: f64,
+    pub output_per_million: f64,
+    pub reasoning_per_million: Option<f64>,
+    pub cached_input_per_million: Option<f64>,
+    pub effective_from: chrono::NaiveDateTime,
+    pub created_at: chrono::NaiveDateTime
+}
@@ -1,5 +1,7 @@
 use sqlx::FromRow;
 
+use super::models::ModelProviderRow;
+
 #[derive(Debug, Clone, FromRow)]
 pub struct PromptRow {
     pub id: i64,
@@ -44,4 +46,34 @@ pub struct PromptRowWithModel {
     pub user_diff: Option<String>,
     pub created_at: chrono::NaiveDateTime,
     pub updated_at: chrono::NaiveDateTime,
+    /// JSON array of message templates, used instead of `system`/`user` when set
+    pub messages: Option<String>,
+    pub messages_diff: Option<String>,
+    /// Managed from a prompts directory, so edits through the UI/API are rejected
+    pub read_only: bool,
+    pub change_message: Option<String>,
+    /// User that created the version, `None` for versions created by imports and syncs
+    pub created_by: Option<i64>,
+    /// Waiting for approval, can't be made active yet
+    pub is_draft: bool,
+    pub approved_by: Option<i64>,
+    pub approved_at: Option<chrono::NaiveDateTime>,
+    /// New versions of this prompt start as drafts
+    pub requires_approval: bool,
+}
+
+impl PromptRowWithModel {
+    /// Swaps the model (and its provider/capabilities) this prompt version executes against,
+    /// keeping the templates and generation params as-is.
+    pub fn with_model(mut self, model: ModelProviderRow) -> Self {
+        self.model_id = model.id;
+        self.model_name = model.model_name;
+        self.provider_name = model.provider_name.into();
+        self.provider_base_url = model.provider_base_url;
+        self.supports_json = model.supports_json;
+        self.supports_json_schema = model.supports_json_schema;
+        self.supports_tools = model.supports_tools;
+        self.is_reasoning = model.is_reasoning;
+        self
+    }
 }
@@ -8,4 +8,19 @@ pub struct PromptEval {
     pub evaluation_type: String,
     pub created_at: chrono::NaiveDateTime,
     pub updated_at: chrono::NaiveDateTime,
+    pub expected_output: Option<String>,
+    /// JSON array of `EvalAssertion`s
+    pub assertions: Option<String>,
+    /// JSON array of the conversation's messages, for multi-turn evals
+    pub messages: Option<String>,
+}
+
+/// The judge that grades every eval of a prompt, see `services::eval_judge`.
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct PromptEvalJudgeRow {
+    pub prompt_id: i64,
+    pub judge_prompt_key: String,
+    pub rubric: String,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
 }
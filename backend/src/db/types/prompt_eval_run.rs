@@ -9,6 +9,36 @@ pub struct PromptEvalRun {
     pub output: String,
     pub created_at: chrono::NaiveDateTime,
     pub updated_at: chrono::NaiveDateTime,
+    pub judge_score: Option<i64>,
+    pub judge_rationale: Option<String>,
+    /// Log row of the judge's call
+    pub judge_log_id: Option<i64>,
+    /// Set instead of the judge's score when grading failed
+    pub judge_error: Option<String>,
+    /// Set when the run came from an eval job
+    pub eval_job_id: Option<i64>,
+    /// Model the run executed against, which differs from the version's under a model override
+    pub model_id: Option<i64>,
+    pub log_id: Option<i64>,
+    pub latency_ms: Option<i64>,
+    /// Usage of the run's call, from its log row
+    pub input_tokens: Option<i64>,
+    pub output_tokens: Option<i64>,
+    pub reasoning_tokens: Option<i64>,
+    /// USD, `None` when the model had no price
+    pub cost: Option<f64>,
+}
+
+#[derive(sqlx::FromRow, Debug)]
+pub struct PromptEvalRunAssertionRow {
+    pub id: i64,
+    pub prompt_eval_run_id: i64,
+    pub position: i64,
+    /// JSON of the `EvalAssertion` as it was when the run was scored
+    pub assertion: String,
+    pub passed: bool,
+    pub message: Option<String>,
+    pub created_at: chrono::NaiveDateTime,
 }
 
 #[derive(Debug, sqlx::FromRow)]
@@ -18,5 +48,51 @@ pub struct PromptEvalVersionPerformance {
     pub version_date: chrono::NaiveDateTime,
     pub avg_score: Option<f64>,
     pub run_count: i64,
+    /// Eval calls of the version, runs that share a call count once
+    pub call_count: i64,
+    pub avg_latency_ms: Option<f64>,
+    pub avg_input_tokens: Option<f64>,
+    pub avg_output_tokens: Option<f64>,
+    pub avg_reasoning_tokens: Option<f64>,
+    /// USD, over the calls that were priced
+    pub avg_cost: Option<f64>,
+    pub total_cost: Option<f64>,
+    /// End-user feedback on the version's completions
+    pub feedback_count: i64,
+    pub avg_feedback_score: Option<f64>,
 }
 
+
+/// Totals of one (version, model) cell of an eval job.
+#[derive(Debug, sqlx::FromRow)]
+pub struct EvalMatrixCellTotals {
+    pub prompt_version_id: i64,
+    pub model_id: Option<i64>,
+    pub run_count: i64,
+    pub scored_count: i64,
+    pub avg_score: Option<f64>,
+    pub assertions_passed: Option<i64>,
+    pub assertions_total: Option<i64>,
+    /// LLM calls, a response with text and tool calls is stored as two runs of one call
+    pub call_count: Option<i64>,
+    pub avg_latency_ms: Option<f64>,
+    pub input_tokens: Option<i64>,
+    pub output_tokens: Option<i64>,
+    pub reasoning_tokens: Option<i64>,
+    /// USD, over the calls that were priced
+    pub cost: Option<f64>,
+}
+
+/// Totals of one eval case across a version's runs.
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct EvalCaseTotals {
+    pub prompt_eval_id: i64,
+    pub prompt_eval_name: String,
+    pub run_count: i64,
+    pub scored_count: i64,
+    pub avg_score: Option<f64>,
+    pub assertions_passed: Option<i64>,
+    pub assertions_total: Option<i64>,
+    /// Output of the case's most recent run
+    pub latest_output: String,
+}
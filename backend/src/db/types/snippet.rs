@@ -0,0 +1,27 @@
+use sqlx::FromRow;
+
+#[derive(Debug, Clone, FromRow)]
+pub struct SnippetRow {
+    pub id: i64,
+    pub key: String,
+    pub description: Option<String>,
+    pub content: String,
+    pub version_id: i64,
+    pub version_number: i64,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: chrono::NaiveDateTime,
+}
+
+/// The raw templates of a single prompt version, used to work out which snippets it pulls in.
+#[derive(Debug, Clone, FromRow)]
+pub struct PromptVersionTemplateRow {
+    pub prompt_id: i64,
+    pub prompt_key: String,
+    pub version_id: i64,
+    pub version_number: i64,
+    pub is_active: bool,
+    pub system: String,
+    pub user: Option<String>,
+    /// JSON array of message templates
+    pub messages: Option<String>,
+}
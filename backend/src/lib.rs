@@ -0,0 +1,162 @@
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+};
+use metrics_exporter_prometheus::PrometheusHandle;
+use moka::future::Cache;
+
+use db::{init::DbData, types::prompt::PromptRowWithModel};
+use services::{
+    eval_jobs::{EvalJobs, DEFAULT_EVAL_CONCURRENCY},
+    metrics::install_recorder,
+    types::llm_error::LlmError,
+};
+
+pub mod common;
+pub mod controllers;
+pub mod db;
+pub mod services;
+pub mod middleware;
+pub mod utils;
+
+// APP STATE
+#[derive(Clone)]
+pub struct AppState {
+    pub db: DbData,
+    pub prompt_cache: Cache<i64, PromptRowWithModel>,
+    pub jwt_secret: String,
+    pub eval_jobs: EvalJobs,
+    pub metrics: PrometheusHandle,
+    pub metrics_token: Option<String>,
+}
+
+impl AppState {
+    pub async fn new(data: DbData) -> Self {
+        let prompt_cache: Cache<i64, PromptRowWithModel> = Cache::new(500);
+        let jwt_secret = std::env::var("JWT_SECRET").expect("Missing JWT_SECRET from env vars");
+        let eval_concurrency = std::env::var("EVAL_CONCURRENCY")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_EVAL_CONCURRENCY);
+        let metrics = install_recorder().expect("Failed to install the metrics recorder");
+        let metrics_token = std::env::var("METRICS_TOKEN").ok().filter(|t| !t.is_empty());
+
+        AppState {
+            db: data,
+            prompt_cache,
+            jwt_secret,
+            eval_jobs: EvalJobs::new(eval_concurrency),
+            metrics,
+            metrics_token,
+        }
+    }
+}
+
+// ANYHOW ERROR HANDLING
+#[allow(dead_code)]
+#[derive(Debug)]
+pub enum AppError {
+    Unauthorized(String),
+    BadRequest(String),
+    NotFound(String),
+    Conflict(String),
+    InternalServerError(String),
+    TooManyRequests(String),
+    Forbidden(String),
+    Other(anyhow::Error),
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        match self {
+            AppError::Unauthorized(e) => {
+                tracing::error!("Unauthorized | error: {}", e);
+                return (StatusCode::UNAUTHORIZED, format!("Unauthorized: {:?}", e))
+                    .into_response();
+            }
+            AppError::BadRequest(e) => {
+                tracing::error!("Bad Request | error: {}", e);
+                return (StatusCode::BAD_REQUEST, format!("Bad Request: {:?}", e)).into_response();
+            }
+            AppError::NotFound(e) => {
+                tracing::error!("Not Found | error: {}", e);
+                return (StatusCode::NOT_FOUND, format!("{}", e)).into_response();
+            }
+            AppError::Conflict(e) => {
+                tracing::error!("Conflict | error: {}", e);
+                return (StatusCode::CONFLICT, format!("{}", e)).into_response();
+            }
+            AppError::InternalServerError(e) => {
+                tracing::error!("Internal server error | error: {}", e);
+                return (StatusCode::INTERNAL_SERVER_ERROR, format!("{}", e)).into_response();
+            }
+            AppError::TooManyRequests(e) => {
+                tracing::error!("Too many requests | error: {}", e);
+                return (StatusCode::TOO_MANY_REQUESTS, format!("{}", e)).into_response();
+            }
+            AppError::Forbidden(e) => {
+                tracing::error!("Forbidden | error: {}", e);
+                return (StatusCode::FORBIDDEN, format!("{}", e)).into_response();
+            }
+            AppError::Other(e) => {
+                tracing::error!("Internal Server Error | error: {}", e);
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    format!("Internal Server Error"),
+                )
+                    .into_response();
+            }
+        }
+    }
+}
+
+impl From<anyhow::Error> for AppError {
+    fn from(err: anyhow::Error) -> Self {
+        AppError::Other(err)
+    }
+}
+
+impl From<LlmError> for AppError {
+    fn from(err: LlmError) -> Self {
+        match err {
+            // Auth errors
+            LlmError::Auth(msg) => AppError::Unauthorized(msg),
+            LlmError::InvalidApiKey => AppError::Unauthorized("API key invalid or expired".to_string()),
+            LlmError::InsufficientPermissions => AppError::Forbidden("Insufficient permissions".to_string()),
+            
+            // Rate limits
+            LlmError::RateLimit(msg) => AppError::TooManyRequests(msg),
+            LlmError::ProviderQuotaExceeded => AppError::TooManyRequests("Provider quota exceeded".to_string()),
+            
+            // Not found errors
+            LlmError::ModelNotFound(msg) => AppError::NotFound(msg),
+            LlmError::NotFound(msg) => AppError::NotFound(msg),
+            
+            // Bad request errors
+            LlmError::MissingField(msg) => AppError::BadRequest(format!("Missing field: {}", msg)),
+            LlmError::InvalidRole(msg) => AppError::BadRequest(format!("Invalid role: {}", msg)),
+            LlmError::UnsupportedMode(mode, context) => AppError::BadRequest(format!("{} not supported in {}", mode, context)),
+            LlmError::MissingSystemMessage => AppError::BadRequest("Missing system message".to_string()),
+            LlmError::MissingUserMessage => AppError::BadRequest("Missing user message".to_string()),
+            LlmError::PromptTooLong(current, limit) => AppError::BadRequest(format!("Prompt exceeds token limit: {}/{}", current, limit)),
+            LlmError::ContentPolicy(msg) => AppError::BadRequest(format!("Content policy violation: {}", msg)),
+            LlmError::InvalidConfig(msg) => AppError::BadRequest(format!("Invalid configuration: {}", msg)),
+            
+            // All network/http errors map to internal server error
+            LlmError::Http(status) => {
+                if status.as_u16() == 429 {
+                    AppError::TooManyRequests(format!("HTTP status {}", status))
+                } else if status.as_u16() == 404 {
+                    AppError::NotFound(format!("Resource not found (HTTP {})", status))
+                } else if status.as_u16() >= 400 && status.as_u16() < 500 {
+                    AppError::BadRequest(format!("HTTP error: {}", status))
+                } else {
+                    AppError::InternalServerError(format!("HTTP error: {}", status))
+                }
+            },
+            
+            // All other errors map to internal server error
+            _ => AppError::InternalServerError(format!("{}", err)),
+        }
+    }
+}
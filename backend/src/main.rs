@@ -1,35 +1,49 @@
 use axum::{
-    http::StatusCode,
-    response::{IntoResponse, Response},
     routing::{delete, get, post, put},
     Router,
 };
 use axum::middleware as axum_middleware;
 
-use middleware::auth::{self, user_auth_middleware};
-use services::types::llm_error::LlmError;
+use backend::{
+    db::init::DbData,
+    middleware::auth::{self, user_auth_middleware},
+    services, AppState,
+};
 use tower_cookies::CookieManagerLayer;
-use tracing_subscriber;
 
 use anyhow::Result;
-use controllers::{
+use backend::controllers::{
     api_keys::{create_api_key, delete_api_key, list_api_keys},
-    logs::{get_log, get_log_by_provider_id, get_logs_count, list_logs},
-    models::{list_models, create_model, update_model},
+    bundles::{export_prompt_bundle, import_prompt_bundle},
+    eval_jobs::{
+        cancel_eval_job, create_eval_job, create_eval_matrix_job, get_eval_job, get_eval_job_matrix,
+        get_eval_job_runs, get_eval_jobs_by_prompt, stream_eval_job_events,
+    },
+    feedback::create_feedback,
+    metrics::render_metrics,
+    logs::{get_log, get_log_by_provider_id, get_logs_count, list_logs, search_logs, list_sessions, get_session, get_cost_totals, get_latency_stats},
+    models::{list_models, create_model, update_model, list_model_prices, create_model_price, delete_model_price},
     providers::{list_providers, update_provider},
     prompt_eval::{
-        create_eval_test, delete_eval_test, get_eval_test_by_id, get_eval_test_by_prompt,
+        create_eval_test, delete_eval_judge, delete_eval_test, get_eval_judge, get_eval_test_by_id,
+        get_eval_test_by_prompt, import_eval_tests, promote_log_to_eval, set_eval_judge,
         update_eval_test,
     },
     prompt_eval_run::{
-        execute_eval_run, get_eval_performance_by_prompt_id, get_eval_run_by_id,
+        execute_eval_run, get_eval_performance_by_prompt_id, get_eval_regression, get_eval_run_by_id,
         get_eval_runs_by_prompt_version, update_eval_run_score,
     },
     prompts::{
-        api_completions, create_prompt, delete_prompt, get_prompt, 
-        get_prompt_versions, list_prompts, set_active_version, update_prompt
+        api_completions, approve_prompt_version, compare_prompt_models, create_prompt, delete_prompt, 
+        get_prompt, get_prompt_version_diff, get_prompt_versions, list_prompts, set_active_version, 
+        set_prompt_requires_approval, update_prompt
     }, 
     schema::validate_schema,
+    snippets::{
+        create_snippet, delete_snippet, get_snippet, get_snippet_dependents, get_snippet_versions,
+        list_snippets, set_active_snippet_version, update_snippet
+    },
+    templates::list_template_filters,
     tools::{
         associate_tool_with_prompt_version, create_tool, delete_tool, 
         get_prompt_versions_by_tool, get_tool, get_tools_by_prompt_version, 
@@ -38,16 +52,6 @@ use controllers::{
     user::{login, register, me},
 };
 
-use db::{init::DbData, types::prompt::PromptRowWithModel};
-use moka::future::Cache;
-
-pub mod common;
-pub mod controllers;
-pub mod db;
-pub mod services;
-pub mod middleware;
-pub mod utils;
-
 #[tokio::main]
 async fn main() -> Result<()> {
     dotenv::dotenv().ok();
@@ -55,14 +59,31 @@ async fn main() -> Result<()> {
     let database_url = std::env::var("DATABASE_URL")?;
     let log_level = std::env::var("RUST_LOG").unwrap_or("info".to_string());
 
-    tracing_subscriber::fmt().with_env_filter(log_level).init();
+    let tracer_provider = services::telemetry::init_tracing(&log_level)?;
 
     let data = DbData::new(&database_url).await?;
+
+    // Optionally reconcile prompts kept as files in a repo before serving
+    if let Ok(prompts_dir) = std::env::var("PROMPTS_DIR") {
+        let read_only = std::env::var("PROMPTS_READ_ONLY")
+            .map(|v| v == "true")
+            .unwrap_or(false);
+
+        services::prompt_sync::sync_prompts_dir(&data, std::path::Path::new(&prompts_dir), read_only).await?;
+    }
+
+    // Eval jobs only live in this process, anything left over was cut off by a restart
+    let interrupted = data.eval_job.fail_interrupted().await?;
+    if interrupted > 0 {
+        tracing::warn!("Marked {} interrupted eval jobs as failed", interrupted);
+    }
+
     let app_state = AppState::new(data).await;
 
     // API routes that require API key auth
     let api_routes = Router::new()
         .route("/chat/completions", post(api_completions))
+        .route("/feedback", post(create_feedback))
         .layer(axum_middleware::from_fn_with_state(
             app_state.clone(),
             auth::api_key_middleware,
@@ -83,22 +104,46 @@ async fn main() -> Result<()> {
         .route("/ui/prompts", post(create_prompt).get(list_prompts))
         .route("/ui/prompts/{id}", get(get_prompt).put(update_prompt).delete(delete_prompt))
         .route("/ui/prompts/{id}/versions", get(get_prompt_versions))
+        .route("/ui/prompts/{id}/diff", get(get_prompt_version_diff))
         .route("/ui/prompts/{prompt_id}/set-version/{version_id}", put(set_active_version))
+        .route("/ui/prompts/{prompt_id}/versions/{version_id}/approve", post(approve_prompt_version))
+        .route("/ui/prompts/{id}/require-approval", put(set_prompt_requires_approval))
+        .route("/ui/prompts/{id}/compare", post(compare_prompt_models))
+        .route("/ui/prompts/{id}/export", get(export_prompt_bundle))
+        .route("/ui/prompts/import", post(import_prompt_bundle))
         .route("/ui/prompts/{id}/prompt-evals", get(get_eval_test_by_prompt))
+        .route("/ui/prompts/{id}/prompt-evals/import", post(import_eval_tests))
         .route("/ui/prompts/{id}/performance", get(get_eval_performance_by_prompt_id))
+        .route("/ui/prompts/{id}/eval-regression", get(get_eval_regression))
+        .route("/ui/prompts/{id}/eval-judge", get(get_eval_judge).put(set_eval_judge).delete(delete_eval_judge))
         .route("/ui/prompts/execute", post(api_completions))
         .route("/ui/prompt-evals", post(create_eval_test))
         .route("/ui/prompt-evals/{id}", get(get_eval_test_by_id).put(update_eval_test).delete(delete_eval_test))
         .route("/ui/prompt-eval-runs/{prompt_id}/version/{prompt_version_id}", post(execute_eval_run).get(get_eval_runs_by_prompt_version))
         .route("/ui/prompt-eval-runs/{id}",get(get_eval_run_by_id).put(update_eval_run_score))
+        .route("/ui/prompt-eval-runs/{prompt_id}/version/{prompt_version_id}/jobs", post(create_eval_job))
+        .route("/ui/prompts/{id}/eval-jobs", get(get_eval_jobs_by_prompt).post(create_eval_matrix_job))
+        .route("/ui/eval-jobs/{id}", get(get_eval_job))
+        .route("/ui/eval-jobs/{id}/runs", get(get_eval_job_runs))
+        .route("/ui/eval-jobs/{id}/matrix", get(get_eval_job_matrix))
+        .route("/ui/eval-jobs/{id}/events", get(stream_eval_job_events))
+        .route("/ui/eval-jobs/{id}/cancel", post(cancel_eval_job))
         .route("/ui/models", get(list_models).post(create_model))
         .route("/ui/models/{id}", put(update_model))
+        .route("/ui/models/{id}/prices", get(list_model_prices).post(create_model_price))
+        .route("/ui/models/{id}/prices/{price_id}", delete(delete_model_price))
         .route("/ui/providers", get(list_providers))
         .route("/ui/providers/{id}", put(update_provider))
         .route("/ui/logs", get(list_logs))
         .route("/ui/logs/count", get(get_logs_count))
+        .route("/ui/logs/search", get(search_logs))
+        .route("/ui/costs/{group}", get(get_cost_totals))
+        .route("/ui/latency/{group}", get(get_latency_stats))
         .route("/ui/logs/provider/{provider_id}", get(get_log_by_provider_id))
         .route("/ui/logs/{trace_id}", get(get_log))
+        .route("/ui/logs/{trace_id}/promote-to-eval", post(promote_log_to_eval))
+        .route("/ui/sessions", get(list_sessions))
+        .route("/ui/sessions/{session_id}", get(get_session))
         .route("/ui/schema/validate", post(validate_schema))
         .route("/ui/tools", post(create_tool).get(list_tools))
         .route("/ui/tools/{id}", get(get_tool).put(update_tool).delete(delete_tool))
@@ -106,11 +151,18 @@ async fn main() -> Result<()> {
         .route("/ui/tools/disassociate", post(remove_tool_prompt_version_association))
         .route("/ui/tools/{id}/prompts", get(get_prompt_versions_by_tool))
         .route("/ui/prompts/versions/{id}/tools", get(get_tools_by_prompt_version))
+        .route("/ui/snippets", post(create_snippet).get(list_snippets))
+        .route("/ui/snippets/{id}", get(get_snippet).put(update_snippet).delete(delete_snippet))
+        .route("/ui/snippets/{id}/versions", get(get_snippet_versions))
+        .route("/ui/snippets/{snippet_id}/set-version/{version_id}", put(set_active_snippet_version))
+        .route("/ui/snippets/{id}/dependents", get(get_snippet_dependents))
+        .route("/ui/templates/filters", get(list_template_filters))
         .layer(axum_middleware::from_fn_with_state(app_state.clone(), user_auth_middleware))
         .layer(CookieManagerLayer::new());
 
-    // Combine all routes into the main router
+    // Combine all routes into the main router, metrics sit at the root where Prometheus looks
     let router = Router::new()
+        .route("/metrics", get(render_metrics))
         .nest(
             "/v1",
             Router::new()
@@ -127,139 +179,16 @@ async fn main() -> Result<()> {
     println!("listening on {}", listener.local_addr().unwrap());
     axum::serve(listener, router).await.unwrap();
 
-    Ok(())
-}
-
-async fn api_version_handler() -> &'static str {
-    "llmkit api v0.1"
-}
-
-// APP STATE
-#[derive(Clone)]
-pub struct AppState {
-    pub db: DbData,
-    pub prompt_cache: Cache<i64, PromptRowWithModel>,
-    pub jwt_secret: String
-}
-
-impl AppState {
-    pub async fn new(data: DbData) -> Self {
-        let prompt_cache: Cache<i64, PromptRowWithModel> = Cache::new(500);
-        let jwt_secret = std::env::var("JWT_SECRET").expect("Missing JWT_SECRET from env vars");
-
-        AppState {
-            db: data,
-            prompt_cache,
-            jwt_secret
+    // Flush the spans still waiting in the batch exporter
+    if let Some(provider) = tracer_provider {
+        if let Err(e) = provider.shutdown() {
+            tracing::error!("Failed to flush traces: {}", e);
         }
     }
-}
 
-// ANYHOW ERROR HANDLING
-#[allow(dead_code)]
-#[derive(Debug)]
-pub enum AppError {
-    Unauthorized(String),
-    BadRequest(String),
-    NotFound(String),
-    Conflict(String),
-    InternalServerError(String),
-    TooManyRequests(String),
-    Forbidden(String),
-    Other(anyhow::Error),
-}
-
-impl IntoResponse for AppError {
-    fn into_response(self) -> Response {
-        match self {
-            AppError::Unauthorized(e) => {
-                tracing::error!("Unauthorized | error: {}", e);
-                return (StatusCode::UNAUTHORIZED, format!("Unauthorized: {:?}", e))
-                    .into_response();
-            }
-            AppError::BadRequest(e) => {
-                tracing::error!("Bad Request | error: {}", e);
-                return (StatusCode::BAD_REQUEST, format!("Bad Request: {:?}", e)).into_response();
-            }
-            AppError::NotFound(e) => {
-                tracing::error!("Not Found | error: {}", e);
-                return (StatusCode::NOT_FOUND, format!("{}", e)).into_response();
-            }
-            AppError::Conflict(e) => {
-                tracing::error!("Conflict | error: {}", e);
-                return (StatusCode::CONFLICT, format!("{}", e)).into_response();
-            }
-            AppError::InternalServerError(e) => {
-                tracing::error!("Internal server error | error: {}", e);
-                return (StatusCode::INTERNAL_SERVER_ERROR, format!("{}", e)).into_response();
-            }
-            AppError::TooManyRequests(e) => {
-                tracing::error!("Too many requests | error: {}", e);
-                return (StatusCode::TOO_MANY_REQUESTS, format!("{}", e)).into_response();
-            }
-            AppError::Forbidden(e) => {
-                tracing::error!("Forbidden | error: {}", e);
-                return (StatusCode::FORBIDDEN, format!("{}", e)).into_response();
-            }
-            AppError::Other(e) => {
-                tracing::error!("Internal Server Error | error: {}", e);
-                return (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    format!("Internal Server Error"),
-                )
-                    .into_response();
-            }
-        }
-    }
-}
-
-impl From<anyhow::Error> for AppError {
-    fn from(err: anyhow::Error) -> Self {
-        AppError::Other(err)
-    }
+    Ok(())
 }
 
-impl From<LlmError> for AppError {
-    fn from(err: LlmError) -> Self {
-        match err {
-            // Auth errors
-            LlmError::Auth(msg) => AppError::Unauthorized(msg),
-            LlmError::InvalidApiKey => AppError::Unauthorized("API key invalid or expired".to_string()),
-            LlmError::InsufficientPermissions => AppError::Forbidden("Insufficient permissions".to_string()),
-            
-            // Rate limits
-            LlmError::RateLimit(msg) => AppError::TooManyRequests(msg),
-            LlmError::ProviderQuotaExceeded => AppError::TooManyRequests("Provider quota exceeded".to_string()),
-            
-            // Not found errors
-            LlmError::ModelNotFound(msg) => AppError::NotFound(msg),
-            LlmError::NotFound(msg) => AppError::NotFound(msg),
-            
-            // Bad request errors
-            LlmError::MissingField(msg) => AppError::BadRequest(format!("Missing field: {}", msg)),
-            LlmError::InvalidRole(msg) => AppError::BadRequest(format!("Invalid role: {}", msg)),
-            LlmError::UnsupportedMode(mode, context) => AppError::BadRequest(format!("{} not supported in {}", mode, context)),
-            LlmError::MissingSystemMessage => AppError::BadRequest("Missing system message".to_string()),
-            LlmError::MissingUserMessage => AppError::BadRequest("Missing user message".to_string()),
-            LlmError::PromptTooLong(current, limit) => AppError::BadRequest(format!("Prompt exceeds token limit: {}/{}", current, limit)),
-            LlmError::ContentPolicy(msg) => AppError::BadRequest(format!("Content policy violation: {}", msg)),
-            LlmError::InvalidConfig(msg) => AppError::BadRequest(format!("Invalid configuration: {}", msg)),
-            
-            // All network/http errors map to internal server error
-            LlmError::Http(status) => {
-                if status.as_u16() == 429 {
-                    AppError::TooManyRequests(format!("HTTP status {}", status))
-                } else if status.as_u16() == 404 {
-                    AppError::NotFound(format!("Resource not found (HTTP {})", status))
-                } else if status.as_u16() >= 400 && status.as_u16() < 500 {
-                    AppError::BadRequest(format!("HTTP error: {}", status))
-                } else {
-                    AppError::InternalServerError(format!("HTTP error: {}", status))
-                }
-            },
-            
-            // All other errors map to internal server error
-            _ => AppError::InternalServerError(format!("{}", err)),
-        }
-    }
+async fn api_version_handler() -> &'static str {
+    "llmkit api v0.1"
 }
@@ -12,9 +12,12 @@ use jsonwebtoken::{decode, DecodingKey, Validation};
 
 use crate::{AppError, AppState};
 
+#[derive(Debug, Clone)]
+pub struct ApiKeyId(pub i64);
+
 pub async fn api_key_middleware(
     State(state): State<AppState>,
-    req: Request<Body>,
+    mut req: Request<Body>,
     next: Next,
 ) -> Result<Response, AppError> {
     let auth_header = req
@@ -31,20 +34,21 @@ pub async fn api_key_middleware(
             }
 
             // Verify API key
-            let is_valid = state
+            let key = state
                 .db
                 .api_key
-                .verify_any_api_key(api_key)
+                .find_api_key_by_key(api_key)
                 .await
                 .map_err(|e| {
                     AppError::InternalServerError(format!("Failed to verify API key: {}", e))
                 })?;
 
-            if !is_valid {
+            let Some(key) = key else {
                 return Err(AppError::Unauthorized("Invalid API key".to_string()));
-            }
+            };
 
-            // API key is valid, proceed
+            // API key is valid, proceed with it attached so usage can be attributed to it
+            req.extensions_mut().insert(ApiKeyId(key.id));
             Ok(next.run(req).await)
         }
         _ => Err(AppError::Unauthorized("API key is required".to_string())),
@@ -0,0 +1,699 @@
+use std::collections::{BTreeMap, HashMap};
+
+use anyhow::Result;
+
+use crate::db::{init::DbData, types::prompt::PromptRowWithModel};
+
+use super::types::{
+    bundle::{
+        BundleConflict, BundleEval, BundleImportAction, BundleImportReport, BundlePrompt,
+        BundlePromptImport, BundlePromptVersion, BundleTool, BundleToolImport, PromptBundle,
+        BUNDLE_FORMAT_VERSION,
+    },
+    eval_assertion::EvalAssertion,
+};
+
+/// Exports a prompt with all of its versions, the tools they use and its evals.
+pub async fn export_prompt(db: &DbData, prompt_id: i64) -> Result<PromptBundle> {
+    let prompt = db.prompt.get_prompt(prompt_id).await?;
+
+    let mut versions = db.prompt.get_prompt_versions(prompt_id).await?;
+    versions.sort_by_key(|v| v.version_number);
+
+    let mut tools = BTreeMap::new();
+    let mut bundle_versions = Vec::with_capacity(versions.len());
+    for version in versions {
+        let version_tools = db.tool.get_tools_by_prompt_version(version.version_id).await?;
+
+        let mut tool_names = Vec::with_capacity(version_tools.len());
+        for tool in version_tools {
+            tool_names.push(tool.name.clone());
+            tools.entry(tool.name.clone()).or_insert(BundleTool {
+                name: tool.name,
+                tool_name: tool.tool_name,
+                description: tool.description,
+                parameters: serde_json::from_str(&tool.parameters).unwrap_or_default(),
+                strict: tool.strict,
+            });
+        }
+
+        bundle_versions.push(BundlePromptVersion {
+            version_number: version.version_number,
+            provider: version.provider_name,
+            model: version.model_name,
+            system: version.system,
+            user: version.user,
+            messages: version.messages.and_then(|m| serde_json::from_str(&m).ok()),
+            max_tokens: version.max_tokens,
+            temperature: version.temperature,
+            json_mode: version.json_mode,
+            json_schema: version.json_schema,
+            prompt_type: version.prompt_type,
+            is_chat: version.is_chat,
+            reasoning_effort: version.reasoning_effort,
+            tools: tool_names,
+        });
+    }
+
+    let evals = db
+        .prompt_eval
+        .get_by_prompt(prompt_id)
+        .await?
+        .into_iter()
+        .map(|e| BundleEval {
+            name: e.name,
+            evaluation_type: e.evaluation_type,
+            system_prompt_input: e.system_prompt_input,
+            user_prompt_input: e.user_prompt_input,
+            expected_output: e.expected_output,
+            assertions: e
+                .assertions
+                .and_then(|a| serde_json::from_str(&a).ok())
+                .unwrap_or_default(),
+            messages: e
+                .messages
+                .and_then(|m| serde_json::from_str(&m).ok())
+                .unwrap_or_default(),
+        })
+        .collect();
+
+    Ok(PromptBundle {
+        format_version: BUNDLE_FORMAT_VERSION,
+        tools: tools.into_values().collect(),
+        prompts: vec![BundlePrompt {
+            key: prompt.key,
+            active_version: prompt.version_number,
+            versions: bundle_versions,
+            evals,
+        }],
+    })
+}
+
+/// Checks that a bundle was written in a format this version can import.
+pub fn validate_bundle(bundle: &PromptBundle) -> Result<(), String> {
+    if bundle.format_version != BUNDLE_FORMAT_VERSION {
+        return Err(format!(
+            "Unsupported bundle format version {} (expected {})",
+            bundle.format_version, BUNDLE_FORMAT_VERSION
+        ));
+    }
+
+    Ok(())
+}
+
+/// Imports a bundle, creating or merging prompts by `key` and tools by `name`.
+///
+/// Nothing that already exists is overwritten: prompt versions are only appended when no
+/// existing version has the same content, and a tool or eval that exists with different
+/// content is reported as a conflict and left as-is. A prompt whose models are missing on this
+/// instance is skipped. With `dry_run` nothing is written and the report describes what would happen.
+///
+/// The whole import runs in one transaction, if any step fails nothing of the bundle is kept.
+pub async fn import_bundle(
+    db: &DbData,
+    bundle: PromptBundle,
+    dry_run: bool,
+) -> Result<BundleImportReport> {
+    // A dry run only reads, so it doesn't hold the write lock while it checks the bundle
+    if dry_run {
+        return import_bundle_with(db, bundle, true).await;
+    }
+
+    let tx = db.begin().await?;
+
+    match import_bundle_with(&tx.db, bundle, false).await {
+        Ok(report) => {
+            tx.commit().await?;
+            Ok(report)
+        }
+        Err(e) => {
+            tx.rollback().await?;
+            Err(e)
+        }
+    }
+}
+
+async fn import_bundle_with(
+    db: &DbData,
+    bundle: PromptBundle,
+    dry_run: bool,
+) -> Result<BundleImportReport> {
+    let mut report = BundleImportReport {
+        dry_run,
+        tools: Vec::new(),
+        prompts: Vec::new(),
+        conflicts: Vec::new(),
+    };
+
+    // Tool ids by name, `None` for tools a dry run would create
+    let existing_tools = db.tool.list_tools().await?;
+    let mut tool_ids: HashMap<String, Option<i64>> = existing_tools
+        .iter()
+        .map(|t| (t.name.clone(), Some(t.id)))
+        .collect();
+
+    for tool in &bundle.tools {
+        let action = match existing_tools.iter().find(|t| t.name == tool.name) {
+            Some(existing) => {
+                let existing_parameters =
+                    serde_json::from_str::<serde_json::Value>(&existing.parameters).unwrap_or_default();
+
+                if existing.tool_name == tool.tool_name
+                    && existing.description == tool.description
+                    && existing_parameters == tool.parameters
+                    && existing.strict == tool.strict
+                {
+                    BundleImportAction::Unchanged
+                } else {
+                    report.conflicts.push(BundleConflict {
+                        kind: "tool".to_string(),
+                        key: tool.name.clone(),
+                        message: "A tool with this name already exists with a different definition, keeping the existing one".to_string(),
+                    });
+                    BundleImportAction::Skipped
+                }
+            }
+            None => {
+                let id = if dry_run {
+                    None
+                } else {
+                    Some(
+                        db.tool
+                            .create_tool(
+                                &tool.name,
+                                &tool.tool_name,
+                                &tool.description,
+                                &tool.parameters.to_string(),
+                                tool.strict,
+                            )
+                            .await?,
+                    )
+                };
+                tool_ids.insert(tool.name.clone(), id);
+                BundleImportAction::Created
+            }
+        };
+
+        report.tools.push(BundleToolImport {
+            name: tool.name.clone(),
+            action,
+        });
+    }
+
+    let models: HashMap<(String, String), i64> = db
+        .model
+        .list_models()
+        .await?
+        .into_iter()
+        .map(|m| ((String::from(m.provider_name), m.model_name), m.id))
+        .collect();
+
+    // key -> (id, read only, requires approval)
+    let existing_prompts: HashMap<String, (i64, bool, bool)> = db
+        .prompt
+        .list_prompts()
+        .await?
+        .into_iter()
+        .map(|p| (p.key, (p.id, p.read_only, p.requires_approval)))
+        .collect();
+
+    for prompt in bundle.prompts {
+        let existing = match existing_prompts.get(&prompt.key) {
+            Some((id, true, _)) => {
+                report.conflicts.push(BundleConflict {
+                    kind: "prompt".to_string(),
+                    key: prompt.key.clone(),
+                    message: "Prompt is managed from the prompts directory and is read-only".to_string(),
+                });
+                report.prompts.push(BundlePromptImport {
+                    key: prompt.key,
+                    prompt_id: Some(*id),
+                    action: BundleImportAction::Skipped,
+                    versions_created: 0,
+                    active_version_number: None,
+                    evals_created: 0,
+                });
+                continue;
+            }
+            Some((id, false, requires_approval)) => Some((*id, *requires_approval)),
+            None => None,
+        };
+        let result = import_prompt(
+            db,
+            prompt,
+            existing,
+            &models,
+            &tool_ids,
+            dry_run,
+            &mut report.conflicts,
+        )
+        .await?;
+
+        report.prompts.push(result);
+    }
+
+    Ok(report)
+}
+
+/// `existing` is the id of the prompt with the same key and whether it requires approval, in
+/// which case new versions are created as drafts and never made active.
+async fn import_prompt(
+    db: &DbData,
+    mut prompt: BundlePrompt,
+    existing: Option<(i64, bool)>,
+    models: &HashMap<(String, String), i64>,
+    tool_ids: &HashMap<String, Option<i64>>,
+    dry_run: bool,
+    conflicts: &mut Vec<BundleConflict>,
+) -> Result<BundlePromptImport> {
+    let existing_prompt_id = existing.map(|(id, _)| id);
+    let draft = existing.is_some_and(|(_, requires_approval)| requires_approval);
+
+    let mut result = BundlePromptImport {
+        key: prompt.key.clone(),
+        prompt_id: existing_prompt_id,
+        action: BundleImportAction::Skipped,
+        versions_created: 0,
+        active_version_number: None,
+        evals_created: 0,
+    };
+
+    prompt.versions.sort_by_key(|v| v.version_number);
+
+    if !prompt.versions.iter().any(|v| v.version_number == prompt.active_version) {
+        conflicts.push(BundleConflict {
+            kind: "prompt".to_string(),
+            key: prompt.key.clone(),
+            message: format!("Active version {} is not part of the bundle", prompt.active_version),
+        });
+        return Ok(result);
+    }
+
+    // Every model has to exist here before anything is written
+    let mut model_ids = Vec::with_capacity(prompt.versions.len());
+    for version in &prompt.versions {
+        match models.get(&(version.provider.clone(), version.model.clone())) {
+            Some(id) => model_ids.push(*id),
+            None => {
+                conflicts.push(BundleConflict {
+                    kind: "model".to_string(),
+                    key: prompt.key.clone(),
+                    message: format!(
+                        "Model '{}' from provider '{}' used by version {} does not exist",
+                        version.model, version.provider, version.version_number
+                    ),
+                });
+                return Ok(result);
+            }
+        }
+    }
+
+    for tool in prompt.versions.iter().flat_map(|v| &v.tools) {
+        if !tool_ids.contains_key(tool) {
+            conflicts.push(BundleConflict {
+                kind: "tool".to_string(),
+                key: prompt.key.clone(),
+                message: format!("Tool '{}' is neither in the bundle nor on this instance, it won't be attached", tool),
+            });
+        }
+    }
+
+    let (existing_versions, current_version_id) = match existing_prompt_id {
+        Some(id) => (
+            db.prompt.get_prompt_versions(id).await?,
+            Some(db.prompt.get_prompt(id).await?.version_id),
+        ),
+        None => (Vec::new(), None),
+    };
+    let mut next_version_number = existing_versions
+        .iter()
+        .map(|v| v.version_number)
+        .max()
+        .unwrap_or(0)
+        + 1;
+
+    let mut prompt_id = existing_prompt_id;
+    let mut active_version: Option<(i64, Option<i64>, bool)> = None;
+
+    for (version, model_id) in prompt.versions.iter().zip(model_ids) {
+        let matched = existing_versions
+            .iter()
+            .find(|existing| version_matches(version, model_id, existing));
+
+        // (version number, version id, is draft) on this instance
+        let target = match matched {
+            Some(existing) => (existing.version_number, Some(existing.version_id), existing.is_draft),
+            None => {
+                let version_number = next_version_number;
+                next_version_number += 1;
+                result.versions_created += 1;
+
+                let version_id = if dry_run {
+                    None
+                } else {
+                    let (id, version_id) = create_version(
+                        db,
+                        prompt_id,
+                        &prompt.key,
+                        version,
+                        model_id,
+                        "Imported from bundle",
+                        draft,
+                    )
+                    .await?;
+                    prompt_id = Some(id);
+
+                    for tool in &version.tools {
+                        if let Some(Some(tool_id)) = tool_ids.get(tool) {
+                            db.tool
+                                .associate_tool_with_prompt_version(*tool_id, version_id)
+                                .await?;
+                        }
+                    }
+
+                    Some(version_id)
+                };
+
+                (version_number, version_id, draft)
+            }
+        };
+
+        if version.version_number == prompt.active_version {
+            active_version = Some(target);
+        }
+    }
+
+    let (active_version_number, active_version_id, active_is_draft) =
+        active_version.expect("active version was checked to be part of the bundle");
+    result.active_version_number = Some(active_version_number);
+
+    // A version created by a dry run has no id yet, so it can only differ from the current one
+    let mut active_changed = match (current_version_id, active_version_id) {
+        (Some(current), Some(target)) => current != target,
+        _ => true,
+    };
+
+    if active_changed && active_is_draft {
+        conflicts.push(BundleConflict {
+            kind: "prompt".to_string(),
+            key: prompt.key.clone(),
+            message: format!(
+                "Prompt requires approval, version {} is a draft and wasn't made active",
+                active_version_number
+            ),
+        });
+        active_changed = false;
+    }
+
+    // Appending versions moves the active version to the newest one, so always set it back
+    if !dry_run && (active_changed || (!draft && result.versions_created > 0)) {
+        if let (Some(id), Some(version_id)) = (prompt_id, active_version_id) {
+            db.prompt.set_active_prompt_version(id, version_id).await?;
+        }
+    }
+
+    result.evals_created = import_evals(db, &prompt, prompt_id, dry_run, conflicts).await?;
+    result.prompt_id = prompt_id;
+    result.action = if existing_prompt_id.is_none() {
+        BundleImportAction::Created
+    } else if result.versions_created > 0 || active_changed || result.evals_created > 0 {
+        BundleImportAction::Updated
+    } else {
+        BundleImportAction::Unchanged
+    };
+
+    Ok(result)
+}
+
+async fn import_evals(
+    db: &DbData,
+    prompt: &BundlePrompt,
+    prompt_id: Option<i64>,
+    dry_run: bool,
+    conflicts: &mut Vec<BundleConflict>,
+) -> Result<i64> {
+    let existing_evals = match prompt_id {
+        Some(id) => db.prompt_eval.get_by_prompt(id).await?,
+        None => Vec::new(),
+    };
+
+    let mut created = 0;
+    for eval in &prompt.evals {
+        match existing_evals.iter().find(|e| e.name == eval.name) {
+            Some(existing) => {
+                if existing.system_prompt_input != eval.system_prompt_input
+                    || existing.user_prompt_input != eval.user_prompt_input
+                    || existing.evaluation_type != eval.evaluation_type
+                    || existing.expected_output != eval.expected_output
+                    || existing
+                        .assertions
+                        .as_deref()
+                        .and_then(|a| serde_json::from_str::<Vec<EvalAssertion>>(a).ok())
+                        .unwrap_or_default()
+                        != eval.assertions
+                    || existing
+                        .messages
+                        .as_deref()
+                        .and_then(|m| serde_json::from_str::<serde_json::Value>(m).ok())
+                        != (!eval.messages.is_empty()).then(|| serde_json::json!(eval.messages))
+                {
+                    conflicts.push(BundleConflict {
+                        kind: "eval".to_string(),
+                        key: prompt.key.clone(),
+                        message: format!(
+                            "Eval '{}' already exists with different inputs, keeping the existing one",
+                            eval.name
+                        ),
+                    });
+                }
+            }
+            None => {
+                if let (false, Some(id)) = (dry_run, prompt_id) {
+                    db.prompt_eval
+                        .create(
+                            id,
+                            eval.system_prompt_input.clone(),
+                            eval.user_prompt_input.clone(),
+                            &eval.evaluation_type,
+                            Some(eval.name.clone()),
+                            eval.expected_output.clone(),
+                            (!eval.assertions.is_empty())
+                                .then(|| serde_json::to_string(&eval.assertions))
+                                .transpose()?,
+                            (!eval.messages.is_empty())
+                                .then(|| serde_json::to_string(&eval.messages))
+                                .transpose()?,
+                        )
+                        .await?;
+                }
+                created += 1;
+            }
+        }
+    }
+
+    Ok(created)
+}
+
+/// Creates the prompt on its first version, or appends a version to it. Returns the prompt id
+/// and the id of the new version.
+pub async fn create_version(
+    db: &DbData,
+    prompt_id: Option<i64>,
+    key: &str,
+    version: &BundlePromptVersion,
+    model_id: i64,
+    change_message: &str,
+    draft: bool,
+) -> Result<(i64, i64)> {
+    let messages = version
+        .messages
+        .as_ref()
+        .map(serde_json::to_string)
+        .transpose()?;
+
+    match prompt_id {
+        Some(id) => {
+            let version_id = db
+                .prompt
+                .update_prompt(
+                    id,
+                    key,
+                    &version.system,
+                    version.user.as_deref().unwrap_or(""),
+                    model_id,
+                    version.max_tokens,
+                    version.temperature,
+                    version.json_mode,
+                    version.json_schema.as_deref(),
+                    &version.prompt_type,
+                    version.is_chat,
+                    version.reasoning_effort.as_deref(),
+                    messages.as_deref(),
+                    Some(change_message),
+                    None,
+                    draft,
+                )
+                .await?
+                .ok_or_else(|| anyhow::anyhow!("Prompt not found"))?;
+            Ok((id, version_id))
+        }
+        None => {
+            let id = db
+                .prompt
+                .create_prompt(
+                    key,
+                    &version.system,
+                    version.user.as_deref().unwrap_or(""),
+                    model_id,
+                    version.max_tokens,
+                    version.temperature,
+                    version.json_mode,
+                    version.json_schema.as_deref(),
+                    &version.prompt_type,
+                    version.is_chat,
+                    version.reasoning_effort.as_deref(),
+                    messages.as_deref(),
+                    Some(change_message),
+                    None,
+                )
+                .await?;
+            Ok((id, db.prompt.get_prompt(id).await?.version_id))
+        }
+    }
+}
+
+/// Whether an existing version has the same content as a bundle version. Tool access isn't
+/// compared, matched versions keep whatever tools they already have.
+pub fn version_matches(
+    version: &BundlePromptVersion,
+    model_id: i64,
+    existing: &PromptRowWithModel,
+) -> bool {
+    let existing_messages = existing
+        .messages
+        .as_deref()
+        .and_then(|m| serde_json::from_str::<serde_json::Value>(m).ok());
+    let messages = version
+        .messages
+        .as_ref()
+        .and_then(|m| serde_json::to_value(m).ok());
+
+    existing.model_id == model_id
+        && existing.system == version.system
+        && existing.user.as_deref().unwrap_or("") == version.user.as_deref().unwrap_or("")
+        && existing_messages == messages
+        && existing.max_tokens == version.max_tokens
+        && existing.temperature == version.temperature
+        && existing.json_mode == version.json_mode
+        && existing.json_schema == version.json_schema
+        && existing.prompt_type == version.prompt_type
+        && existing.is_chat == version.is_chat
+        && existing.reasoning_effort == version.reasoning_effort
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Creates a prompt with one tool and one eval, returning its id.
+    async fn seed_prompt(db: &DbData, key: &str) -> i64 {
+        let model_id = db.model.list_models().await.unwrap()[0].id;
+        let prompt_id = db
+            .prompt
+            .create_prompt(
+                key, "You are {{ name }}.", "", model_id, 256, 0.2, false, None,
+                "dynamic_system", false, None, None, None, None,
+            )
+            .await
+            .unwrap();
+        let version_id = db.prompt.get_prompt(prompt_id).await.unwrap().version_id;
+
+        let tool_id = db
+            .tool
+            .create_tool("bundle-weather", "get_weather", "Weather by city", r#"{"type":"object"}"#, false)
+            .await
+            .unwrap();
+        db.tool.associate_tool_with_prompt_version(tool_id, version_id).await.unwrap();
+
+        db.prompt_eval
+            .create(
+                prompt_id,
+                Some(r#"{"name":"Ada"}"#.to_string()),
+                String::new(),
+                "human",
+                Some("ada".to_string()),
+                None,
+                None,
+                None,
+            )
+            .await
+            .unwrap();
+
+        prompt_id
+    }
+
+    #[tokio::test]
+    async fn test_export_import_round_trip() {
+        let source = DbData::in_memory().await.unwrap();
+        let prompt_id = seed_prompt(&source, "bundle-round-trip").await;
+        let bundle = export_prompt(&source, prompt_id).await.unwrap();
+
+        let target = DbData::in_memory().await.unwrap();
+        let preview = import_bundle(&target, bundle.clone(), true).await.unwrap();
+        assert!(matches!(preview.prompts[0].action, BundleImportAction::Created));
+        assert!(target.prompt.get_prompt_by_key("bundle-round-trip").await.is_err());
+
+        let report = import_bundle(&target, bundle.clone(), false).await.unwrap();
+        assert!(report.conflicts.is_empty(), "{:?}", report.conflicts);
+        assert!(matches!(report.prompts[0].action, BundleImportAction::Created));
+        assert_eq!(report.prompts[0].evals_created, 1);
+
+        let imported_id = target.prompt.get_prompt_by_key("bundle-round-trip").await.unwrap().id;
+        let exported = export_prompt(&target, imported_id).await.unwrap();
+        assert_eq!(
+            serde_json::to_value(&exported).unwrap(),
+            serde_json::to_value(&bundle).unwrap()
+        );
+
+        // Importing the same bundle again changes nothing
+        let report = import_bundle(&target, bundle, false).await.unwrap();
+        assert!(matches!(report.prompts[0].action, BundleImportAction::Unchanged));
+        assert_eq!(report.prompts[0].versions_created, 0);
+        assert_eq!(report.prompts[0].evals_created, 0);
+    }
+
+    #[tokio::test]
+    async fn test_import_rejections() {
+        let source = DbData::in_memory().await.unwrap();
+        let prompt_id = seed_prompt(&source, "bundle-rejected").await;
+        let bundle = export_prompt(&source, prompt_id).await.unwrap();
+
+        let mut unsupported = bundle.clone();
+        unsupported.format_version = BUNDLE_FORMAT_VERSION + 1;
+        assert!(validate_bundle(&unsupported).is_err());
+        assert!(validate_bundle(&bundle).is_ok());
+
+        let target = DbData::in_memory().await.unwrap();
+
+        // A failing write rolls back everything written before it, tool included
+        let mut failing = bundle.clone();
+        failing.prompts[0].evals[0].evaluation_type = "invalid".to_string();
+        assert!(import_bundle(&target, failing, false).await.is_err());
+        assert!(target.prompt.get_prompt_by_key("bundle-rejected").await.is_err());
+        assert!(!target
+            .tool
+            .list_tools()
+            .await
+            .unwrap()
+            .iter()
+            .any(|t| t.name == "bundle-weather"));
+
+        // A model missing on this instance skips the prompt
+        let mut missing_model = bundle;
+        missing_model.prompts[0].versions[0].model = "missing/model".to_string();
+        let report = import_bundle(&target, missing_model, false).await.unwrap();
+        assert!(matches!(report.prompts[0].action, BundleImportAction::Skipped));
+        assert!(report.conflicts.iter().any(|c| c.kind == "model"));
+        assert!(target.prompt.get_prompt_by_key("bundle-rejected").await.is_err());
+    }
+}
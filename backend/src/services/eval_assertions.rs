@@ -0,0 +1,354 @@
+use regex::Regex;
+use serde_json::Value;
+
+use crate::common::types::chat_response::LlmServiceChatCompletionResponseToolCall;
+
+use super::types::eval_assertion::{AssertionResult, EvalAssertion};
+
+/// Parses the assertions stored on an eval. An eval that only has an expected output is
+/// scored by exact match against it.
+pub fn eval_assertions(
+    assertions: Option<&str>,
+    expected_output: Option<&str>,
+) -> anyhow::Result<Vec<EvalAssertion>> {
+    let assertions = match assertions {
+        Some(json) => serde_json::from_str::<Vec<EvalAssertion>>(json)?,
+        None => Vec::new(),
+    };
+
+    if assertions.is_empty() && expected_output.is_some() {
+        return Ok(vec![EvalAssertion::ExactMatch {
+            expected: None,
+            trim: true,
+        }]);
+    }
+
+    Ok(assertions)
+}
+
+/// Rejects assertions that could never pass, like a regex or schema that doesn't compile.
+pub fn validate_assertions(assertions: &[EvalAssertion]) -> Result<(), String> {
+    for assertion in assertions {
+        match assertion {
+            EvalAssertion::Regex { pattern } => {
+                Regex::new(pattern).map_err(|e| format!("Invalid regex {:?}: {}", pattern, e))?;
+            }
+            EvalAssertion::JsonSchemaValid { schema: Some(schema) } => {
+                jsonschema::validator_for(schema).map_err(|e| format!("Invalid JSON schema: {}", e))?;
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks every assertion against a response. `content` is the text of the response and
+/// `tool_calls` the tools it called, either may be empty.
+pub fn run_assertions(
+    assertions: &[EvalAssertion],
+    expected_output: Option<&str>,
+    prompt_json_schema: Option<&str>,
+    content: Option<&str>,
+    tool_calls: &[LlmServiceChatCompletionResponseToolCall],
+) -> Vec<AssertionResult> {
+    let text = content.unwrap_or("");
+
+    assertions
+        .iter()
+        .map(|assertion| {
+            let outcome = check(assertion, expected_output, prompt_json_schema, text, tool_calls);
+            AssertionResult {
+                assertion: assertion.clone(),
+                passed: outcome.is_ok(),
+                message: outcome.err(),
+            }
+        })
+        .collect()
+}
+
+/// Maps the share of passed assertions onto the 1-5 score used by human reviews, so automated
+/// and human runs chart together. `None` when there's nothing to score.
+pub fn assertion_score(results: &[AssertionResult]) -> Option<i64> {
+    if results.is_empty() {
+        return None;
+    }
+
+    let passed = results.iter().filter(|r| r.passed).count() as f64;
+    Some(1 + (4.0 * passed / results.len() as f64).round() as i64)
+}
+
+fn check(
+    assertion: &EvalAssertion,
+    expected_output: Option<&str>,
+    prompt_json_schema: Option<&str>,
+    text: &str,
+    tool_calls: &[LlmServiceChatCompletionResponseToolCall],
+) -> Result<(), String> {
+    match assertion {
+        EvalAssertion::ExactMatch { expected, trim } => {
+            let expected = expected
+                .as_deref()
+                .or(expected_output)
+                .ok_or("No expected value and the eval has no expected output")?;
+
+            let matches = if *trim {
+                text.trim() == expected.trim()
+            } else {
+                text == expected
+            };
+
+            if matches {
+                Ok(())
+            } else {
+                Err(format!("Expected {:?}, got {:?}", expected, text))
+            }
+        }
+        EvalAssertion::Contains { value, ignore_case } => {
+            let found = if *ignore_case {
+                text.to_lowercase().contains(&value.to_lowercase())
+            } else {
+                text.contains(value.as_str())
+            };
+
+            if found {
+                Ok(())
+            } else {
+                Err(format!("Response doesn't contain {:?}", value))
+            }
+        }
+        EvalAssertion::Regex { pattern } => {
+            let regex = Regex::new(pattern).map_err(|e| format!("Invalid regex: {}", e))?;
+            if regex.is_match(text) {
+                Ok(())
+            } else {
+                Err(format!("Response doesn't match /{}/", pattern))
+            }
+        }
+        EvalAssertion::JsonSchemaValid { schema } => {
+            let schema = match schema {
+                Some(schema) => schema.clone(),
+                None => {
+                    let schema = prompt_json_schema
+                        .ok_or("No schema given and the prompt has no JSON schema")?;
+                    serde_json::from_str(schema)
+                        .map_err(|e| format!("Prompt JSON schema is invalid: {}", e))?
+                }
+            };
+            let response = parse_json(text)?;
+
+            let validator =
+                jsonschema::validator_for(&schema).map_err(|e| format!("Invalid schema: {}", e))?;
+            let first_error = validator
+                .iter_errors(&response)
+                .next()
+                .map(|error| format!("{} at {}", error, error.instance_path));
+
+            match first_error {
+                None => Ok(()),
+                Some(error) => Err(error),
+            }
+        }
+        EvalAssertion::JsonPathEquals { path, expected } => {
+            let response = parse_json(text)?;
+            let actual = json_path(&response, path)?
+                .ok_or_else(|| format!("Nothing at {}", path))?;
+
+            if actual == expected {
+                Ok(())
+            } else {
+                Err(format!("Expected {} at {}, got {}", expected, path, actual))
+            }
+        }
+        EvalAssertion::MaxLength { max } => {
+            let length = text.chars().count();
+            if length <= *max {
+                Ok(())
+            } else {
+                Err(format!("Response is {} characters, more than {}", length, max))
+            }
+        }
+        EvalAssertion::ToolCall { name, arguments } => {
+            let calls = tool_calls
+                .iter()
+                .filter(|c| c.function_call.name.as_deref() == Some(name.as_str()))
+                .collect::<Vec<_>>();
+
+            if calls.is_empty() {
+                let called = tool_calls
+                    .iter()
+                    .filter_map(|c| c.function_call.name.as_deref())
+                    .collect::<Vec<_>>();
+                return Err(format!("Tool '{}' wasn't called (called: {:?})", name, called));
+            }
+
+            let Some(expected) = arguments else {
+                return Ok(());
+            };
+
+            let matched = calls.iter().any(|c| {
+                serde_json::from_str::<Value>(&c.function_call.arguments)
+                    .is_ok_and(|actual| json_contains(&actual, expected))
+            });
+
+            if matched {
+                Ok(())
+            } else {
+                let actual = calls
+                    .iter()
+                    .map(|c| c.function_call.arguments.as_str())
+                    .collect::<Vec<_>>();
+                Err(format!("Tool '{}' was called with {:?}, expected {}", name, actual, expected))
+            }
+        }
+    }
+}
+
+fn parse_json(text: &str) -> Result<Value, String> {
+    serde_json::from_str(text.trim()).map_err(|e| format!("Response isn't valid JSON: {}", e))
+}
+
+// Objects match when every expected field matches, everything else has to be equal
+fn json_contains(actual: &Value, expected: &Value) -> bool {
+    match (actual, expected) {
+        (Value::Object(actual), Value::Object(expected)) => expected
+            .iter()
+            .all(|(key, value)| actual.get(key).is_some_and(|a| json_contains(a, value))),
+        _ => actual == expected,
+    }
+}
+
+/// Looks up a value by JSON pointer (`/a/0`) or a simple JSON path (`$.a[0]`, `$['a b']`).
+fn json_path<'a>(value: &'a Value, path: &str) -> Result<Option<&'a Value>, String> {
+    if path.is_empty() || path.starts_with('/') {
+        return Ok(value.pointer(path));
+    }
+
+    let rest = path
+        .strip_prefix('$')
+        .ok_or_else(|| format!("Path {:?} has to start with `$` or `/`", path))?;
+    let invalid = || format!("Invalid JSON path {:?}", path);
+
+    let mut current = value;
+    let mut chars = rest.chars().peekable();
+    while let Some(c) = chars.next() {
+        let next = match c {
+            '.' => {
+                let mut key = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c == '.' || c == '[' {
+                        break;
+                    }
+                    key.push(c);
+                    chars.next();
+                }
+                if key.is_empty() {
+                    return Err(invalid());
+                }
+                current.get(&key)
+            }
+            '[' => {
+                let mut segment = String::new();
+                for c in chars.by_ref() {
+                    if c == ']' {
+                        break;
+                    }
+                    segment.push(c);
+                }
+
+                let quoted = segment
+                    .strip_prefix('\'')
+                    .and_then(|s| s.strip_suffix('\''))
+                    .or_else(|| segment.strip_prefix('"').and_then(|s| s.strip_suffix('"')));
+
+                match quoted {
+                    Some(key) => current.get(key),
+                    None => current.get(segment.parse::<usize>().map_err(|_| invalid())?),
+                }
+            }
+            _ => return Err(invalid()),
+        };
+
+        match next {
+            Some(next) => current = next,
+            None => return Ok(None),
+        }
+    }
+
+    Ok(Some(current))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::types::chat_response::LlmServiceChatCompletionResponseFunctionCall;
+    use serde_json::json;
+
+    fn tool_call(name: &str, arguments: &str) -> LlmServiceChatCompletionResponseToolCall {
+        LlmServiceChatCompletionResponseToolCall {
+            id: None,
+            index: None,
+            kind: Some("function".to_string()),
+            function_call: LlmServiceChatCompletionResponseFunctionCall {
+                name: Some(name.to_string()),
+                arguments: arguments.to_string(),
+            },
+        }
+    }
+
+    fn passed(assertion: EvalAssertion, content: &str) -> bool {
+        run_assertions(&[assertion], Some("Paris"), None, Some(content), &[])[0].passed
+    }
+
+    #[test]
+    fn test_text_assertions() {
+        assert!(passed(EvalAssertion::ExactMatch { expected: None, trim: true }, " Paris\n"));
+        assert!(!passed(EvalAssertion::ExactMatch { expected: None, trim: false }, " Paris\n"));
+        assert!(passed(
+            EvalAssertion::Contains { value: "paris".to_string(), ignore_case: true },
+            "It's Paris."
+        ));
+        assert!(passed(EvalAssertion::Regex { pattern: r"^\d{3}$".to_string() }, "123"));
+        assert!(!passed(EvalAssertion::MaxLength { max: 3 }, "Paris"));
+    }
+
+    #[test]
+    fn test_json_assertions() {
+        let response = r#"{"city": {"name": "Paris", "tags": ["capital"]}}"#;
+
+        assert!(passed(
+            EvalAssertion::JsonPathEquals { path: "$.city.tags[0]".to_string(), expected: json!("capital") },
+            response
+        ));
+        assert!(passed(
+            EvalAssertion::JsonPathEquals { path: "/city/name".to_string(), expected: json!("Paris") },
+            response
+        ));
+        assert!(passed(
+            EvalAssertion::JsonSchemaValid {
+                schema: Some(json!({ "type": "object", "required": ["city"] }))
+            },
+            response
+        ));
+        assert!(!passed(
+            EvalAssertion::JsonSchemaValid { schema: Some(json!({ "type": "array" })) },
+            response
+        ));
+        assert!(!passed(EvalAssertion::JsonSchemaValid { schema: None }, response));
+    }
+
+    #[test]
+    fn test_tool_call_assertion_and_score() {
+        let calls = [tool_call("get_weather", r#"{"city": "Paris", "unit": "c"}"#)];
+        let assertions = [
+            EvalAssertion::ToolCall { name: "get_weather".to_string(), arguments: Some(json!({ "city": "Paris" })) },
+            EvalAssertion::ToolCall { name: "get_weather".to_string(), arguments: Some(json!({ "city": "Rome" })) },
+            EvalAssertion::ToolCall { name: "book_flight".to_string(), arguments: None },
+        ];
+
+        let results = run_assertions(&assertions, None, None, None, &calls);
+        assert_eq!(results.iter().map(|r| r.passed).collect::<Vec<_>>(), vec![true, false, false]);
+        assert_eq!(assertion_score(&results), Some(2));
+        assert_eq!(assertion_score(&[]), None);
+    }
+}
@@ -0,0 +1,95 @@
+use std::collections::HashSet;
+
+use crate::common::types::chat_request::ChatCompletionRequestMessage;
+
+/// Parses the conversation stored on an eval, `None` for single-turn evals.
+pub fn eval_conversation(messages: Option<&str>) -> anyhow::Result<Option<Vec<ChatCompletionRequestMessage>>> {
+    match messages {
+        Some(json) => Ok(Some(serde_json::from_str(json)?)),
+        None => Ok(None),
+    }
+}
+
+/// A conversation eval holds the history up to the turn being evaluated, so it has to end on
+/// a user message or a tool result. The system message comes from the eval's system input.
+pub fn validate_conversation(messages: &[ChatCompletionRequestMessage]) -> Result<(), String> {
+    if !messages.iter().any(|msg| msg.is_user()) {
+        return Err("A conversation needs at least one user message".to_string());
+    }
+
+    let mut tool_call_ids = HashSet::new();
+    for (i, msg) in messages.iter().enumerate() {
+        match msg {
+            ChatCompletionRequestMessage::System { .. } => {
+                return Err(format!(
+                    "Message {} is a system message, use the system input instead",
+                    i + 1
+                ));
+            }
+            ChatCompletionRequestMessage::Assistant { tool_calls, .. } => {
+                tool_call_ids.extend(tool_calls.iter().flatten().map(|call| call.id.as_str()));
+            }
+            ChatCompletionRequestMessage::Tool { tool_call_id, .. } => {
+                if !tool_call_ids.contains(tool_call_id.as_str()) {
+                    return Err(format!(
+                        "Message {} answers tool call '{}', which no earlier assistant message made",
+                        i + 1,
+                        tool_call_id
+                    ));
+                }
+            }
+            ChatCompletionRequestMessage::User { .. } => {}
+        }
+    }
+
+    match messages.last() {
+        Some(ChatCompletionRequestMessage::User { .. } | ChatCompletionRequestMessage::Tool { .. }) => Ok(()),
+        _ => Err("A conversation has to end with a user message or a tool result".to_string()),
+    }
+}
+
+/// The last user message, stored as the eval's user input so it can be listed and judged
+/// like a single-turn eval.
+pub fn last_user_input(messages: &[ChatCompletionRequestMessage]) -> String {
+    messages
+        .iter()
+        .rev()
+        .find(|msg| msg.is_user())
+        .map(|msg| msg.user_content())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_validate_conversation() {
+        let messages = serde_json::from_value::<Vec<ChatCompletionRequestMessage>>(json!([
+            { "role": "user", "content": "What's the weather in Paris?" },
+            { "role": "assistant", "content": null, "tool_calls": [
+                { "id": "call_1", "type": "function", "function": { "name": "weather", "arguments": "{\"city\":\"Paris\"}" } }
+            ], "name": null },
+            { "role": "tool", "content": "{\"temp\": 18}", "tool_call_id": "call_1" }
+        ]))
+        .unwrap();
+        assert!(validate_conversation(&messages).is_ok());
+        assert_eq!(last_user_input(&messages), "What's the weather in Paris?");
+
+        let unanswered = serde_json::from_value::<Vec<ChatCompletionRequestMessage>>(json!([
+            { "role": "user", "content": "Hi" },
+            { "role": "tool", "content": "{}", "tool_call_id": "call_2" }
+        ]))
+        .unwrap();
+        assert!(validate_conversation(&unanswered).is_err());
+
+        let ends_on_assistant = serde_json::from_value::<Vec<ChatCompletionRequestMessage>>(json!([
+            { "role": "user", "content": "Hi" },
+            { "role": "assistant", "content": "Hello!", "tool_calls": null, "name": null }
+        ]))
+        .unwrap();
+        assert!(validate_conversation(&ends_on_assistant).is_err());
+        assert!(validate_conversation(&[]).is_err());
+    }
+}
@@ -0,0 +1,283 @@
+use std::collections::BTreeMap;
+
+use serde_json::{Map, Value};
+
+use super::{
+    eval_assertions::validate_assertions,
+    types::{
+        eval_assertion::EvalAssertion,
+        eval_import::{EvalImportCase, EvalImportFormat, EvalImportMapping, EvalImportRowError},
+        llm_service::TemplateVariables,
+    },
+};
+
+type Record = Map<String, Value>;
+
+/// Parses an uploaded dataset into eval cases for a prompt of `prompt_type`. Every row is
+/// checked, so all the problems come back at once.
+pub fn parse_eval_cases(
+    format: EvalImportFormat,
+    data: &str,
+    mapping: &EvalImportMapping,
+    prompt_type: &str,
+) -> (Vec<EvalImportCase>, Vec<EvalImportRowError>) {
+    let mut errors = Vec::new();
+    let records = match format {
+        EvalImportFormat::Csv => csv_records(data, &mut errors),
+        EvalImportFormat::Jsonl => jsonl_records(data, &mut errors),
+    };
+
+    let mut cases = Vec::with_capacity(records.len());
+    for (row, record) in records {
+        let mapping = if mapping.is_empty() {
+            mapping_by_name(&record)
+        } else {
+            mapping.clone()
+        };
+
+        match map_record(&record, &mapping, prompt_type) {
+            Ok(case) => cases.push(case),
+            Err(message) => errors.push(EvalImportRowError { row, message }),
+        }
+    }
+
+    (cases, errors)
+}
+
+/// Turns a logged request into an eval case with the variables it was rendered with, and
+/// optionally its response as the expected output.
+pub fn case_from_log(
+    request_body: &str,
+    response_data: Option<&str>,
+    use_response: bool,
+) -> Result<EvalImportCase, String> {
+    let request = serde_json::from_str::<Value>(request_body)
+        .map_err(|e| format!("Log request isn't valid JSON: {}", e))?;
+    let variables = request
+        .get("template_variables")
+        .cloned()
+        .ok_or("This log was recorded before template variables were logged")?;
+    let variables = serde_json::from_value::<TemplateVariables>(variables)
+        .map_err(|e| format!("Invalid template variables: {}", e))?;
+
+    let system_prompt_input = match variables.system {
+        Value::Object(map) if map.is_empty() => None,
+        system => Some(system),
+    };
+
+    let expected_output = if use_response {
+        let response = response_data
+            .and_then(|r| serde_json::from_str::<Value>(r).ok())
+            .ok_or("This log has no response")?;
+        let content = response
+            .pointer("/choices/0/message/content")
+            .and_then(Value::as_str)
+            .ok_or("The logged response has no text content")?;
+        Some(content.to_string())
+    } else {
+        None
+    };
+
+    Ok(EvalImportCase {
+        name: None,
+        system_prompt_input,
+        user_prompt_input: variables.user.unwrap_or(Value::String(String::new())),
+        expected_output,
+        assertions: Vec::new(),
+    })
+}
+
+fn csv_records(data: &str, errors: &mut Vec<EvalImportRowError>) -> Vec<(u64, Record)> {
+    let mut reader = csv::Reader::from_reader(data.as_bytes());
+    let headers = match reader.headers() {
+        Ok(headers) => headers.clone(),
+        Err(e) => {
+            errors.push(EvalImportRowError { row: 1, message: format!("Invalid CSV header: {}", e) });
+            return Vec::new();
+        }
+    };
+
+    let mut records = Vec::new();
+    for result in reader.records() {
+        match result {
+            Ok(row) => {
+                let line = row.position().map_or(0, |p| p.line());
+                let record = headers
+                    .iter()
+                    .zip(row.iter())
+                    .map(|(header, value)| (header.to_string(), Value::String(value.to_string())))
+                    .collect();
+                records.push((line, record));
+            }
+            Err(e) => {
+                let line = e.position().map_or(0, |p| p.line());
+                errors.push(EvalImportRowError { row: line, message: format!("Invalid CSV row: {}", e) });
+            }
+        }
+    }
+
+    records
+}
+
+fn jsonl_records(data: &str, errors: &mut Vec<EvalImportRowError>) -> Vec<(u64, Record)> {
+    let mut records = Vec::new();
+    for (i, line) in data.lines().enumerate() {
+        let row = i as u64 + 1;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        match serde_json::from_str::<Value>(line) {
+            Ok(Value::Object(record)) => records.push((row, record)),
+            Ok(_) => errors.push(EvalImportRowError { row, message: "Line isn't a JSON object".to_string() }),
+            Err(e) => errors.push(EvalImportRowError { row, message: format!("Invalid JSON: {}", e) }),
+        }
+    }
+
+    records
+}
+
+fn mapping_by_name(record: &Record) -> EvalImportMapping {
+    let has = |column: &str| record.contains_key(column).then(|| column.to_string());
+    let variables = |prefix: &str| {
+        record
+            .keys()
+            .filter_map(|column| {
+                column
+                    .strip_prefix(prefix)
+                    .map(|variable| (variable.to_string(), column.clone()))
+            })
+            .collect::<BTreeMap<_, _>>()
+    };
+
+    EvalImportMapping {
+        name: has("name"),
+        system_variables: variables("system."),
+        user_variables: variables("user."),
+        user_input: has("user"),
+        expected_output: has("expected_output"),
+        assertions: has("assertions"),
+    }
+}
+
+fn map_record(record: &Record, mapping: &EvalImportMapping, prompt_type: &str) -> Result<EvalImportCase, String> {
+    let field = |column: &str| {
+        record
+            .get(column)
+            .ok_or_else(|| format!("Column '{}' not found", column))
+    };
+    let text = |column: &Option<String>| -> Result<Option<String>, String> {
+        match column {
+            Some(column) => Ok(Some(as_text(field(column)?)).filter(|t| !t.is_empty())),
+            None => Ok(None),
+        }
+    };
+    let variables = |map: &BTreeMap<String, String>| -> Result<Value, String> {
+        map.iter()
+            .map(|(variable, column)| Ok((variable.clone(), field(column)?.clone())))
+            .collect::<Result<Map<_, _>, String>>()
+            .map(Value::Object)
+    };
+
+    let system_prompt_input = if mapping.system_variables.is_empty() {
+        None
+    } else {
+        Some(variables(&mapping.system_variables)?)
+    };
+
+    let user_prompt_input = if !mapping.user_variables.is_empty() {
+        variables(&mapping.user_variables)?
+    } else if let Some(column) = &mapping.user_input {
+        let user = as_text(field(column)?);
+        // A dynamic_both user column can hold the variables as a JSON object
+        match serde_json::from_str::<Value>(&user) {
+            Ok(value @ Value::Object(_)) if prompt_type == "dynamic_both" => value,
+            _ => Value::String(user),
+        }
+    } else {
+        return Err("No user input is mapped".to_string());
+    };
+
+    if prompt_type == "dynamic_both" && !user_prompt_input.is_object() {
+        return Err("This prompt has a user template, so the user input has to be variables".to_string());
+    }
+
+    let assertions = match &mapping.assertions {
+        Some(column) => match field(column)? {
+            Value::String(s) if s.trim().is_empty() => Vec::new(),
+            Value::String(s) => serde_json::from_str::<Vec<EvalAssertion>>(s)
+                .map_err(|e| format!("Invalid assertions: {}", e))?,
+            Value::Null => Vec::new(),
+            value => serde_json::from_value::<Vec<EvalAssertion>>(value.clone())
+                .map_err(|e| format!("Invalid assertions: {}", e))?,
+        },
+        None => Vec::new(),
+    };
+    validate_assertions(&assertions)?;
+
+    Ok(EvalImportCase {
+        name: text(&mapping.name)?,
+        system_prompt_input,
+        user_prompt_input,
+        expected_output: text(&mapping.expected_output)?,
+        assertions,
+    })
+}
+
+fn as_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_csv_and_jsonl() {
+        let csv = "name,system.tone,user.question,expected_output\n\
+                   capital,formal,\"What's the capital of France?\",Paris\n\
+                   missing,casual,,\n";
+        let (cases, errors) = parse_eval_cases(EvalImportFormat::Csv, csv, &EvalImportMapping::default(), "dynamic_both");
+        assert!(errors.is_empty());
+        assert_eq!(cases.len(), 2);
+        assert_eq!(cases[0].system_prompt_input, Some(json!({ "tone": "formal" })));
+        assert_eq!(cases[0].user_prompt_input, json!({ "question": "What's the capital of France?" }));
+        assert_eq!(cases[0].expected_output.as_deref(), Some("Paris"));
+        assert_eq!(cases[1].expected_output, None);
+
+        let mapping = EvalImportMapping {
+            user_input: Some("q".to_string()),
+            assertions: Some("checks".to_string()),
+            ..Default::default()
+        };
+        let jsonl = "{\"q\": \"Hi\", \"checks\": [{\"type\": \"max_length\", \"max\": 20}]}\n\n\
+                     {\"q\": \"Bye\", \"checks\": [{\"type\": \"regex\", \"pattern\": \"(\"}]}\n\
+                     [1, 2]\n";
+        let (cases, errors) = parse_eval_cases(EvalImportFormat::Jsonl, jsonl, &mapping, "static");
+        assert_eq!(cases.len(), 1);
+        assert_eq!(cases[0].user_prompt_input, json!("Hi"));
+        assert_eq!(errors.iter().map(|e| e.row).collect::<Vec<_>>(), vec![4, 3]);
+    }
+
+    #[test]
+    fn test_case_from_log() {
+        let request = json!({
+            "request": {},
+            "template_variables": { "system": { "tone": "formal" }, "user": { "question": "Capital?" } }
+        })
+        .to_string();
+        let response = json!({ "choices": [{ "message": { "role": "assistant", "content": "Paris" } }] }).to_string();
+
+        let case = case_from_log(&request, Some(&response), true).unwrap();
+        assert_eq!(case.system_prompt_input, Some(json!({ "tone": "formal" })));
+        assert_eq!(case.user_prompt_input, json!({ "question": "Capital?" }));
+        assert_eq!(case.expected_output.as_deref(), Some("Paris"));
+
+        assert!(case_from_log(r#"{"request": {}}"#, None, false).is_err());
+    }
+}
@@ -0,0 +1,231 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use anyhow::Result;
+use futures::{stream, StreamExt};
+use tokio::sync::{broadcast, watch, Semaphore};
+use uuid::Uuid;
+
+use crate::db::{
+    init::DbData,
+    types::{eval_job::EvalJobRow, prompt_eval::PromptEval},
+};
+
+use super::{
+    eval_runner::{run_eval_case, EvalTarget},
+    types::eval_job::EvalJobEvent,
+};
+
+pub const DEFAULT_EVAL_CONCURRENCY: usize = 4;
+
+/// Eval jobs running in this process. Cases of every job share one pool of `concurrency`
+/// slots, so several big suites can't flood a provider between them.
+#[derive(Clone)]
+pub struct EvalJobs {
+    running: Arc<Mutex<HashMap<i64, RunningJob>>>,
+    permits: Arc<Semaphore>,
+    concurrency: usize,
+}
+
+struct RunningJob {
+    cancel: watch::Sender<bool>,
+    events: broadcast::Sender<EvalJobEvent>,
+}
+
+impl EvalJobs {
+    pub fn new(concurrency: usize) -> Self {
+        let concurrency = concurrency.max(1);
+        EvalJobs {
+            running: Arc::new(Mutex::new(HashMap::new())),
+            permits: Arc::new(Semaphore::new(concurrency)),
+            concurrency,
+        }
+    }
+
+    /// Runs every eval once per round against every target in the background, storing each
+    /// result as it completes.
+    pub fn start(&self, db: DbData, job: EvalJobRow, targets: Vec<EvalTarget>, evals: Vec<PromptEval>) {
+        let (cancel_tx, cancel_rx) = watch::channel(false);
+        let (events, _) = broadcast::channel(256);
+
+        self.running.lock().expect("eval jobs lock poisoned").insert(
+            job.id,
+            RunningJob {
+                cancel: cancel_tx,
+                events: events.clone(),
+            },
+        );
+
+        let jobs = self.clone();
+        tokio::spawn(async move {
+            let job_id = job.id;
+            let outcome = jobs.run(&db, job, &targets, &evals, &events, cancel_rx).await;
+
+            let (status, error) = match outcome {
+                Ok(status) => (status, None),
+                Err(e) => {
+                    tracing::error!("Eval job {} failed: {}", job_id, e);
+                    ("failed", Some(e.to_string()))
+                }
+            };
+
+            let error = match db.eval_job.finish(job_id, status, error.as_deref()).await {
+                Ok(row) => row.error,
+                Err(e) => {
+                    tracing::error!("Failed to finish eval job {}: {}", job_id, e);
+                    error
+                }
+            };
+
+            let _ = events.send(EvalJobEvent::Done {
+                status: status.to_string(),
+                error,
+            });
+            jobs.running.lock().expect("eval jobs lock poisoned").remove(&job_id);
+        });
+    }
+
+    /// `None` when the job isn't running anymore.
+    pub fn subscribe(&self, job_id: i64) -> Option<broadcast::Receiver<EvalJobEvent>> {
+        self.running
+            .lock()
+            .expect("eval jobs lock poisoned")
+            .get(&job_id)
+            .map(|job| job.events.subscribe())
+    }
+
+    /// Stops a job from starting new cases, the ones already running still finish and are
+    /// stored. `false` when the job isn't running.
+    pub fn cancel(&self, job_id: i64) -> bool {
+        match self.running.lock().expect("eval jobs lock poisoned").get(&job_id) {
+            Some(job) => {
+                let _ = job.cancel.send(true);
+                true
+            }
+            None => false,
+        }
+    }
+
+    async fn run(
+        &self,
+        db: &DbData,
+        job: EvalJobRow,
+        targets: &[EvalTarget],
+        evals: &[PromptEval],
+        events: &broadcast::Sender<EvalJobEvent>,
+        cancel: watch::Receiver<bool>,
+    ) -> Result<&'static str> {
+        let row = db.eval_job.set_running(job.id).await?;
+        let _ = events.send(progress(&row));
+
+        // One run id per round of a target, shared by every eval in it
+        let cases = (0..job.rounds)
+            .flat_map(|round| {
+                targets.iter().flat_map(move |target| {
+                    let run_id = Uuid::new_v4().to_string();
+                    evals.iter().map(move |eval| (round, target, run_id.clone(), eval))
+                })
+            })
+            .collect::<Vec<_>>();
+
+        stream::iter(cases)
+            .for_each_concurrent(self.concurrency, |(round, target, run_id, eval)| {
+                let cancel = cancel.clone();
+                async move {
+                    let Ok(_permit) = self.permits.acquire().await else {
+                        return;
+                    };
+                    if *cancel.borrow() {
+                        return;
+                    }
+
+                    let (run_ids, error) = match run_eval_case(db, target, eval, &run_id, Some(job.id)).await {
+                        Ok(runs) => (runs.iter().map(|(run, _)| run.id).collect(), None),
+                        Err(e) => {
+                            tracing::error!("Eval job {} case {} failed: {}", job.id, eval.id, e);
+                            (Vec::new(), Some(e.to_string()))
+                        }
+                    };
+
+                    match db.eval_job.record_case(job.id, error.as_deref()).await {
+                        Ok(row) => {
+                            let _ = events.send(EvalJobEvent::Case {
+                                prompt_version_id: target.prompt.version_id,
+                                model_id: target.prompt.model_id,
+                                prompt_eval_id: eval.id,
+                                round: round + 1,
+                                run_ids,
+                                error,
+                            });
+                            let _ = events.send(progress(&row));
+                        }
+                        Err(e) => tracing::error!("Failed to record eval job {} progress: {}", job.id, e),
+                    }
+                }
+            })
+            .await;
+
+        let row = db.eval_job.get_by_id(job.id).await?.unwrap_or(row);
+        let status = if *cancel.borrow() && row.completed < row.total {
+            "cancelled"
+        } else if row.completed > 0 && row.failed == row.completed {
+            "failed"
+        } else {
+            "completed"
+        };
+
+        Ok(status)
+    }
+}
+
+pub fn progress(job: &EvalJobRow) -> EvalJobEvent {
+    EvalJobEvent::Progress {
+        status: job.status.clone(),
+        total: job.total,
+        completed: job.completed,
+        failed: job.failed,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_started_job_runs_to_done() {
+        let db = DbData::in_memory().await.unwrap();
+        let prompt = db.prompt.list_prompts().await.unwrap().remove(0);
+        let user_id = db.user.create("Test", "test@test.com", "hash").await.unwrap();
+        let job = db
+            .eval_job
+            .create(prompt.id, prompt.version_id, 1, 0, user_id, "[]")
+            .await
+            .unwrap();
+        assert_eq!(job.status, "queued");
+
+        let jobs = EvalJobs::new(1);
+        jobs.start(db.clone(), job.clone(), Vec::new(), Vec::new());
+        let mut events = jobs.subscribe(job.id).expect("job is running");
+
+        loop {
+            match events.recv().await.unwrap() {
+                EvalJobEvent::Progress { status, .. } => assert_eq!(status, "running"),
+                EvalJobEvent::Done { status, error } => {
+                    assert_eq!(status, "completed");
+                    assert_eq!(error, None);
+                    break;
+                }
+                EvalJobEvent::Case { .. } => panic!("job has no cases"),
+            }
+        }
+
+        let row = db.eval_job.get_by_id(job.id).await.unwrap().unwrap();
+        assert_eq!(row.status, "completed");
+        assert!(row.finished_at.is_some());
+
+        // A finished job can't be cancelled anymore
+        assert!(!jobs.cancel(job.id));
+    }
+}
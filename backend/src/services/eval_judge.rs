@@ -0,0 +1,166 @@
+use anyhow::{anyhow, Result};
+use serde_json::{json, Value};
+
+use crate::{
+    common::types::chat_request::{ChatCompletionRequest, ChatCompletionRequestMessage},
+    db::{
+        logs::LogRepository,
+        types::{prompt::PromptRowWithModel, prompt_eval::PromptEval, snippet::SnippetRow},
+    },
+};
+
+use super::{
+    llm::Llm,
+    types::{eval_judge::JudgeVerdict, llm_service::LlmServiceRequest},
+};
+
+/// The schema every judge answers with, whatever the judge prompt's own JSON settings are.
+pub fn judge_schema() -> Value {
+    json!({
+        "type": "object",
+        "properties": {
+            "score": { "type": "integer", "minimum": 1, "maximum": 5 },
+            "rationale": { "type": "string" }
+        },
+        "required": ["score", "rationale"],
+        "additionalProperties": false
+    })
+}
+
+/// A judge prompt has to be able to answer in JSON.
+pub fn validate_judge_prompt(judge: &PromptRowWithModel) -> Result<(), String> {
+    if !judge.supports_json {
+        return Err(format!(
+            "Judge prompt '{}' uses {}, which doesn't support JSON mode",
+            judge.key, judge.model_name
+        ));
+    }
+
+    Ok(())
+}
+
+/// Variables the judge prompt's templates can use: `rubric`, `system_input`, `user_input`,
+/// `conversation`, `output` and `expected_output`. Inputs stored as JSON are passed as JSON,
+/// `conversation` is the message history of a multi-turn eval and `null` otherwise.
+pub fn judge_context(rubric: &str, eval: &PromptEval, output: &str) -> Value {
+    let parse = |input: &str| {
+        serde_json::from_str::<Value>(input).unwrap_or_else(|_| Value::String(input.to_string()))
+    };
+
+    json!({
+        "rubric": rubric,
+        "system_input": eval.system_prompt_input.as_deref().map(parse),
+        "user_input": parse(&eval.user_prompt_input),
+        "conversation": eval.messages.as_deref().map(parse),
+        "output": output,
+        "expected_output": eval.expected_output,
+    })
+}
+
+/// Grades one eval output with the judge prompt. Returns the verdict and the judge call's log id.
+///
+/// The context goes in the system message. `dynamic_both` judges get it as the user context
+/// too, any other judge gets the output as the user message.
+pub async fn judge_output(
+    db_log: LogRepository,
+    judge: &PromptRowWithModel,
+    snippets: &[SnippetRow],
+    rubric: &str,
+    eval: &PromptEval,
+    output: &str,
+) -> Result<(JudgeVerdict, i64)> {
+    validate_judge_prompt(judge).map_err(|e| anyhow!(e))?;
+
+    let context = judge_context(rubric, eval, output).to_string();
+    let user_content = if judge.prompt_type == "dynamic_both" {
+        context.clone()
+    } else {
+        output.to_string()
+    };
+
+    // The fixed schema replaces whatever JSON settings the judge prompt has
+    let mut judge = judge.clone();
+    judge.json_mode = true;
+    judge.json_schema = Some(judge_schema().to_string());
+
+    let request = ChatCompletionRequest {
+        model: judge.key.clone(),
+        messages: vec![
+            ChatCompletionRequestMessage::System {
+                content: context,
+                name: None,
+            },
+            ChatCompletionRequestMessage::User {
+                content: user_content,
+                name: None,
+            },
+        ],
+        stream: None,
+        response_format: None,
+        tools: None,
+        provider: None,
+        models: None,
+        transforms: None,
+        max_tokens: Some(judge.max_tokens as u32),
+        temperature: Some(judge.temperature as f32),
+        llmkit: None,
+    };
+
+    let props = LlmServiceRequest::new_with_snippets(judge, request, snippets)?;
+    let (response, log_id) = Llm::new(props, db_log).json().await?;
+
+    let content = response
+        .choices
+        .first()
+        .and_then(|c| c.message.content.as_deref())
+        .ok_or_else(|| anyhow!("Judge returned no content"))?;
+
+    Ok((parse_verdict(content)?, log_id))
+}
+
+/// Parses the judge's answer. Models without strict schema support can still stray from
+/// it, so the score range is checked again here.
+pub fn parse_verdict(content: &str) -> Result<JudgeVerdict> {
+    let verdict = serde_json::from_str::<JudgeVerdict>(content.trim())
+        .map_err(|e| anyhow!("Judge answer doesn't match the score schema: {}", e))?;
+
+    if !(1..=5).contains(&verdict.score) {
+        return Err(anyhow!("Judge score {} is outside 1-5", verdict.score));
+    }
+
+    Ok(verdict)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_verdict_and_context() {
+        let verdict = parse_verdict(r#" {"score": 4, "rationale": "Mostly right"} "#).unwrap();
+        assert_eq!(verdict, JudgeVerdict { score: 4, rationale: "Mostly right".to_string() });
+        assert!(parse_verdict(r#"{"score": 7, "rationale": "Too high"}"#).is_err());
+        assert!(parse_verdict(r#"{"score": 3}"#).is_err());
+        assert!(jsonschema::is_valid(&judge_schema(), &json!({ "score": 5, "rationale": "ok" })));
+
+        let eval = PromptEval {
+            id: 1,
+            prompt_id: 1,
+            system_prompt_input: Some(r#"{"tone": "formal"}"#.to_string()),
+            user_prompt_input: "Summarize this".to_string(),
+            name: "summary".to_string(),
+            evaluation_type: "human".to_string(),
+            created_at: chrono::NaiveDateTime::default(),
+            updated_at: chrono::NaiveDateTime::default(),
+            expected_output: None,
+            assertions: None,
+            messages: None,
+        };
+        let context = judge_context("Be concise", &eval, "A summary");
+        assert_eq!(context["system_input"], json!({ "tone": "formal" }));
+        assert_eq!(context["user_input"], json!("Summarize this"));
+        assert_eq!(context["output"], json!("A summary"));
+        assert_eq!(context["expected_output"], Value::Null);
+        assert_eq!(context["conversation"], Value::Null);
+    }
+}
@@ -0,0 +1,150 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+
+use crate::db::{
+    init::DbData,
+    types::{eval_job::EvalJobRow, prompt_eval_run::EvalMatrixCellTotals},
+};
+
+use super::types::eval_matrix::{EvalMatrixCell, EvalMatrixCellReport, EvalMatrixReport};
+
+/// Every version paired with every model, or with its own model when there are no overrides.
+/// Duplicate cells are dropped.
+pub fn matrix_cells(versions: &[(i64, i64)], model_ids: &[i64]) -> Vec<EvalMatrixCell> {
+    let mut cells = Vec::new();
+    for &(prompt_version_id, version_model_id) in versions {
+        let models = if model_ids.is_empty() {
+            std::slice::from_ref(&version_model_id)
+        } else {
+            model_ids
+        };
+
+        for &model_id in models {
+            let cell = EvalMatrixCell { prompt_version_id, model_id };
+            if !cells.contains(&cell) {
+                cells.push(cell);
+            }
+        }
+    }
+
+    cells
+}
+
+/// Score, pass rate, latency and tokens for every cell of a job, in the order the cells
+/// were requested. Cells without results yet are reported empty.
+pub async fn matrix_report(db: &DbData, job: &EvalJobRow) -> Result<EvalMatrixReport> {
+    let totals = db.prompt_eval_run.get_eval_job_matrix(job.id).await?;
+
+    let mut cells = match job.matrix.as_deref() {
+        Some(matrix) => serde_json::from_str::<Vec<EvalMatrixCell>>(matrix)?,
+        None => Vec::new(),
+    };
+    // Runs of a cell that isn't listed, like runs stored before the job had a matrix
+    for t in &totals {
+        if let Some(model_id) = t.model_id {
+            let cell = EvalMatrixCell { prompt_version_id: t.prompt_version_id, model_id };
+            if !cells.contains(&cell) {
+                cells.push(cell);
+            }
+        }
+    }
+
+    let version_numbers = db
+        .prompt
+        .get_prompt_versions(job.prompt_id)
+        .await?
+        .into_iter()
+        .map(|v| (v.version_id, v.version_number))
+        .collect::<HashMap<_, _>>();
+
+    let mut model_names = HashMap::new();
+    for cell in &cells {
+        if let Some(model) = db.model.get_model_by_id(cell.model_id).await? {
+            model_names.insert(cell.model_id, model.model_name);
+        }
+    }
+
+    let cells = cells
+        .into_iter()
+        .map(|cell| {
+            let cell_totals = totals
+                .iter()
+                .find(|t| t.prompt_version_id == cell.prompt_version_id && t.model_id == Some(cell.model_id));
+            let mut report = cell_report(cell, cell_totals);
+            report.version_number = version_numbers.get(&cell.prompt_version_id).copied();
+            report.model_name = model_names.get(&cell.model_id).cloned();
+            report
+        })
+        .collect();
+
+    Ok(EvalMatrixReport {
+        eval_job_id: job.id,
+        prompt_id: job.prompt_id,
+        status: job.status.clone(),
+        cells,
+    })
+}
+
+fn cell_report(cell: EvalMatrixCell, totals: Option<&EvalMatrixCellTotals>) -> EvalMatrixCellReport {
+    let pass_rate = totals.and_then(|t| match (t.assertions_passed, t.assertions_total) {
+        (Some(passed), Some(total)) if total > 0 => Some(passed as f64 / total as f64),
+        _ => None,
+    });
+
+    EvalMatrixCellReport {
+        prompt_version_id: cell.prompt_version_id,
+        version_number: None,
+        model_id: cell.model_id,
+        model_name: None,
+        runs: totals.map_or(0, |t| t.run_count),
+        scored_runs: totals.map_or(0, |t| t.scored_count),
+        avg_score: totals.and_then(|t| t.avg_score),
+        pass_rate,
+        avg_latency_ms: totals.and_then(|t| t.avg_latency_ms),
+        input_tokens: totals.and_then(|t| t.input_tokens).unwrap_or(0),
+        output_tokens: totals.and_then(|t| t.output_tokens).unwrap_or(0),
+        reasoning_tokens: totals.and_then(|t| t.reasoning_tokens).unwrap_or(0),
+        cost: totals.and_then(|t| t.cost),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matrix_cells_and_pass_rate() {
+        let cells = matrix_cells(&[(8, 1), (9, 1)], &[]);
+        assert_eq!(
+            cells,
+            vec![
+                EvalMatrixCell { prompt_version_id: 8, model_id: 1 },
+                EvalMatrixCell { prompt_version_id: 9, model_id: 1 },
+            ]
+        );
+        assert_eq!(matrix_cells(&[(8, 1), (9, 1)], &[1, 2, 2]).len(), 4);
+
+        let totals = EvalMatrixCellTotals {
+            prompt_version_id: 9,
+            model_id: Some(2),
+            run_count: 4,
+            scored_count: 4,
+            avg_score: Some(4.5),
+            assertions_passed: Some(3),
+            assertions_total: Some(4),
+            call_count: Some(4),
+            avg_latency_ms: Some(820.0),
+            input_tokens: Some(400),
+            output_tokens: Some(120),
+            reasoning_tokens: None,
+            cost: Some(0.0042),
+        };
+        let report = cell_report(EvalMatrixCell { prompt_version_id: 9, model_id: 2 }, Some(&totals));
+        assert_eq!(report.pass_rate, Some(0.75));
+        assert_eq!(report.reasoning_tokens, 0);
+
+        let empty = cell_report(EvalMatrixCell { prompt_version_id: 8, model_id: 2 }, None);
+        assert_eq!((empty.runs, empty.avg_score, empty.pass_rate), (0, None, None));
+    }
+}
@@ -0,0 +1,219 @@
+use std::{cmp::Ordering, collections::HashMap};
+
+use anyhow::Result;
+
+use crate::db::{init::DbData, types::prompt_eval_run::EvalCaseTotals};
+
+use super::types::eval_regression::{EvalCaseComparison, EvalCaseOutcome, EvalRegressionReport};
+
+/// p-value under which a difference between two versions is reported as significant.
+pub const SIGNIFICANCE_LEVEL: f64 = 0.05;
+
+// Average scores closer than this count as a tie
+const SCORE_EPSILON: f64 = 1e-9;
+
+/// Compares a candidate version's eval results with a baseline's, case by case, over all of
+/// their runs or only the ones against `model_id`.
+pub async fn regression_report(
+    db: &DbData,
+    prompt_id: i64,
+    baseline_version_id: i64,
+    candidate_version_id: i64,
+    model_id: Option<i64>,
+) -> Result<EvalRegressionReport> {
+    let baseline = db.prompt_eval_run.get_eval_case_totals(baseline_version_id, model_id).await?;
+    let candidate = db.prompt_eval_run.get_eval_case_totals(candidate_version_id, model_id).await?;
+
+    let mut report = compare_cases(&baseline, &candidate);
+    report.prompt_id = prompt_id;
+    report.baseline_version_id = baseline_version_id;
+    report.candidate_version_id = candidate_version_id;
+    report.model_id = model_id;
+    Ok(report)
+}
+
+/// Only cases run on both versions are compared, the ids of the report are left empty.
+fn compare_cases(baseline: &[EvalCaseTotals], candidate: &[EvalCaseTotals]) -> EvalRegressionReport {
+    let baseline_by_case = baseline
+        .iter()
+        .map(|b| (b.prompt_eval_id, b))
+        .collect::<HashMap<_, _>>();
+
+    let mut cases = Vec::new();
+    let (mut baseline_assertions, mut candidate_assertions) = ((0, 0), (0, 0));
+    for c in candidate {
+        let Some(b) = baseline_by_case.get(&c.prompt_eval_id) else {
+            continue;
+        };
+
+        let score_delta = match (b.avg_score, c.avg_score) {
+            (Some(b), Some(c)) => Some(c - b),
+            _ => None,
+        };
+        let outcome = match score_delta {
+            Some(d) if d > SCORE_EPSILON => EvalCaseOutcome::Win,
+            Some(d) if d < -SCORE_EPSILON => EvalCaseOutcome::Loss,
+            Some(_) => EvalCaseOutcome::Tie,
+            None => EvalCaseOutcome::Unscored,
+        };
+
+        let baseline_pass_rate = pass_rate(b);
+        let candidate_pass_rate = pass_rate(c);
+        let pass_rate_dropped = matches!(
+            (baseline_pass_rate, candidate_pass_rate),
+            (Some(b), Some(c)) if c < b - SCORE_EPSILON
+        );
+
+        // Pass rates are only comparable over cases both versions ran assertions on
+        if let (Some(_), Some(_)) = (baseline_pass_rate, candidate_pass_rate) {
+            add_assertions(&mut baseline_assertions, b);
+            add_assertions(&mut candidate_assertions, c);
+        }
+
+        cases.push(EvalCaseComparison {
+            prompt_eval_id: c.prompt_eval_id,
+            prompt_eval_name: c.prompt_eval_name.clone(),
+            outcome,
+            regressed: outcome == EvalCaseOutcome::Loss || pass_rate_dropped,
+            baseline_score: b.avg_score,
+            candidate_score: c.avg_score,
+            score_delta,
+            baseline_pass_rate,
+            candidate_pass_rate,
+            baseline_output: b.latest_output.clone(),
+            candidate_output: c.latest_output.clone(),
+        });
+    }
+
+    let count = |outcome| cases.iter().filter(|c| c.outcome == outcome).count();
+    let (wins, losses, ties) = (
+        count(EvalCaseOutcome::Win),
+        count(EvalCaseOutcome::Loss),
+        count(EvalCaseOutcome::Tie),
+    );
+
+    let deltas = cases.iter().filter_map(|c| c.score_delta).collect::<Vec<_>>();
+    let mean_score_delta = (!deltas.is_empty()).then(|| deltas.iter().sum::<f64>() / deltas.len() as f64);
+    let p_value = sign_test(wins, losses);
+
+    let compared_cases = cases.len();
+    let unmatched_cases = baseline.len() + candidate.len() - 2 * compared_cases;
+
+    cases.sort_by(|a, b| {
+        b.regressed
+            .cmp(&a.regressed)
+            .then_with(|| {
+                let delta = |c: &EvalCaseComparison| c.score_delta.unwrap_or(0.0);
+                delta(a).partial_cmp(&delta(b)).unwrap_or(Ordering::Equal)
+            })
+            .then_with(|| a.prompt_eval_id.cmp(&b.prompt_eval_id))
+    });
+
+    EvalRegressionReport {
+        prompt_id: 0,
+        baseline_version_id: 0,
+        candidate_version_id: 0,
+        model_id: None,
+        compared_cases,
+        unmatched_cases,
+        wins,
+        losses,
+        ties,
+        mean_score_delta,
+        baseline_pass_rate: ratio(baseline_assertions),
+        candidate_pass_rate: ratio(candidate_assertions),
+        p_value,
+        significant: p_value.is_some_and(|p| p < SIGNIFICANCE_LEVEL),
+        cases,
+    }
+}
+
+/// Exact two-sided sign test: the probability of a split at least this lopsided between wins
+/// and losses if both versions were equally good. Ties carry no information and are dropped.
+pub fn sign_test(wins: usize, losses: usize) -> Option<f64> {
+    let n = wins + losses;
+    if n == 0 {
+        return None;
+    }
+
+    // Binomial(n, 0.5) tail, summed in log space so large suites don't underflow
+    let k = wins.min(losses);
+    let mut ln_term = n as f64 * 0.5f64.ln();
+    let mut tail = ln_term.exp();
+    for i in 0..k {
+        ln_term += ((n - i) as f64).ln() - ((i + 1) as f64).ln();
+        tail += ln_term.exp();
+    }
+
+    Some((2.0 * tail).min(1.0))
+}
+
+fn pass_rate(totals: &EvalCaseTotals) -> Option<f64> {
+    ratio((totals.assertions_passed.unwrap_or(0), totals.assertions_total.unwrap_or(0)))
+}
+
+fn add_assertions(sum: &mut (i64, i64), totals: &EvalCaseTotals) {
+    sum.0 += totals.assertions_passed.unwrap_or(0);
+    sum.1 += totals.assertions_total.unwrap_or(0);
+}
+
+fn ratio((passed, total): (i64, i64)) -> Option<f64> {
+    (total > 0).then(|| passed as f64 / total as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn totals(prompt_eval_id: i64, avg_score: Option<f64>, assertions: (i64, i64)) -> EvalCaseTotals {
+        EvalCaseTotals {
+            prompt_eval_id,
+            prompt_eval_name: format!("case {}", prompt_eval_id),
+            run_count: 1,
+            scored_count: avg_score.map_or(0, |_| 1),
+            avg_score,
+            assertions_passed: Some(assertions.0),
+            assertions_total: Some(assertions.1),
+            latest_output: format!("output {}", prompt_eval_id),
+        }
+    }
+
+    #[test]
+    fn test_sign_test() {
+        assert_eq!(sign_test(0, 0), None);
+        assert_eq!(sign_test(3, 3), Some(1.0));
+        // 10 wins and no losses: 2 * 0.5^10
+        assert!((sign_test(10, 0).unwrap() - 0.001953125).abs() < 1e-12);
+        assert!((sign_test(2, 8).unwrap() - 0.109375).abs() < 1e-12);
+        assert!(sign_test(2000, 1000).unwrap() < 1e-10);
+    }
+
+    #[test]
+    fn test_compare_cases() {
+        let baseline = vec![
+            totals(1, Some(4.0), (2, 2)),
+            totals(2, Some(3.0), (1, 2)),
+            totals(3, Some(5.0), (2, 2)),
+            totals(4, Some(2.0), (0, 0)),
+        ];
+        let candidate = vec![
+            totals(1, Some(2.0), (1, 2)),
+            totals(2, Some(5.0), (2, 2)),
+            totals(3, Some(5.0), (1, 2)),
+            totals(5, None, (0, 0)),
+        ];
+
+        let report = compare_cases(&baseline, &candidate);
+        assert_eq!((report.compared_cases, report.unmatched_cases), (3, 2));
+        assert_eq!((report.wins, report.losses, report.ties), (1, 1, 1));
+        assert_eq!(report.mean_score_delta, Some(0.0));
+        assert_eq!(report.baseline_pass_rate, Some(5.0 / 6.0));
+        assert_eq!(report.candidate_pass_rate, Some(4.0 / 6.0));
+        assert!(!report.significant);
+
+        // A lower score and a dropped pass rate both count as regressions, worst first
+        let regressed = report.cases.iter().filter(|c| c.regressed).map(|c| c.prompt_eval_id).collect::<Vec<_>>();
+        assert_eq!(regressed, vec![1, 3]);
+        assert_eq!(report.cases[0].baseline_output, "output 1");
+    }
+}
@@ -0,0 +1,313 @@
+use std::time::Instant;
+
+use anyhow::{anyhow, Result};
+
+use crate::{
+    common::types::chat_request::{
+        ChatCompletionRequest, ChatCompletionRequestFunctionDescription, ChatCompletionRequestMessage,
+        ChatCompletionRequestTool,
+    },
+    db::{
+        init::DbData,
+        types::{
+            models::ModelProviderRow, prompt::PromptRowWithModel, prompt_eval::PromptEval,
+            prompt_eval_run::PromptEvalRun, snippet::SnippetRow,
+        },
+    },
+};
+
+use super::{
+    eval_assertions::{assertion_score, eval_assertions, run_assertions},
+    eval_conversation::eval_conversation,
+    eval_judge::judge_output,
+    llm::Llm,
+    templates::{load_snippet_dependencies, prompt_templates},
+    types::{eval_assertion::AssertionResult, llm_service::LlmServiceRequest},
+};
+
+/// Everything needed to run a prompt version's evals, loaded once per run.
+#[derive(Debug, Clone)]
+pub struct EvalTarget {
+    pub prompt: PromptRowWithModel,
+    pub tools: Vec<ChatCompletionRequestTool>,
+    pub snippets: Vec<SnippetRow>,
+    /// Judge prompt and rubric, when the prompt has a judge
+    pub judge: Option<(PromptRowWithModel, String)>,
+}
+
+impl EvalTarget {
+    /// Runs the version against another model, see `PromptRowWithModel::with_model`.
+    pub fn with_model(mut self, model: ModelProviderRow) -> Self {
+        self.prompt = self.prompt.with_model(model);
+        self
+    }
+}
+
+/// Loads exactly the requested version, so drafts can be evaluated before they're activated.
+/// `None` when the version doesn't belong to the prompt.
+pub async fn load_eval_target(
+    db: &DbData,
+    prompt_id: i64,
+    prompt_version_id: i64,
+) -> Result<Option<EvalTarget>> {
+    let Some(prompt) = db
+        .prompt
+        .get_prompt_versions(prompt_id)
+        .await?
+        .into_iter()
+        .find(|v| v.version_id == prompt_version_id)
+    else {
+        return Ok(None);
+    };
+
+    let tools = db
+        .tool
+        .get_tools_by_prompt_version(prompt.version_id)
+        .await?
+        .into_iter()
+        .map(|t| ChatCompletionRequestTool::Function {
+            function: ChatCompletionRequestFunctionDescription {
+                name: t.tool_name,
+                description: Some(t.description),
+                parameters: serde_json::from_str(&t.parameters).unwrap_or_default(),
+                strict: Some(t.strict),
+            },
+        })
+        .collect();
+
+    // Every output is also graded by the prompt's judge when it has one
+    let judge = match db.prompt_eval.get_judge(prompt_id).await? {
+        Some(config) => {
+            let judge_prompt = db
+                .prompt
+                .get_prompt_by_key(&config.judge_prompt_key)
+                .await
+                .map_err(|_| anyhow!("Judge prompt '{}' not found", config.judge_prompt_key))?;
+            Some((judge_prompt, config.rubric))
+        }
+        None => None,
+    };
+
+    // Only the snippets the prompt and its judge include, an unrelated broken one can't fail the run
+    let mut templates = prompt_templates(&prompt.system, prompt.user.as_deref(), prompt.messages.as_deref());
+    if let Some((judge_prompt, _)) = &judge {
+        templates.extend(prompt_templates(
+            &judge_prompt.system,
+            judge_prompt.user.as_deref(),
+            judge_prompt.messages.as_deref(),
+        ));
+    }
+    let snippets = load_snippet_dependencies(&templates, &db.snippet).await?;
+
+    Ok(Some(EvalTarget {
+        prompt,
+        tools,
+        snippets,
+        judge,
+    }))
+}
+
+/// Runs one eval once and stores the outputs under `run_id`, scored by the eval's assertions
+/// and the judge. A response with both text and tool calls stores a row for each.
+pub async fn run_eval_case(
+    db: &DbData,
+    target: &EvalTarget,
+    eval: &PromptEval,
+    run_id: &str,
+    eval_job_id: Option<i64>,
+) -> Result<Vec<(PromptEvalRun, Vec<AssertionResult>)>> {
+    let prompt = &target.prompt;
+
+    // Automated evals are scored from their assertions, human ones are left unscored
+    let assertions = eval_assertions(eval.assertions.as_deref(), eval.expected_output.as_deref())
+        .map_err(|e| anyhow!("Eval '{}' has invalid assertions: {}", eval.name, e))?;
+
+    // Parse system_prompt_input if present
+    let system_content = match &eval.system_prompt_input {
+        Some(system_json_str) => system_json_str.clone(),
+        None => "{}".to_string(), // Empty object if no system input
+    };
+
+    // Conversation evals replay their history and evaluate the next assistant turn
+    let conversation = eval_conversation(eval.messages.as_deref())
+        .map_err(|e| anyhow!("Eval '{}' has an invalid conversation: {}", eval.name, e))?
+        .unwrap_or_else(|| {
+            vec![ChatCompletionRequestMessage::User {
+                content: eval.user_prompt_input.clone(),
+                name: None,
+            }]
+        });
+
+    let mut messages = Vec::with_capacity(conversation.len() + 1);
+    messages.push(ChatCompletionRequestMessage::System {
+        content: system_content,
+        name: None,
+    });
+    messages.extend(conversation);
+
+    // Create a ChatCompletionRequest with the inputs
+    let chat_request = ChatCompletionRequest {
+        model: prompt.key.clone(),
+        messages,
+        stream: None,
+        response_format: None,
+        tools: Some(target.tools.clone()),
+        provider: None,
+        models: None,
+        transforms: None,
+        max_tokens: Some(prompt.max_tokens as u32),
+        temperature: Some(prompt.temperature as f32),
+        llmkit: None,
+    };
+
+    let llm_props = LlmServiceRequest::new_with_snippets(prompt.clone(), chat_request, &target.snippets)
+        .map_err(|e| anyhow!("An error occured processing prompt template: {}", e))?;
+
+    // Same call `api_completions` makes, so the eval grades what production would return
+    let llm = Llm::new(llm_props, db.log.clone());
+    let started = Instant::now();
+    let result = if prompt.json_mode {
+        llm.json().await
+    } else {
+        llm.text().await
+    };
+    let (res, log_id) = result.map_err(|e| anyhow!("LLM service error: {}", e))?;
+    let latency_ms = started.elapsed().as_millis() as i64;
+
+    let Some(c) = res.choices.first() else {
+        return Ok(Vec::new());
+    };
+
+    let results = run_assertions(
+        &assertions,
+        eval.expected_output.as_deref(),
+        prompt.json_schema.as_deref(),
+        c.message.content.as_deref(),
+        c.message.tool_calls.as_deref().unwrap_or_default(),
+    );
+    let score = assertion_score(&results);
+
+    let mut outputs = Vec::new();
+    if let Some(content) = &c.message.content {
+        outputs.push(content.clone());
+    }
+
+    // for now just stringify the tool calls
+    if let Some(tool_calls) = &c.message.tool_calls {
+        outputs.push(serde_json::to_string(&tool_calls)?);
+    }
+
+    let mut runs = Vec::with_capacity(outputs.len());
+    for output in outputs {
+        // A failed grading is recorded on the run rather than failing the whole eval run
+        let verdict = match &target.judge {
+            Some((judge_prompt, rubric)) => Some(
+                judge_output(db.log.clone(), judge_prompt, &target.snippets, rubric, eval, &output).await,
+            ),
+            None => None,
+        };
+
+        // Assertions take precedence, the judge scores evals that have none
+        let score = score.or(match &verdict {
+            Some(Ok((v, _))) => Some(v.score),
+            _ => None,
+        });
+
+        let mut eval_run = db
+            .prompt_eval_run
+            .create(
+                run_id,
+                prompt.version_id,
+                eval.id,
+                score,
+                &output,
+                eval_job_id,
+                prompt.model_id,
+                log_id,
+                latency_ms,
+            )
+            .await?;
+
+        match verdict {
+            Some(Ok((verdict, judge_log_id))) => {
+                eval_run = db
+                    .prompt_eval_run
+                    .set_judge_result(eval_run.id, verdict.score, &verdict.rationale, judge_log_id)
+                    .await?;
+            }
+            Some(Err(e)) => {
+                tracing::warn!("Eval judge failed on eval '{}': {}", eval.name, e);
+                eval_run = db
+                    .prompt_eval_run
+                    .set_judge_error(eval_run.id, &format!("Eval judge error: {}", e))
+                    .await?;
+            }
+            None => {}
+        }
+
+        db.prompt_eval_run
+            .create_assertion_results(eval_run.id, &assertion_rows(&results)?)
+            .await?;
+
+        runs.push((eval_run, results.clone()));
+    }
+
+    Ok(runs)
+}
+
+fn assertion_rows(results: &[AssertionResult]) -> Result<Vec<(String, bool, Option<String>)>> {
+    results
+        .iter()
+        .map(|r| Ok((serde_json::to_string(&r.assertion)?, r.passed, r.message.clone())))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Runs used to execute the prompt's active version instead of the requested one
+    #[tokio::test]
+    async fn test_eval_target_is_the_requested_version() {
+        let db = DbData::in_memory().await.unwrap();
+        let prompts = db.prompt.list_prompts().await.unwrap();
+        let (prompt, other) = (&prompts[0], &prompts[1]);
+
+        // A newer version becomes the active one
+        db.prompt
+            .update_prompt(
+                prompt.id,
+                &prompt.key,
+                "A newer system prompt",
+                prompt.user.as_deref().unwrap_or(""),
+                prompt.model_id,
+                prompt.max_tokens,
+                prompt.temperature,
+                prompt.json_mode,
+                prompt.json_schema.as_deref(),
+                &prompt.prompt_type,
+                prompt.is_chat,
+                prompt.reasoning_effort.as_deref(),
+                prompt.messages.as_deref(),
+                None,
+                None,
+                false,
+            )
+            .await
+            .unwrap();
+        assert_ne!(db.prompt.get_prompt(prompt.id).await.unwrap().version_id, prompt.version_id);
+
+        let target = load_eval_target(&db, prompt.id, prompt.version_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(target.prompt.version_id, prompt.version_id);
+        assert_eq!(target.prompt.system, prompt.system);
+
+        // A version of another prompt isn't found
+        assert!(load_eval_target(&db, other.id, prompt.version_id)
+            .await
+            .unwrap()
+            .is_none());
+    }
+}
@@ -0,0 +1,271 @@
+use anyhow::{anyhow, Result};
+use uuid::Uuid;
+
+use crate::db::init::DbData;
+
+use super::{
+    eval_runner::{load_eval_target, run_eval_case},
+    types::{
+        eval_assertion::AssertionResult,
+        eval_suite::{EvalSuiteCase, EvalSuiteCaseStatus, EvalSuiteReport, EvalThresholds},
+    },
+};
+
+/// Score a case graded only by a judge needs to pass, unless told otherwise.
+pub const DEFAULT_MIN_CASE_SCORE: i64 = 3;
+
+/// Runs every eval of a prompt `rounds` times against one version, the active one unless a
+/// version number is given. The runs are stored like any other eval run, a case that errors
+/// is reported without stopping the rest.
+pub async fn run_eval_suite(
+    db: &DbData,
+    prompt_key: &str,
+    version_number: Option<i64>,
+    rounds: i64,
+    min_case_score: i64,
+) -> Result<EvalSuiteReport> {
+    let active = db
+        .prompt
+        .get_prompt_by_key(prompt_key)
+        .await
+        .map_err(|_| anyhow!("Prompt '{}' not found", prompt_key))?;
+
+    let version = match version_number {
+        Some(number) => db
+            .prompt
+            .get_prompt_versions(active.id)
+            .await?
+            .into_iter()
+            .find(|v| v.version_number == number)
+            .ok_or_else(|| anyhow!("Prompt '{}' has no version {}", prompt_key, number))?,
+        None => active,
+    };
+
+    let target = load_eval_target(db, version.id, version.version_id)
+        .await?
+        .ok_or_else(|| anyhow!("Version {} not found", version.version_id))?;
+    let evals = db.prompt_eval.get_by_prompt(version.id).await?;
+
+    let mut cases = Vec::with_capacity(evals.len() * rounds.max(0) as usize);
+    for round in 1..=rounds {
+        let run_id = Uuid::new_v4().to_string();
+
+        for eval in &evals {
+            let case = match run_eval_case(db, &target, eval, &run_id, None).await {
+                Ok(runs) => match runs.first() {
+                    Some((run, results)) => EvalSuiteCase {
+                        prompt_eval_id: eval.id,
+                        name: eval.name.clone(),
+                        round,
+                        status: EvalSuiteCaseStatus::Skipped,
+                        score: run.score,
+                        assertions_passed: results.iter().filter(|r| r.passed).count(),
+                        assertions_total: results.len(),
+                        failures: assertion_failures(results),
+                        // A response with text and tool calls is stored as a run for each
+                        output: Some(runs.iter().map(|(r, _)| r.output.as_str()).collect::<Vec<_>>().join("\n")),
+                        latency_ms: run.latency_ms,
+                    },
+                    None => EvalSuiteCase::errored(eval.id, eval.name.clone(), round, "No response".to_string()),
+                },
+                Err(e) => EvalSuiteCase::errored(eval.id, eval.name.clone(), round, e.to_string()),
+            };
+
+            cases.push(case.grade(min_case_score));
+        }
+    }
+
+    Ok(suite_report(
+        version.key,
+        version.id,
+        version.version_id,
+        version.version_number,
+        cases,
+    ))
+}
+
+/// Totals of a suite run's cases.
+pub fn suite_report(
+    prompt_key: String,
+    prompt_id: i64,
+    prompt_version_id: i64,
+    version_number: i64,
+    cases: Vec<EvalSuiteCase>,
+) -> EvalSuiteReport {
+    let count = |status| cases.iter().filter(|c| c.status == status).count();
+    let (passed, failed, errored, skipped) = (
+        count(EvalSuiteCaseStatus::Passed),
+        count(EvalSuiteCaseStatus::Failed),
+        count(EvalSuiteCaseStatus::Errored),
+        count(EvalSuiteCaseStatus::Skipped),
+    );
+
+    let graded = passed + failed + errored;
+    let scores = cases.iter().filter_map(|c| c.score).collect::<Vec<_>>();
+
+    EvalSuiteReport {
+        prompt_key,
+        prompt_id,
+        prompt_version_id,
+        version_number,
+        total: cases.len(),
+        passed,
+        failed,
+        errored,
+        skipped,
+        pass_rate: (graded > 0).then(|| passed as f64 / graded as f64),
+        avg_score: (!scores.is_empty()).then(|| scores.iter().sum::<i64>() as f64 / scores.len() as f64),
+        threshold_failures: Vec::new(),
+        cases,
+    }
+}
+
+/// Records every threshold the run fell below on the report. Returns whether it passed.
+pub fn apply_thresholds(report: &mut EvalSuiteReport, thresholds: &EvalThresholds) -> bool {
+    let mut failures = Vec::new();
+
+    if let Some(min) = thresholds.min_pass_rate {
+        match report.pass_rate {
+            Some(rate) if rate < min => failures.push(format!("Pass rate {:.3} is below {:.3}", rate, min)),
+            None => failures.push("No case was graded, so there's no pass rate".to_string()),
+            _ => {}
+        }
+    }
+
+    if let Some(min) = thresholds.min_score {
+        match report.avg_score {
+            Some(score) if score < min => failures.push(format!("Average score {:.2} is below {:.2}", score, min)),
+            None => failures.push("No case was scored, so there's no average score".to_string()),
+            _ => {}
+        }
+    }
+
+    report.threshold_failures = failures;
+    report.threshold_failures.is_empty()
+}
+
+/// JUnit XML with one test case per eval case and round, as CI systems display it.
+pub fn junit_xml(report: &EvalSuiteReport) -> String {
+    let suite = format!("{} v{}", report.prompt_key, report.version_number);
+    let seconds = |ms: Option<i64>| ms.unwrap_or(0) as f64 / 1000.0;
+    let time = seconds(Some(report.cases.iter().filter_map(|c| c.latency_ms).sum()));
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(&format!(
+        "<testsuites name=\"llmkit\" tests=\"{}\" failures=\"{}\" errors=\"{}\" skipped=\"{}\" time=\"{:.3}\">\n",
+        report.total, report.failed, report.errored, report.skipped, time
+    ));
+    xml.push_str(&format!(
+        "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"{}\" skipped=\"{}\" time=\"{:.3}\">\n",
+        escape_xml(&suite), report.total, report.failed, report.errored, report.skipped, time
+    ));
+
+    for case in &report.cases {
+        xml.push_str(&format!(
+            "    <testcase classname=\"{}\" name=\"{}\" time=\"{:.3}\">\n",
+            escape_xml(&suite),
+            escape_xml(&format!("{} (round {})", case.name, case.round)),
+            seconds(case.latency_ms)
+        ));
+
+        let details = escape_xml(&case.failures.join("\n"));
+        match case.status {
+            EvalSuiteCaseStatus::Failed => xml.push_str(&format!(
+                "      <failure message=\"{}\">{}</failure>\n",
+                escape_xml(case.failures.first().map_or("Failed", String::as_str)),
+                details
+            )),
+            EvalSuiteCaseStatus::Errored => xml.push_str(&format!(
+                "      <error message=\"{}\">{}</error>\n",
+                escape_xml(case.failures.first().map_or("Errored", String::as_str)),
+                details
+            )),
+            EvalSuiteCaseStatus::Skipped => xml.push_str("      <skipped message=\"Human-reviewed eval\"/>\n"),
+            EvalSuiteCaseStatus::Passed => {}
+        }
+
+        if let Some(output) = &case.output {
+            xml.push_str(&format!("      <system-out>{}</system-out>\n", escape_xml(output)));
+        }
+        xml.push_str("    </testcase>\n");
+    }
+
+    xml.push_str("  </testsuite>\n</testsuites>\n");
+    xml
+}
+
+pub fn assertion_failures(results: &[AssertionResult]) -> Vec<String> {
+    results
+        .iter()
+        .filter(|r| !r.passed)
+        .map(|r| {
+            r.message
+                .clone()
+                .unwrap_or_else(|| format!("Assertion failed: {}", serde_json::to_string(&r.assertion).unwrap_or_default()))
+        })
+        .collect()
+}
+
+fn escape_xml(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            // Control characters aren't allowed in XML 1.0
+            c if c.is_control() && !matches!(c, '\n' | '\r' | '\t') => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn case(name: &str, score: Option<i64>, assertions: (usize, usize)) -> EvalSuiteCase {
+        EvalSuiteCase {
+            prompt_eval_id: 1,
+            name: name.to_string(),
+            round: 1,
+            status: EvalSuiteCaseStatus::Skipped,
+            score,
+            assertions_passed: assertions.0,
+            assertions_total: assertions.1,
+            failures: Vec::new(),
+            output: Some("<b>\"Paris\" & co</b>".to_string()),
+            latency_ms: Some(1500),
+        }
+        .grade(DEFAULT_MIN_CASE_SCORE)
+    }
+
+    #[test]
+    fn test_suite_report_thresholds_and_junit() {
+        let cases = vec![
+            case("exact", Some(5), (1, 1)),
+            case("judged", Some(2), (0, 0)),
+            case("human", None, (0, 0)),
+            EvalSuiteCase::errored(4, "flaky".to_string(), 1, "Rate limited".to_string()),
+        ];
+        assert_eq!(cases[1].failures, vec!["Score 2 is below 3".to_string()]);
+
+        let mut report = suite_report("capitals".to_string(), 1, 7, 3, cases);
+        assert_eq!((report.passed, report.failed, report.errored, report.skipped), (1, 1, 1, 1));
+        assert_eq!(report.pass_rate, Some(1.0 / 3.0));
+        assert_eq!(report.avg_score, Some(3.5));
+
+        assert!(apply_thresholds(&mut report, &EvalThresholds { min_pass_rate: Some(0.3), min_score: Some(3.5) }));
+        assert!(!apply_thresholds(&mut report, &EvalThresholds { min_pass_rate: Some(0.9), min_score: None }));
+        assert_eq!(report.threshold_failures.len(), 1);
+
+        let xml = junit_xml(&report);
+        assert!(xml.contains("<testsuite name=\"capitals v3\" tests=\"4\" failures=\"1\" errors=\"1\" skipped=\"1\" time=\"4.500\">"));
+        assert!(xml.contains("<failure message=\"Score 2 is below 3\">"));
+        assert!(xml.contains("<error message=\"Rate limited\">"));
+        assert!(xml.contains("&lt;b&gt;&quot;Paris&quot; &amp; co&lt;/b&gt;"));
+    }
+}
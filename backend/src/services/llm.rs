@@ -1,29 +1,43 @@
-use std::time::Duration;
+use std::{
+    sync::atomic::{AtomicI64, Ordering},
+    time::{Duration, Instant},
+};
 
 use anyhow::Result;
-use tokio::sync::mpsc::Sender;
+use tokio::sync::mpsc::{self, Sender};
 use tokio_retry::{
     strategy::{jitter, ExponentialBackoff},
     Retry,
 };
-use tracing;
+use tracing::{self, Instrument};
 
 use super::{
     providers::{openai::OpenAiProvider, openrouter::OpenrouterProvider},
     types::{
-        llm_error::{LlmError, LlmStreamingError}, llm_service::LlmServiceRequest
+        llm_error::{LlmError, LlmStreamingError}, llm_service::{LlmServiceRequest, RequestTiming},
+        pricing::TokenUsage,
     },
+    metrics::{self, InFlightStream},
+    pricing::request_cost,
+    telemetry,
 };
-use crate::{common::types::{chat_response::{LlmServiceChatCompletionChunk, LlmServiceChatCompletionResponse}, models::LlmApiProvider}, db::logs::LogRepository};
+use crate::{common::types::{chat_response::{LlmServiceChatCompletionChunk, LlmServiceChatCompletionResponse}, models::LlmApiProvider}, db::{logs::LogRepository, types::log::NewLog}};
 
 pub struct Llm {
     props: LlmServiceRequest,
     db_log: LogRepository,
+    /// Attempts made so far, retries included
+    attempts: AtomicI64,
 }
 
 impl Llm {
     pub fn new(props: LlmServiceRequest, db_log: LogRepository) -> Self {
-        Llm { props, db_log }
+        Llm { props, db_log, attempts: AtomicI64::new(0) }
+    }
+
+    /// Counts an attempt at the request and returns its number, every retry goes through here
+    fn next_attempt(&self) -> i64 {
+        self.attempts.fetch_add(1, Ordering::Relaxed) + 1
     }
 
     fn retry_strategy(&self) -> impl Iterator<Item = Duration> {
@@ -108,9 +122,7 @@ impl Llm {
 
     async fn send_request(&self) -> Result<(LlmServiceChatCompletionResponse, i64), LlmError> {
         // Initialize variables to capture data even in error cases
-        let mut input_tokens = None;
-        let mut output_tokens = None;
-        let mut reasoning_tokens = None;
+        let mut usage = TokenUsage::default();
         let mut raw_response: Option<String> = None;
         let mut status = Some(500); // Default to error status
 
@@ -118,20 +130,35 @@ impl Llm {
         let request_body = serde_json::to_string(&self.props)
             .map_err(|e| LlmError::SerializationError(e.to_string()))?;
 
+        let attempt = self.next_attempt();
+        let started_at = chrono::Utc::now().naive_utc();
+        let start = Instant::now();
+
         // Execute request and capture result
-        let result = match &self.props.provider {
-            LlmApiProvider::Openrouter => {
-                let provider = OpenrouterProvider::new(&self.props)?;
-                provider.execute_chat().await
-            }
-            LlmApiProvider::OpenAi => {
-                let provider = OpenAiProvider::new(&self.props)?;
-                provider.execute_chat().await
-            }
-            LlmApiProvider::Azure => {
-                let provider = OpenAiProvider::new_azure(&self.props)?;
-                provider.execute_chat().await
+        let span = telemetry::chat_span(&self.props, attempt);
+        let result = async {
+            match &self.props.provider {
+                LlmApiProvider::Openrouter => {
+                    let provider = OpenrouterProvider::new(&self.props)?;
+                    provider.execute_chat().await
+                }
+                LlmApiProvider::OpenAi => {
+                    let provider = OpenAiProvider::new(&self.props)?;
+                    provider.execute_chat().await
+                }
+                LlmApiProvider::Azure => {
+                    let provider = OpenAiProvider::new_azure(&self.props)?;
+                    provider.execute_chat().await
+                }
             }
+        }
+        .instrument(span.clone())
+        .await;
+        let timing = RequestTiming {
+            started_at,
+            duration_ms: start.elapsed().as_millis() as i64,
+            first_token_ms: None,
+            attempt,
         };
 
         // Process the result or prepare error
@@ -141,23 +168,9 @@ impl Llm {
                 status = Some(200);
 
                 // Extract tokens and usage information
-                input_tokens = provider_response
-                    .usage
-                    .as_ref()
-                    .map(|usage| usage.prompt_tokens as i64);
-
-                output_tokens = provider_response
-                    .usage
-                    .as_ref()
-                    .map(|usage| usage.completion_tokens as i64);
-
-                // Extract reasoning tokens if available
-                reasoning_tokens = provider_response
-                    .usage
-                    .as_ref()
-                    .and_then(|usage| usage.completion_tokens_details.as_ref())
-                    .and_then(|details| details.reasoning_tokens)
-                    .map(|tokens| tokens as i64);
+                if let Some(u) = provider_response.usage.as_ref() {
+                    usage = TokenUsage::from(u);
+                }
 
                 // Save raw response for logging
                 raw_response = serde_json::to_string(&provider_response).ok();
@@ -178,19 +191,29 @@ impl Llm {
             }
         };
 
+        // Ends the attempt's span before logging, which gets a span of its own
+        match &exec_result {
+            Ok(r) => telemetry::record_response(&span, r),
+            Err(e) => telemetry::record_error(&span, e),
+        }
+        drop(span);
+
         // Always log the request, regardless of success or failure
         let log_id = self
             .log_request(
                 raw_response.as_deref(),
                 status,
-                input_tokens,
-                output_tokens,
-                reasoning_tokens,
+                usage,
+                Some(timing),
                 &request_body,
                 &provider_response_id,
             )
             .await?;
 
+        if let Err(e) = &exec_result {
+            metrics::record_error(&self.props, e);
+        }
+
         // Return the original result but with the correct log_id
         match exec_result {
             Ok(r) => Ok((r, log_id)),
@@ -203,9 +226,7 @@ impl Llm {
         tx: Sender<Result<LlmServiceChatCompletionChunk, LlmStreamingError>>,
     ) -> Result<(LlmServiceChatCompletionResponse, i64), LlmError> {
         // Initialize variables to capture data even in error cases
-        let mut input_tokens = None;
-        let mut output_tokens = None;
-        let mut reasoning_tokens = None;
+        let mut usage = TokenUsage::default();
         let mut raw_response: Option<String> = None;
         let mut status = Some(500); // Default to error status
 
@@ -226,32 +247,64 @@ impl Llm {
             self.log_request(
                 raw_response.as_deref(),
                 status,
-                input_tokens,
-                output_tokens,
-                reasoning_tokens,
+                usage,
+                None,
                 &request_body,
                 &provider_response_id
             )
             .await?;
 
+            metrics::record_error(&self.props, &error);
             return Err(error);
         }
 
-        // Execute request and capture result
-        let result = match &self.props.provider {
-            LlmApiProvider::Openrouter => {
-                let provider = OpenrouterProvider::new(&self.props)?;
-                provider.execute_chat_stream(tx).await
-            }
-            LlmApiProvider::OpenAi => {
-                let provider = OpenAiProvider::new(&self.props)?;
-                provider.execute_chat_stream(tx).await
+        let attempt = self.next_attempt();
+        let started_at = chrono::Utc::now().naive_utc();
+        let start = Instant::now();
+
+        let _in_flight = InFlightStream::start(&self.props);
+
+        // Chunks pass through here on their way to the caller so the first one can be timed
+        let (chunk_tx, mut chunk_rx) =
+            mpsc::channel::<Result<LlmServiceChatCompletionChunk, LlmStreamingError>>(100);
+        let forward = async {
+            let mut first_token_ms = None;
+            while let Some(chunk) = chunk_rx.recv().await {
+                if first_token_ms.is_none() && chunk.is_ok() {
+                    first_token_ms = Some(start.elapsed().as_millis() as i64);
+                }
+                if tx.send(chunk).await.is_err() {
+                    break;
+                }
             }
-            LlmApiProvider::Azure => {
-                let provider = OpenAiProvider::new_azure(&self.props)?;
-                provider.execute_chat_stream(tx).await
+            first_token_ms
+        };
+
+        // Execute request and capture result
+        let execute = async move {
+            match &self.props.provider {
+                LlmApiProvider::Openrouter => {
+                    let provider = OpenrouterProvider::new(&self.props)?;
+                    provider.execute_chat_stream(chunk_tx).await
+                }
+                LlmApiProvider::OpenAi => {
+                    let provider = OpenAiProvider::new(&self.props)?;
+                    provider.execute_chat_stream(chunk_tx).await
+                }
+                LlmApiProvider::Azure => {
+                    let provider = OpenAiProvider::new_azure(&self.props)?;
+                    provider.execute_chat_stream(chunk_tx).await
+                }
             }
         };
+        let span = telemetry::chat_span(&self.props, attempt);
+        let (result, first_token_ms) = tokio::join!(execute.instrument(span.clone()), forward);
+        let timing = RequestTiming {
+            started_at,
+            duration_ms: start.elapsed().as_millis() as i64,
+            first_token_ms,
+            attempt,
+        };
 
         // Process the result or prepare error
         let (exec_result, provider_response_id) = match result {
@@ -260,23 +313,9 @@ impl Llm {
                 status = Some(200);
 
                 // Extract tokens and usage information
-                input_tokens = response
-                    .usage
-                    .as_ref()
-                    .map(|usage| usage.prompt_tokens as i64);
-
-                output_tokens = response
-                    .usage
-                    .as_ref()
-                    .map(|usage| usage.completion_tokens as i64);
-
-                // Extract reasoning tokens if available
-                reasoning_tokens = response
-                    .usage
-                    .as_ref()
-                    .and_then(|usage| usage.completion_tokens_details.as_ref())
-                    .and_then(|details| details.reasoning_tokens)
-                    .map(|tokens| tokens as i64);
+                if let Some(u) = response.usage.as_ref() {
+                    usage = TokenUsage::from(u);
+                }
 
                 // Save raw response for logging
                 raw_response = serde_json::to_string(&response).ok();
@@ -296,19 +335,29 @@ impl Llm {
             }
         };
 
+        // Ends the attempt's span before logging, which gets a span of its own
+        match &exec_result {
+            Ok(r) => telemetry::record_response(&span, r),
+            Err(e) => telemetry::record_error(&span, e),
+        }
+        drop(span);
+
         // Always log the request, regardless of success or failure
         let log_id = self
             .log_request(
                 raw_response.as_deref(),
                 status,
-                input_tokens,
-                output_tokens,
-                reasoning_tokens,
+                usage,
+                Some(timing),
                 &request_body,
                 &provider_response_id
             )
             .await?;
 
+        if let Err(e) = &exec_result {
+            metrics::record_error(&self.props, e);
+        }
+
         // Return the original result but with the correct log_id
         match exec_result {
             Ok(r) => Ok((r, log_id)),
@@ -317,28 +366,55 @@ impl Llm {
     }
 
     /// Logs the request and returns a log ID.
+    #[tracing::instrument(name = "log_request", skip_all)]
     async fn log_request(
         &self,
         raw_response: Option<&str>,
         status: Option<i64>,
-        input_tokens: Option<i64>,
-        output_tokens: Option<i64>,
-        reasoning_tokens: Option<i64>,
+        usage: TokenUsage,
+        timing: Option<RequestTiming>,
         request_body: &str,
         provider_response_id: &str,
     ) -> Result<i64, LlmError> {
+        // Costed at the price in effect now, so later price changes leave the log as it was billed
+        let price = self
+            .db_log
+            .get_model_price(self.props.model_id)
+            .await
+            .map_err(|e| LlmError::DbLoggingError(e.to_string()))?;
+        let cost = price.and_then(|price| request_cost(&price, &usage));
+
+        metrics::record_attempt(&self.props, status, &usage, cost, timing.as_ref());
+
+        let metadata = self.props.request.llmkit.clone().unwrap_or_default();
+        let tags = (!metadata.tags.is_empty())
+            .then(|| serde_json::to_string(&metadata.tags))
+            .transpose()
+            .map_err(|e| LlmError::SerializationError(e.to_string()))?;
+
         self.db_log
-            .create_log(
-                Some(self.props.prompt_id),
-                self.props.model_id,
-                raw_response,
-                status,
-                input_tokens,
-                output_tokens,
-                reasoning_tokens,
-                Some(request_body),
+            .create_log(&NewLog {
+                prompt_id: Some(self.props.prompt_id),
+                prompt_version_id: self.props.prompt_version_id,
+                model_id: self.props.model_id,
+                response_data: raw_response,
+                status_code: status,
+                input_tokens: usage.input_tokens,
+                output_tokens: usage.output_tokens,
+                reasoning_tokens: usage.reasoning_tokens,
+                cached_input_tokens: usage.cached_input_tokens,
+                cost,
+                api_key_id: self.props.api_key_id,
+                started_at: timing.map(|t| t.started_at),
+                duration_ms: timing.map(|t| t.duration_ms),
+                first_token_ms: timing.and_then(|t| t.first_token_ms),
+                attempt: timing.map(|t| t.attempt),
+                client_user_id: metadata.user_id.as_deref(),
+                session_id: metadata.session_id.as_deref(),
+                tags: tags.as_deref(),
+                request_body: Some(request_body),
                 provider_response_id,
-            )
+            })
             .await
             .map_err(|e| LlmError::DbLoggingError(e.to_string()))
     }
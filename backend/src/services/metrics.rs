@@ -0,0 +1,220 @@
+use std::{sync::OnceLock, time::Duration};
+
+use anyhow::Result;
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
+
+use super::types::{
+    llm_error::LlmError,
+    llm_service::{LlmServiceRequest, RequestTiming},
+    pricing::TokenUsage,
+};
+
+const REQUESTS: &str = "llmkit_requests_total";
+const REQUEST_ERRORS: &str = "llmkit_request_errors_total";
+const REQUEST_RETRIES: &str = "llmkit_request_retries_total";
+const TOKENS: &str = "llmkit_tokens_total";
+const COST: &str = "llmkit_cost_usd";
+const REQUEST_DURATION: &str = "llmkit_request_duration_seconds";
+const TIME_TO_FIRST_TOKEN: &str = "llmkit_time_to_first_token_seconds";
+const STREAMS_IN_FLIGHT: &str = "llmkit_streams_in_flight";
+const PROMPT_CACHE_LOOKUPS: &str = "llmkit_prompt_cache_lookups_total";
+
+const REQUEST_DURATION_BUCKETS: &[f64] = &[0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 20.0, 30.0, 60.0, 120.0];
+const TIME_TO_FIRST_TOKEN_BUCKETS: &[f64] = &[0.05, 0.1, 0.25, 0.5, 0.75, 1.0, 2.0, 5.0, 10.0];
+
+// Histograms are drained into their buckets on upkeep
+const UPKEEP_INTERVAL: Duration = Duration::from_secs(5);
+
+static RECORDER: OnceLock<PrometheusHandle> = OnceLock::new();
+
+/// Installs the process-wide Prometheus recorder and returns the handle `/metrics` renders from.
+/// Only the first call installs it, later ones share its handle.
+pub fn install_recorder() -> Result<PrometheusHandle> {
+    if let Some(handle) = RECORDER.get() {
+        return Ok(handle.clone());
+    }
+
+    let handle = PrometheusBuilder::new()
+        .set_buckets_for_metric(Matcher::Full(REQUEST_DURATION.to_string()), REQUEST_DURATION_BUCKETS)?
+        .set_buckets_for_metric(Matcher::Full(TIME_TO_FIRST_TOKEN.to_string()), TIME_TO_FIRST_TOKEN_BUCKETS)?
+        .install_recorder()?;
+
+    describe();
+
+    // A thread rather than a task, so upkeep outlives the runtime that happened to install it
+    let upkeep = handle.clone();
+    std::thread::spawn(move || loop {
+        std::thread::sleep(UPKEEP_INTERVAL);
+        upkeep.run_upkeep();
+    });
+
+    Ok(RECORDER.get_or_init(|| handle).clone())
+}
+
+fn describe() {
+    metrics::describe_counter!(REQUESTS, "Attempts at LLM requests, by the status they were logged with");
+    metrics::describe_counter!(REQUEST_ERRORS, "Failed attempts at LLM requests, by LlmError variant");
+    metrics::describe_counter!(REQUEST_RETRIES, "Attempts that retried an earlier failed one");
+    metrics::describe_counter!(TOKENS, "Tokens billed, by type: input, cached_input, output or reasoning");
+    metrics::describe_gauge!(COST, "Running total cost of requests to priced models, in USD");
+    metrics::describe_histogram!(REQUEST_DURATION, metrics::Unit::Seconds, "Duration of each attempt at an LLM request");
+    metrics::describe_histogram!(TIME_TO_FIRST_TOKEN, metrics::Unit::Seconds, "Time until the first chunk of a streamed response");
+    metrics::describe_gauge!(STREAMS_IN_FLIGHT, "Streamed responses currently being sent");
+    metrics::describe_counter!(PROMPT_CACHE_LOOKUPS, "Prompt cache lookups, by result: hit or miss");
+}
+
+fn request_labels(props: &LlmServiceRequest) -> [(&'static str, String); 3] {
+    [
+        ("prompt", props.prompt_key.clone()),
+        ("model", props.request.model.clone()),
+        ("provider", String::from(props.provider.clone())),
+    ]
+}
+
+/// Counts an attempt at a request along with the tokens, cost and time it took.
+pub fn record_attempt(
+    props: &LlmServiceRequest,
+    status: Option<i64>,
+    usage: &TokenUsage,
+    cost: Option<f64>,
+    timing: Option<&RequestTiming>,
+) {
+    let labels = request_labels(props);
+
+    let status = status.map_or_else(|| "unknown".to_string(), |s| s.to_string());
+    let mut with_status = labels.to_vec();
+    with_status.push(("status", status));
+    metrics::counter!(REQUESTS, &with_status).increment(1);
+
+    let tokens = [
+        ("input", usage.input_tokens),
+        ("cached_input", usage.cached_input_tokens),
+        ("output", usage.output_tokens),
+        ("reasoning", usage.reasoning_tokens),
+    ];
+    for (kind, count) in tokens {
+        if let Some(count) = count.filter(|c| *c > 0) {
+            let mut with_type = labels.to_vec();
+            with_type.push(("type", kind.to_string()));
+            metrics::counter!(TOKENS, &with_type).increment(count as u64);
+        }
+    }
+
+    if let Some(cost) = cost {
+        // Counters only take whole numbers, a gauge keeps fractions of a cent from being rounded away
+        metrics::gauge!(COST, &labels).increment(cost);
+    }
+
+    if let Some(timing) = timing {
+        metrics::histogram!(REQUEST_DURATION, &labels).record(timing.duration_ms as f64 / 1000.0);
+
+        if let Some(first_token_ms) = timing.first_token_ms {
+            metrics::histogram!(TIME_TO_FIRST_TOKEN, &labels).record(first_token_ms as f64 / 1000.0);
+        }
+
+        if timing.attempt > 1 {
+            metrics::counter!(REQUEST_RETRIES, &labels).increment(1);
+        }
+    }
+}
+
+pub fn record_error(props: &LlmServiceRequest, error: &LlmError) {
+    let mut labels = request_labels(props).to_vec();
+    labels.push(("error", error.variant_name()));
+    metrics::counter!(REQUEST_ERRORS, &labels).increment(1);
+}
+
+pub fn record_prompt_cache_lookup(hit: bool) {
+    let result = if hit { "hit" } else { "miss" };
+    metrics::counter!(PROMPT_CACHE_LOOKUPS, "result" => result).increment(1);
+}
+
+/// Counts a stream as in flight until it's dropped.
+pub struct InFlightStream {
+    labels: [(&'static str, String); 3],
+}
+
+impl InFlightStream {
+    pub fn start(props: &LlmServiceRequest) -> Self {
+        let labels = request_labels(props);
+        metrics::gauge!(STREAMS_IN_FLIGHT, &labels).increment(1.0);
+        InFlightStream { labels }
+    }
+}
+
+impl Drop for InFlightStream {
+    fn drop(&mut self) {
+        metrics::gauge!(STREAMS_IN_FLIGHT, &self.labels).decrement(1.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::{
+        common::types::models::LlmApiProvider, services::types::llm_service::TemplateVariables,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_record_attempt_and_error() {
+        let recorder = PrometheusBuilder::new()
+            .set_buckets_for_metric(Matcher::Full(REQUEST_DURATION.to_string()), REQUEST_DURATION_BUCKETS)
+            .unwrap()
+            .build_recorder();
+        let handle = recorder.handle();
+
+        let props = LlmServiceRequest {
+            provider: LlmApiProvider::Openrouter,
+            base_url: None,
+            prompt_id: 1,
+            prompt_version_id: None,
+            model_id: 1,
+            is_reasoning: false,
+            reasoning_effort: None,
+            request: serde_json::from_value(json!({ "model": "openai/gpt-4o", "messages": [] })).unwrap(),
+            template_variables: TemplateVariables { system: json!({}), user: None },
+            api_key_id: None,
+            prompt_key: "summarize".to_string(),
+            provider_api_key: None,
+        };
+        let usage = TokenUsage {
+            input_tokens: Some(120),
+            cached_input_tokens: None,
+            output_tokens: Some(30),
+            reasoning_tokens: None,
+        };
+        let timing = RequestTiming {
+            started_at: chrono::NaiveDateTime::default(),
+            duration_ms: 800,
+            first_token_ms: None,
+            attempt: 2,
+        };
+
+        metrics::with_local_recorder(&recorder, || {
+            record_attempt(&props, Some(200), &usage, Some(0.0015), Some(&timing));
+            record_error(&props, &LlmError::RateLimit("slow down".to_string()));
+        });
+
+        let rendered = handle.render();
+        let labels = r#"prompt="summarize",model="openai/gpt-4o",provider="openrouter""#;
+        assert!(rendered.contains(&format!(r#"{}{{{},status="200"}} 1"#, REQUESTS, labels)));
+        assert!(rendered.contains(&format!(r#"{}{{{},type="input"}} 120"#, TOKENS, labels)));
+        assert!(rendered.contains(&format!("{}{{{}}} 0.0015", COST, labels)));
+        assert!(rendered.contains(&format!("{}{{{}}} 1", REQUEST_RETRIES, labels)));
+        assert!(rendered.contains(&format!(r#"{}_bucket{{{},le="1"}} 1"#, REQUEST_DURATION, labels)));
+        assert!(rendered.contains(&format!(r#"{}{{{},error="RateLimit"}} 1"#, REQUEST_ERRORS, labels)));
+        assert!(!rendered.contains(r#"type="reasoning""#));
+    }
+
+    #[test]
+    fn test_install_recorder_twice() {
+        let first = install_recorder().unwrap();
+        let second = install_recorder().unwrap();
+
+        metrics::counter!(REQUESTS).increment(1);
+        assert_eq!(first.render(), second.render());
+    }
+}
@@ -2,3 +2,20 @@ pub mod llm;
 pub mod providers;
 pub mod types;
 pub mod utils;
+pub mod templates;
+pub mod template_helpers;
+pub mod bundles;
+pub mod prompt_sync;
+pub mod prompt_diff;
+pub mod eval_assertions;
+pub mod eval_judge;
+pub mod eval_runner;
+pub mod eval_jobs;
+pub mod eval_matrix;
+pub mod eval_import;
+pub mod eval_conversation;
+pub mod eval_regression;
+pub mod eval_suite;
+pub mod pricing;
+pub mod metrics;
+pub mod telemetry;
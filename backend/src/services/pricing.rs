@@ -0,0 +1,68 @@
+use crate::db::types::models::ModelPriceRow;
+
+use super::types::pricing::TokenUsage;
+
+const TOKENS_PER_PRICE_UNIT: f64 = 1_000_000.0;
+
+/// Cost in USD of a request's tokens at the given price, `None` when the provider reported no usage.
+///
+/// Cached input and reasoning tokens are part of the input and output counts, they're billed at
+/// their own price when the model has one and at the input and output price otherwise.
+pub fn request_cost(price: &ModelPriceRow, usage: &TokenUsage) -> Option<f64> {
+    if usage.input_tokens.is_none() && usage.output_tokens.is_none() {
+        return None;
+    }
+
+    let input = usage.input_tokens.unwrap_or(0).max(0) as f64;
+    let cached_input = (usage.cached_input_tokens.unwrap_or(0).max(0) as f64).min(input);
+    let output = usage.output_tokens.unwrap_or(0).max(0) as f64;
+    let reasoning = (usage.reasoning_tokens.unwrap_or(0).max(0) as f64).min(output);
+
+    let cached_input_price = price.cached_input_per_million.unwrap_or(price.input_per_million);
+    let reasoning_price = price.reasoning_per_million.unwrap_or(price.output_per_million);
+
+    let cost = (input - cached_input) * price.input_per_million
+        + cached_input * cached_input_price
+        + (output - reasoning) * price.output_per_million
+        + reasoning * reasoning_price;
+
+    Some(cost / TOKENS_PER_PRICE_UNIT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn price(reasoning: Option<f64>, cached_input: Option<f64>) -> ModelPriceRow {
+        ModelPriceRow {
+            id: 1,
+            model_id: 1,
+            input_per_million: 2.0,
+            output_per_million: 8.0,
+            reasoning_per_million: reasoning,
+            cached_input_per_million: cached_input,
+            effective_from: chrono::NaiveDateTime::default(),
+            created_at: chrono::NaiveDateTime::default(),
+        }
+    }
+
+    #[test]
+    fn test_request_cost() {
+        let usage = TokenUsage {
+            input_tokens: Some(1_000),
+            cached_input_tokens: Some(400),
+            output_tokens: Some(500),
+            reasoning_tokens: Some(200),
+        };
+
+        // Without their own prices cached and reasoning tokens cost the same as the rest
+        let cost = request_cost(&price(None, None), &usage).unwrap();
+        assert!((cost - (1_000.0 * 2.0 + 500.0 * 8.0) / 1e6).abs() < 1e-12);
+
+        let cost = request_cost(&price(Some(10.0), Some(0.5)), &usage).unwrap();
+        let expected = (600.0 * 2.0 + 400.0 * 0.5 + 300.0 * 8.0 + 200.0 * 10.0) / 1e6;
+        assert!((cost - expected).abs() < 1e-12);
+
+        assert_eq!(request_cost(&price(None, None), &TokenUsage::default()), None);
+    }
+}
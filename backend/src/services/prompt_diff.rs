@@ -0,0 +1,363 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde_json::Value;
+use similar::{ChangeTag, TextDiff as SimilarTextDiff};
+
+use crate::db::types::{prompt::PromptRowWithModel, tool::ToolRow};
+
+use super::types::prompt_diff::{
+    DiffHunk, DiffLine, FieldChange, JsonChange, PromptVersionDiff, TextDiff, ToolChange,
+    ToolDefinition, ToolsDiff,
+};
+
+const CONTEXT_LINES: usize = 3;
+
+/// Diffs two versions of the same prompt, along with the tools each version has access to.
+pub fn diff_versions(
+    from: &PromptRowWithModel,
+    from_tools: &[ToolRow],
+    to: &PromptRowWithModel,
+    to_tools: &[ToolRow],
+) -> PromptVersionDiff {
+    let from_messages = pretty_json(from.messages.as_deref());
+    let to_messages = pretty_json(to.messages.as_deref());
+    let from_schema = pretty_json(from.json_schema.as_deref());
+    let to_schema = pretty_json(to.json_schema.as_deref());
+
+    let text = [
+        ("system", Some(from.system.clone()), Some(to.system.clone())),
+        ("user", from.user.clone(), to.user.clone()),
+        ("messages", from_messages, to_messages),
+        ("json_schema", from_schema, to_schema),
+    ]
+    .into_iter()
+    .filter_map(|(field, old, new)| {
+        text_diff(field, old.as_deref().unwrap_or(""), new.as_deref().unwrap_or(""))
+    })
+    .collect();
+
+    PromptVersionDiff {
+        prompt_id: to.id,
+        from_version_id: from.version_id,
+        from_version_number: from.version_number,
+        to_version_id: to.version_id,
+        to_version_number: to.version_number,
+        text,
+        json_schema: json_schema_changes(from.json_schema.as_deref(), to.json_schema.as_deref()),
+        config: config_changes(from, to),
+        tools: tools_diff(from_tools, to_tools),
+    }
+}
+
+/// Unified diff of two texts with `CONTEXT_LINES` lines of context, `None` when they're equal.
+pub fn text_diff(field: &str, old: &str, new: &str) -> Option<TextDiff> {
+    if old == new {
+        return None;
+    }
+
+    let diff = SimilarTextDiff::from_lines(old, new);
+    let mut unified_diff = diff.unified_diff();
+    unified_diff.context_radius(CONTEXT_LINES);
+
+    let hunks = unified_diff
+        .iter_hunks()
+        .map(|hunk| DiffHunk {
+            header: hunk.header().to_string(),
+            lines: hunk
+                .iter_changes()
+                .map(|change| DiffLine {
+                    tag: match change.tag() {
+                        ChangeTag::Equal => "equal",
+                        ChangeTag::Insert => "insert",
+                        ChangeTag::Delete => "delete",
+                    },
+                    old_line: change.old_index().map(|i| i + 1),
+                    new_line: change.new_index().map(|i| i + 1),
+                    content: change.value().trim_end_matches(['\n', '\r']).to_string(),
+                })
+                .collect(),
+        })
+        .collect();
+
+    let unified = unified_diff
+        .header(&format!("a/{}", field), &format!("b/{}", field))
+        .to_string();
+
+    Some(TextDiff {
+        field: field.to_string(),
+        unified,
+        hunks,
+    })
+}
+
+/// Structural diff of two JSON schemas. A schema that's missing or isn't valid JSON is
+/// treated as absent, so adding or removing a schema shows up as one change at the root.
+pub fn json_schema_changes(old: Option<&str>, new: Option<&str>) -> Vec<JsonChange> {
+    let parse = |schema: Option<&str>| schema.and_then(|s| serde_json::from_str::<Value>(s).ok());
+
+    let mut changes = Vec::new();
+    match (parse(old), parse(new)) {
+        (Some(old), Some(new)) => json_changes("", &old, &new, &mut changes),
+        (None, Some(new)) => changes.push(JsonChange {
+            path: String::new(),
+            kind: "added",
+            old: None,
+            new: Some(new),
+        }),
+        (Some(old), None) => changes.push(JsonChange {
+            path: String::new(),
+            kind: "removed",
+            old: Some(old),
+            new: None,
+        }),
+        (None, None) => {}
+    }
+
+    changes
+}
+
+fn json_changes(path: &str, old: &Value, new: &Value, changes: &mut Vec<JsonChange>) {
+    match (old, new) {
+        (Value::Object(old_map), Value::Object(new_map)) => {
+            let keys = old_map.keys().chain(new_map.keys()).collect::<BTreeSet<_>>();
+            for key in keys {
+                let child = format!("{}/{}", path, key.replace('~', "~0").replace('/', "~1"));
+                match (old_map.get(key), new_map.get(key)) {
+                    (Some(o), Some(n)) => json_changes(&child, o, n, changes),
+                    (Some(o), None) => changes.push(JsonChange {
+                        path: child,
+                        kind: "removed",
+                        old: Some(o.clone()),
+                        new: None,
+                    }),
+                    (None, Some(n)) => changes.push(JsonChange {
+                        path: child,
+                        kind: "added",
+                        old: None,
+                        new: Some(n.clone()),
+                    }),
+                    (None, None) => {}
+                }
+            }
+        }
+        (Value::Array(old_items), Value::Array(new_items)) => {
+            for i in 0..old_items.len().max(new_items.len()) {
+                let child = format!("{}/{}", path, i);
+                match (old_items.get(i), new_items.get(i)) {
+                    (Some(o), Some(n)) => json_changes(&child, o, n, changes),
+                    (Some(o), None) => changes.push(JsonChange {
+                        path: child,
+                        kind: "removed",
+                        old: Some(o.clone()),
+                        new: None,
+                    }),
+                    (None, Some(n)) => changes.push(JsonChange {
+                        path: child,
+                        kind: "added",
+                        old: None,
+                        new: Some(n.clone()),
+                    }),
+                    (None, None) => {}
+                }
+            }
+        }
+        (o, n) if o != n => changes.push(JsonChange {
+            path: path.to_string(),
+            kind: "changed",
+            old: Some(o.clone()),
+            new: Some(n.clone()),
+        }),
+        _ => {}
+    }
+}
+
+fn config_changes(from: &PromptRowWithModel, to: &PromptRowWithModel) -> Vec<FieldChange> {
+    let fields = [
+        ("provider", Value::from(from.provider_name.clone()), Value::from(to.provider_name.clone())),
+        ("model", Value::from(from.model_name.clone()), Value::from(to.model_name.clone())),
+        ("max_tokens", Value::from(from.max_tokens), Value::from(to.max_tokens)),
+        ("temperature", Value::from(from.temperature), Value::from(to.temperature)),
+        ("json_mode", Value::from(from.json_mode), Value::from(to.json_mode)),
+        ("prompt_type", Value::from(from.prompt_type.clone()), Value::from(to.prompt_type.clone())),
+        ("is_chat", Value::from(from.is_chat), Value::from(to.is_chat)),
+        (
+            "reasoning_effort",
+            Value::from(from.reasoning_effort.clone()),
+            Value::from(to.reasoning_effort.clone()),
+        ),
+    ];
+
+    fields
+        .into_iter()
+        .filter(|(_, old, new)| old != new)
+        .map(|(field, old, new)| FieldChange {
+            field: field.to_string(),
+            old,
+            new,
+        })
+        .collect()
+}
+
+fn tools_diff(from_tools: &[ToolRow], to_tools: &[ToolRow]) -> ToolsDiff {
+    let by_tool_name = |tools: &[ToolRow]| {
+        tools
+            .iter()
+            .map(|t| (t.tool_name.clone(), tool_definition(t)))
+            .collect::<BTreeMap<_, _>>()
+    };
+    let from = by_tool_name(from_tools);
+    let mut to = by_tool_name(to_tools);
+
+    let mut diff = ToolsDiff::default();
+    for (tool_name, old) in from {
+        let Some(new) = to.remove(&tool_name) else {
+            diff.removed.push(old);
+            continue;
+        };
+
+        let fields = [
+            ("name", Value::from(old.name), Value::from(new.name)),
+            ("description", Value::from(old.description), Value::from(new.description)),
+            ("strict", Value::from(old.strict), Value::from(new.strict)),
+        ]
+        .into_iter()
+        .filter(|(_, old, new)| old != new)
+        .map(|(field, old, new)| FieldChange {
+            field: field.to_string(),
+            old,
+            new,
+        })
+        .collect::<Vec<_>>();
+
+        let mut parameters = Vec::new();
+        json_changes("", &old.parameters, &new.parameters, &mut parameters);
+
+        if fields.is_empty() && parameters.is_empty() {
+            diff.unchanged.push(tool_name);
+        } else {
+            diff.changed.push(ToolChange {
+                tool_name,
+                fields,
+                parameters,
+            });
+        }
+    }
+    diff.added = to.into_values().collect();
+
+    diff
+}
+
+fn tool_definition(tool: &ToolRow) -> ToolDefinition {
+    ToolDefinition {
+        name: tool.name.clone(),
+        tool_name: tool.tool_name.clone(),
+        description: tool.description.clone(),
+        // Unparseable parameters still diff as a plain string
+        parameters: serde_json::from_str(&tool.parameters)
+            .unwrap_or_else(|_| Value::from(tool.parameters.clone())),
+        strict: tool.strict,
+    }
+}
+
+// Pretty printed so JSON stored on one line still diffs line by line
+fn pretty_json(json: Option<&str>) -> Option<String> {
+    json.map(|j| match serde_json::from_str::<Value>(j) {
+        Ok(value) => serde_json::to_string_pretty(&value).unwrap_or_else(|_| j.to_string()),
+        Err(_) => j.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_text_diff_has_context_hunks() {
+        let old = "a\nb\nc\nd\ne\nf\ng\nh\ni\nj\n";
+        let new = "a\nb\nc\nd\ne\nF\ng\nh\ni\nj\n";
+
+        let diff = text_diff("system", old, new).unwrap();
+        assert_eq!(diff.hunks.len(), 1);
+        assert_eq!(diff.hunks[0].header, "@@ -3,7 +3,7 @@");
+        assert_eq!(diff.hunks[0].lines.len(), 8);
+        assert!(diff.unified.starts_with("--- a/system\n+++ b/system\n@@ -3,7 +3,7 @@\n"));
+
+        let deleted = &diff.hunks[0].lines[3];
+        assert_eq!((deleted.tag, deleted.old_line, deleted.new_line), ("delete", Some(6), None));
+        assert_eq!(deleted.content, "f");
+
+        assert!(text_diff("system", old, old).is_none());
+    }
+
+    #[test]
+    fn test_json_schema_changes_are_structural() {
+        let old = json!({
+            "type": "object",
+            "properties": { "name": { "type": "string" }, "age": { "type": "integer" } },
+            "required": ["name"]
+        });
+        let new = json!({
+            "type": "object",
+            "properties": { "name": { "type": "string" }, "email/work": { "type": "string" } },
+            "required": ["name", "email/work"]
+        });
+
+        let changes = json_schema_changes(Some(&old.to_string()), Some(&new.to_string()));
+        let summary = changes.iter().map(|c| (c.path.as_str(), c.kind)).collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            vec![
+                ("/properties/age", "removed"),
+                ("/properties/email~1work", "added"),
+                ("/required/1", "added"),
+            ]
+        );
+
+        assert_eq!(json_schema_changes(None, Some("{}"))[0].kind, "added");
+        assert!(json_schema_changes(None, None).is_empty());
+    }
+
+    #[test]
+    fn test_tools_diff_compares_definitions() {
+        let tool = |name: &str, tool_name: &str, parameters: Value| ToolRow {
+            id: 0,
+            name: name.to_string(),
+            tool_name: tool_name.to_string(),
+            description: "Looks things up".to_string(),
+            parameters: parameters.to_string(),
+            strict: true,
+            created_at: chrono::NaiveDateTime::default(),
+            updated_at: chrono::NaiveDateTime::default(),
+        };
+        let city = json!({ "type": "object", "properties": { "city": { "type": "string" } } });
+        let units = json!({
+            "type": "object",
+            "properties": { "city": { "type": "string" }, "units": { "type": "string" } }
+        });
+
+        let from = [
+            tool("weather-v1", "get_weather", city.clone()),
+            tool("search", "search", json!({})),
+            tool("time", "get_time", json!({})),
+        ];
+        let to = [
+            tool("weather-v2", "get_weather", units),
+            tool("time", "get_time", json!({})),
+            tool("translate", "translate", json!({})),
+        ];
+
+        let diff = tools_diff(&from, &to);
+        assert_eq!(diff.unchanged, vec!["get_time"]);
+        assert_eq!(diff.removed[0].tool_name, "search");
+        assert_eq!(diff.added[0].tool_name, "translate");
+
+        let change = &diff.changed[0];
+        assert_eq!(change.tool_name, "get_weather");
+        assert_eq!(change.fields.len(), 1);
+        assert_eq!(change.fields[0].field, "name");
+        assert_eq!(change.parameters.len(), 1);
+        assert_eq!(change.parameters[0].path, "/properties/units");
+        assert_eq!(change.parameters[0].kind, "added");
+    }
+}
@@ -0,0 +1,270 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    fs,
+    path::Path,
+};
+
+use anyhow::{Context, Result};
+
+use crate::{
+    common::types::chat_request::ChatCompletionRequestMessage,
+    db::init::DbData,
+};
+
+use super::{
+    bundles::{create_version, version_matches},
+    types::{bundle::BundlePromptVersion, prompt_sync::PromptManifest},
+};
+
+const MANIFEST_FILE: &str = "prompt.yaml";
+const SYNC_MESSAGE: &str = "Synced from prompts directory";
+
+/// Reconciles every `<dir>/<prompt>/prompt.yaml` into the database. A prompt gets a new
+/// version only when its files no longer match the active version, and is locked against
+/// UI/API edits when `read_only` is set. Prompts that aren't in the directory are left alone.
+/// Changes to the files are reviewed in git, so synced versions skip the approval gate.
+pub async fn sync_prompts_dir(db: &DbData, dir: &Path, read_only: bool) -> Result<()> {
+    let mut prompt_dirs = fs::read_dir(dir)
+        .with_context(|| format!("Failed to read prompts directory {}", dir.display()))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.join(MANIFEST_FILE).is_file())
+        .collect::<Vec<_>>();
+    prompt_dirs.sort();
+
+    let models: HashMap<(String, String), i64> = db
+        .model
+        .list_models()
+        .await?
+        .into_iter()
+        .map(|m| ((String::from(m.provider_name), m.model_name), m.id))
+        .collect();
+
+    let tools: HashMap<String, i64> = db
+        .tool
+        .list_tools()
+        .await?
+        .into_iter()
+        .map(|t| (t.name, t.id))
+        .collect();
+
+    for prompt_dir in prompt_dirs {
+        sync_prompt(db, &prompt_dir, &models, &tools, read_only)
+            .await
+            .with_context(|| format!("Failed to sync prompt {}", prompt_dir.display()))?;
+    }
+
+    Ok(())
+}
+
+async fn sync_prompt(
+    db: &DbData,
+    prompt_dir: &Path,
+    models: &HashMap<(String, String), i64>,
+    tools: &HashMap<String, i64>,
+    read_only: bool,
+) -> Result<()> {
+    let manifest: PromptManifest =
+        serde_yaml::from_str(&read_file(prompt_dir, MANIFEST_FILE)?)?;
+
+    let key = match &manifest.key {
+        Some(key) => key.clone(),
+        None => prompt_dir
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .context("Prompt directory has no name")?,
+    };
+
+    let model_id = *models
+        .get(&(manifest.provider.clone(), manifest.model.clone()))
+        .with_context(|| {
+            format!("Model '{}' from provider '{}' does not exist", manifest.model, manifest.provider)
+        })?;
+
+    let tool_ids = manifest
+        .tools
+        .iter()
+        .map(|name| {
+            tools
+                .get(name)
+                .copied()
+                .with_context(|| format!("Tool '{}' does not exist", name))
+        })
+        .collect::<Result<BTreeSet<_>>>()?;
+
+    let version = read_version(prompt_dir, &manifest)?;
+
+    let existing = db
+        .prompt
+        .list_prompts()
+        .await?
+        .into_iter()
+        .find(|p| p.key == key);
+
+    match existing {
+        Some(active) => {
+            let active_tool_ids = db
+                .tool
+                .get_tools_by_prompt_version(active.version_id)
+                .await?
+                .into_iter()
+                .map(|t| t.id)
+                .collect::<BTreeSet<_>>();
+
+            if version_matches(&version, model_id, &active) && active_tool_ids == tool_ids {
+                tracing::info!("Prompt '{}' is up to date", key);
+            } else {
+                let (_, version_id) =
+                    create_version(db, Some(active.id), &key, &version, model_id, SYNC_MESSAGE, false)
+                        .await?;
+                associate_tools(db, version_id, &tool_ids).await?;
+                tracing::info!("Prompt '{}' changed, created a new version", key);
+            }
+
+            if active.read_only != read_only {
+                db.prompt.set_prompt_read_only(active.id, read_only).await?;
+            }
+        }
+        None => {
+            let (id, version_id) =
+                create_version(db, None, &key, &version, model_id, SYNC_MESSAGE, false).await?;
+            associate_tools(db, version_id, &tool_ids).await?;
+            if read_only {
+                db.prompt.set_prompt_read_only(id, true).await?;
+            }
+            tracing::info!("Prompt '{}' created", key);
+        }
+    }
+
+    Ok(())
+}
+
+async fn associate_tools(db: &DbData, version_id: i64, tool_ids: &BTreeSet<i64>) -> Result<()> {
+    for tool_id in tool_ids {
+        db.tool
+            .associate_tool_with_prompt_version(*tool_id, version_id)
+            .await?;
+    }
+
+    Ok(())
+}
+
+fn read_version(prompt_dir: &Path, manifest: &PromptManifest) -> Result<BundlePromptVersion> {
+    let read_optional = |file: &Option<String>| -> Result<Option<String>> {
+        file.as_deref().map(|f| read_file(prompt_dir, f)).transpose()
+    };
+
+    let messages = match read_optional(&manifest.messages_file)? {
+        Some(content) => {
+            let messages: Vec<ChatCompletionRequestMessage> = serde_yaml::from_str(&content)
+                .context("Invalid message templates")?;
+            if messages.is_empty() {
+                anyhow::bail!("Message templates cannot be empty");
+            }
+            Some(messages)
+        }
+        None => None,
+    };
+
+    let json_schema = match read_optional(&manifest.json_schema_file)? {
+        Some(content) => {
+            let schema: serde_json::Value =
+                serde_json::from_str(&content).context("Invalid JSON schema")?;
+            Some(serde_json::to_string_pretty(&schema)?)
+        }
+        None => None,
+    };
+
+    Ok(BundlePromptVersion {
+        version_number: 0,
+        provider: manifest.provider.clone(),
+        model: manifest.model.clone(),
+        system: read_optional(&manifest.system_file)?.unwrap_or_default(),
+        user: read_optional(&manifest.user_file)?,
+        messages,
+        max_tokens: manifest.max_tokens,
+        temperature: manifest.temperature,
+        json_mode: manifest.json_mode,
+        json_schema,
+        prompt_type: manifest.prompt_type.clone(),
+        is_chat: manifest.is_chat,
+        reasoning_effort: manifest.reasoning_effort.clone(),
+        tools: manifest.tools.clone(),
+    })
+}
+
+// Trailing newlines are an editor artifact, not part of the template
+fn read_file(prompt_dir: &Path, file: &str) -> Result<String> {
+    let path = prompt_dir.join(file);
+    let content = fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+
+    Ok(content.trim_end_matches(['\n', '\r']).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_version_from_files() {
+        let dir = std::env::temp_dir().join(format!("llmkit-prompt-sync-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("system.md"), "You are {{ name }}.\n").unwrap();
+        fs::write(dir.join("schema.json"), "{\"type\": \"object\"}").unwrap();
+        fs::write(
+            dir.join(MANIFEST_FILE),
+            "provider: openrouter\nmodel: openai/gpt-4o\nmax_tokens: 256\ntemperature: 0.2\n\
+             prompt_type: dynamic_system\nsystem_file: system.md\njson_schema_file: schema.json\n",
+        )
+        .unwrap();
+
+        let manifest: PromptManifest =
+            serde_yaml::from_str(&read_file(&dir, MANIFEST_FILE).unwrap()).unwrap();
+        let version = read_version(&dir, &manifest).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(version.system, "You are {{ name }}.");
+        assert_eq!(version.user, None);
+        assert_eq!(version.json_schema.as_deref(), Some("{\n  \"type\": \"object\"\n}"));
+        assert!(!version.is_chat);
+    }
+
+    #[tokio::test]
+    async fn test_sync_reconciles_prompts_dir() {
+        let db = DbData::in_memory().await.unwrap();
+        let model = db.model.list_models().await.unwrap().remove(0);
+
+        let dir = std::env::temp_dir().join(format!("llmkit-prompt-reconcile-{}", std::process::id()));
+        let prompt_dir = dir.join("synced-prompt");
+        fs::create_dir_all(&prompt_dir).unwrap();
+        fs::write(prompt_dir.join("system.md"), "You are helpful.\n").unwrap();
+        fs::write(
+            prompt_dir.join(MANIFEST_FILE),
+            format!(
+                "provider: {}\nmodel: {}\nmax_tokens: 256\ntemperature: 0.2\n\
+                 prompt_type: static\nsystem_file: system.md\n",
+                String::from(model.provider_name),
+                model.model_name
+            ),
+        )
+        .unwrap();
+
+        sync_prompts_dir(&db, &dir, true).await.unwrap();
+        let prompt = db.prompt.get_prompt_by_key("synced-prompt").await.unwrap();
+        assert!(prompt.read_only);
+        assert_eq!(prompt.system, "You are helpful.");
+
+        // Unchanged files don't add a version
+        sync_prompts_dir(&db, &dir, true).await.unwrap();
+        assert_eq!(db.prompt.get_prompt_versions(prompt.id).await.unwrap().len(), 1);
+
+        fs::write(prompt_dir.join("system.md"), "You are terse.\n").unwrap();
+        sync_prompts_dir(&db, &dir, false).await.unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        let synced = db.prompt.get_prompt(prompt.id).await.unwrap();
+        assert_eq!(db.prompt.get_prompt_versions(prompt.id).await.unwrap().len(), 2);
+        assert_eq!(synced.system, "You are terse.");
+        assert!(!synced.read_only);
+    }
+}
@@ -41,8 +41,7 @@ pub struct OpenAiProvider<'a> {
 
 impl<'a> OpenAiProvider<'a> {
     pub fn new(props: &'a LlmServiceRequest) -> Result<Self, LlmError> {
-        let api_key = std::env::var("OPENAI_API_KEY")
-            .map_err(|_| LlmError::InvalidConfig("Missing OPENAI_API_KEY".to_string()))?;
+        let api_key = props.provider_api_key("OPENAI_API_KEY")?;
 
         let config = config::OpenAIConfig::new().with_api_key(api_key);
 
@@ -55,8 +54,7 @@ impl<'a> OpenAiProvider<'a> {
     }
 
     pub fn new_azure(props: &'a LlmServiceRequest) -> Result<Self, LlmError> {
-        let api_key = std::env::var("AZURE_API_KEY")
-            .map_err(|_| LlmError::InvalidConfig("Missing AZURE_API_KEY".to_string()))?;
+        let api_key = props.provider_api_key("AZURE_API_KEY")?;
 
         let base_url = props.base_url.clone()
             .ok_or_else(|| LlmError::InvalidConfig("Missing AZURE_BASE_URL".to_string()))?;
@@ -0,0 +1,118 @@
+use anyhow::Result;
+use axum::http::HeaderMap;
+use opentelemetry::{global, propagation::Extractor, trace::TracerProvider as _};
+use opentelemetry_otlp::SpanExporter;
+use opentelemetry_sdk::{propagation::TraceContextPropagator, trace::SdkTracerProvider, Resource};
+use tracing::{field::Empty, Span};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Layer};
+
+use crate::common::types::chat_response::LlmServiceChatCompletionResponse;
+
+use super::types::{llm_error::LlmError, llm_service::LlmServiceRequest, pricing::TokenUsage};
+
+const DEFAULT_SERVICE_NAME: &str = "llmkit";
+
+// Only our own spans are exported, dependencies' log lines would flood the traces
+const EXPORTED_SPANS: &str = "backend=info";
+
+/// Sets up log output, plus OTLP trace export when `OTEL_EXPORTER_OTLP_ENDPOINT` or
+/// `OTEL_EXPORTER_OTLP_TRACES_ENDPOINT` is set. The returned provider has to be shut down
+/// to flush the last spans.
+pub fn init_tracing(log_level: &str) -> Result<Option<SdkTracerProvider>> {
+    let fmt_layer = tracing_subscriber::fmt::layer().with_filter(EnvFilter::new(log_level));
+
+    let otlp_enabled = ["OTEL_EXPORTER_OTLP_ENDPOINT", "OTEL_EXPORTER_OTLP_TRACES_ENDPOINT"]
+        .iter()
+        .any(|var| std::env::var(var).is_ok_and(|v| !v.is_empty()));
+
+    if !otlp_enabled {
+        tracing_subscriber::registry().with(fmt_layer).init();
+        return Ok(None);
+    }
+
+    // The exporter picks up the endpoint, headers and timeout from the standard OTEL_* variables
+    let exporter = SpanExporter::builder().with_http().build()?;
+
+    let mut resource = Resource::builder();
+    if std::env::var("OTEL_SERVICE_NAME").is_err() {
+        resource = resource.with_service_name(DEFAULT_SERVICE_NAME);
+    }
+
+    let provider = SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(resource.build())
+        .build();
+
+    global::set_text_map_propagator(TraceContextPropagator::new());
+
+    let otel_layer = tracing_opentelemetry::layer()
+        .with_tracer(provider.tracer(DEFAULT_SERVICE_NAME))
+        .with_filter(EnvFilter::new(EXPORTED_SPANS));
+
+    tracing_subscriber::registry().with(fmt_layer).with(otel_layer).init();
+
+    Ok(Some(provider))
+}
+
+struct HeaderExtractor<'a>(&'a HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|v| v.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(|k| k.as_str()).collect()
+    }
+}
+
+/// Continues the caller's trace when the request carries a W3C `traceparent` header.
+pub fn continue_trace(span: &Span, headers: &HeaderMap) {
+    let parent = global::get_text_map_propagator(|propagator| propagator.extract(&HeaderExtractor(headers)));
+    span.set_parent(parent);
+}
+
+/// Span of one attempt at a chat completion, named and tagged per the GenAI semantic conventions.
+pub fn chat_span(props: &LlmServiceRequest, attempt: i64) -> Span {
+    tracing::info_span!(
+        "gen_ai.chat",
+        otel.name = %format!("chat {}", props.request.model),
+        otel.kind = "client",
+        otel.status_code = Empty,
+        gen_ai.operation.name = "chat",
+        gen_ai.system = %String::from(props.provider.clone()),
+        gen_ai.request.model = %props.request.model,
+        gen_ai.request.max_tokens = props.request.max_tokens,
+        gen_ai.request.temperature = props.request.temperature,
+        gen_ai.response.id = Empty,
+        gen_ai.response.model = Empty,
+        gen_ai.response.finish_reasons = Empty,
+        gen_ai.usage.input_tokens = Empty,
+        gen_ai.usage.output_tokens = Empty,
+        llmkit.prompt.key = %props.prompt_key,
+        llmkit.attempt = attempt,
+        error.type = Empty,
+    )
+}
+
+/// Tags a span created with `chat_span`, or the request's own span, with what the model returned.
+pub fn record_response(span: &Span, response: &LlmServiceChatCompletionResponse) {
+    let usage = response.usage.as_ref().map(TokenUsage::from).unwrap_or_default();
+    let finish_reasons = response
+        .choices
+        .iter()
+        .filter_map(|c| c.finish_reason.as_deref())
+        .collect::<Vec<_>>();
+
+    span.record("gen_ai.response.id", response.id.as_str());
+    span.record("gen_ai.response.model", response.model.as_str());
+    span.record("gen_ai.response.finish_reasons", tracing::field::debug(&finish_reasons));
+    span.record("gen_ai.usage.input_tokens", usage.input_tokens);
+    span.record("gen_ai.usage.output_tokens", usage.output_tokens);
+}
+
+pub fn record_error(span: &Span, error: &LlmError) {
+    span.record("otel.status_code", "ERROR");
+    span.record("error.type", error.variant_name().as_str());
+}
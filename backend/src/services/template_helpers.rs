@@ -0,0 +1,360 @@
+use std::{collections::HashMap, fmt::Write, str::FromStr};
+
+use chrono::{DateTime, Local, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde_json::Value;
+use tera::{Error, Result, Tera};
+use tiktoken_rs::{
+    cl100k_base_singleton, o200k_base_singleton, p50k_base_singleton, p50k_edit_singleton,
+    r50k_base_singleton, tokenizer::{get_tokenizer, Tokenizer}, CoreBPE,
+};
+
+/// A filter or function registered for prompt templates, as shown in the editor.
+#[derive(Debug, Clone, Copy)]
+pub struct TemplateHelper {
+    pub name: &'static str,
+    pub kind: &'static str,
+    pub signature: &'static str,
+    pub description: &'static str,
+    pub example: &'static str,
+}
+
+pub const TEMPLATE_HELPERS: &[TemplateHelper] = &[
+    TemplateHelper {
+        name: "tojson",
+        kind: "filter",
+        signature: "tojson(pretty=true)",
+        description: "Serializes a value to JSON, pretty printed unless `pretty=false`.",
+        example: "{{ user | tojson }}",
+    },
+    TemplateHelper {
+        name: "truncate_tokens",
+        kind: "filter",
+        signature: "truncate_tokens(n, end=\"\")",
+        description: "Cuts text down to at most `n` tokens using the prompt model's tokenizer, appending `end` when truncated.",
+        example: "{{ document | truncate_tokens(n=500, end=\"...\") }}",
+    },
+    TemplateHelper {
+        name: "bullet_list",
+        kind: "filter",
+        signature: "bullet_list(marker=\"- \")",
+        description: "Renders an array as one bulleted line per item.",
+        example: "{{ rules | bullet_list }}",
+    },
+    TemplateHelper {
+        name: "xml_escape",
+        kind: "filter",
+        signature: "xml_escape",
+        description: "Escapes `&`, `<`, `>`, `\"` and `'` so text can sit safely inside XML tags.",
+        example: "<document>{{ document | xml_escape }}</document>",
+    },
+    TemplateHelper {
+        name: "examples",
+        kind: "filter",
+        signature: "examples(tag=\"example\", limit, enabled=true)",
+        description: "Wraps each item of an array in `<example>` tags inside an `<examples>` block. Renders nothing when the array is empty or `enabled` is false.",
+        example: "{{ few_shots | examples(limit=3, enabled=include_examples) }}",
+    },
+    TemplateHelper {
+        name: "now",
+        kind: "function",
+        signature: "now(tz=\"UTC\", format, timestamp=false, utc)",
+        description: "Current time in the given IANA timezone, RFC 3339 unless a strftime `format` is given. `timestamp=true` returns the unix timestamp. Like Tera's built-in `now`, `utc=false` without a `tz` gives the server's local time.",
+        example: "{{ now(tz=\"Europe/Paris\", format=\"%A %d %B %Y\") }}",
+    },
+];
+
+/// Registers every helper in `TEMPLATE_HELPERS` on the Tera instance. `truncate_tokens`
+/// counts tokens with the tokenizer of `model_name`.
+pub fn register_helpers(tera: &mut Tera, model_name: &str) {
+    // Resolved lazily so templates that never truncate don't pay for loading a tokenizer
+    let model_name = model_name.to_string();
+
+    tera.register_filter("tojson", tojson_filter);
+    tera.register_filter(
+        "truncate_tokens",
+        move |value: &Value, args: &HashMap<String, Value>| {
+            truncate_tokens_filter(tokenizer_for_model(&model_name), value, args)
+        },
+    );
+    tera.register_filter("bullet_list", bullet_list_filter);
+    tera.register_filter("xml_escape", xml_escape_filter);
+    tera.register_filter("examples", examples_filter);
+    tera.register_function("now", now_function);
+}
+
+fn tokenizer_for_model(model_name: &str) -> &'static CoreBPE {
+    // OpenRouter model names are prefixed with the vendor, e.g. `openai/gpt-4o`
+    let name = model_name.rsplit('/').next().unwrap_or(model_name);
+
+    match get_tokenizer(name) {
+        Some(Tokenizer::O200kBase) => o200k_base_singleton(),
+        Some(Tokenizer::P50kBase) => p50k_base_singleton(),
+        Some(Tokenizer::P50kEdit) => p50k_edit_singleton(),
+        Some(Tokenizer::R50kBase) | Some(Tokenizer::Gpt2) => r50k_base_singleton(),
+        // Models without a public tokenizer (Claude, Gemini, ...) get cl100k as an approximation
+        Some(Tokenizer::Cl100kBase) | None => cl100k_base_singleton(),
+    }
+}
+
+fn tojson_filter(value: &Value, args: &HashMap<String, Value>) -> Result<Value> {
+    let pretty = optional_bool_arg("tojson", "pretty", args)?.unwrap_or(true);
+
+    let json = if pretty {
+        serde_json::to_string_pretty(value)
+    } else {
+        serde_json::to_string(value)
+    }
+    .map_err(Error::json)?;
+
+    Ok(Value::String(json))
+}
+
+fn truncate_tokens_filter(
+    bpe: &CoreBPE,
+    value: &Value,
+    args: &HashMap<String, Value>,
+) -> Result<Value> {
+    let text = string_value("truncate_tokens", value)?;
+    let max_tokens = args
+        .get("n")
+        .and_then(|n| n.as_u64())
+        .ok_or_else(|| Error::msg("Filter `truncate_tokens` expected a positive integer argument `n`"))?
+        as usize;
+    let end = args.get("end").and_then(|e| e.as_str()).unwrap_or("");
+
+    let tokens = bpe.encode_ordinary(text);
+    if tokens.len() <= max_tokens {
+        return Ok(value.clone());
+    }
+
+    // The cut can land in the middle of a multi-byte character, so back off until it decodes
+    let mut cut = max_tokens;
+    let truncated = loop {
+        match bpe.decode(tokens[..cut].to_vec()) {
+            Ok(text) => break text,
+            Err(_) if cut > 0 => cut -= 1,
+            Err(e) => return Err(Error::msg(format!("Filter `truncate_tokens` failed: {}", e))),
+        }
+    };
+
+    Ok(Value::String(format!("{}{}", truncated, end)))
+}
+
+fn bullet_list_filter(value: &Value, args: &HashMap<String, Value>) -> Result<Value> {
+    let items = array_value("bullet_list", value)?;
+    let marker = args.get("marker").and_then(|m| m.as_str()).unwrap_or("- ");
+
+    let lines = items
+        .iter()
+        .map(|item| format!("{}{}", marker, display_value(item)))
+        .collect::<Vec<_>>();
+
+    Ok(Value::String(lines.join("\n")))
+}
+
+fn xml_escape_filter(value: &Value, _: &HashMap<String, Value>) -> Result<Value> {
+    let text = string_value("xml_escape", value)?;
+
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+
+    Ok(Value::String(escaped))
+}
+
+fn examples_filter(value: &Value, args: &HashMap<String, Value>) -> Result<Value> {
+    let items = array_value("examples", value)?;
+    let tag = args.get("tag").and_then(|t| t.as_str()).unwrap_or("example");
+    let limit = args
+        .get("limit")
+        .and_then(|l| l.as_u64())
+        .map(|l| l as usize)
+        .unwrap_or(items.len());
+    let enabled = optional_bool_arg("examples", "enabled", args)?.unwrap_or(true);
+
+    if !enabled || items.is_empty() || limit == 0 {
+        return Ok(Value::String(String::new()));
+    }
+
+    let mut block = format!("<{}s>\n", tag);
+    for item in items.iter().take(limit) {
+        block.push_str(&format!("<{tag}>\n{}\n</{tag}>\n", display_value(item), tag = tag));
+    }
+    block.push_str(&format!("</{}s>", tag));
+
+    Ok(Value::String(block))
+}
+
+// Replaces Tera's built-in `now`, so its `timestamp` and `utc` arguments keep working
+fn now_function(args: &HashMap<String, Value>) -> Result<Value> {
+    let timestamp = optional_bool_arg("now", "timestamp", args)?.unwrap_or(false);
+    let utc = optional_bool_arg("now", "utc", args)?;
+    let tz_name = args.get("tz").and_then(|t| t.as_str());
+    let format = args.get("format").and_then(|f| f.as_str());
+
+    if utc == Some(true) && tz_name.is_some_and(|tz| tz != "UTC") {
+        return Err(Error::msg("Function `now` can't take both `utc=true` and a `tz`"));
+    }
+
+    if timestamp {
+        return Ok(Value::from(Utc::now().timestamp()));
+    }
+
+    let formatted = match (tz_name, utc) {
+        (None, Some(false)) => format_time(Local::now(), format)?,
+        _ => {
+            let tz_name = tz_name.unwrap_or("UTC");
+            let tz = Tz::from_str(tz_name)
+                .map_err(|_| Error::msg(format!("Function `now` received an unknown timezone `{}`", tz_name)))?;
+            format_time(Utc::now().with_timezone(&tz), format)?
+        }
+    };
+
+    Ok(Value::String(formatted))
+}
+
+fn format_time<T: TimeZone>(time: DateTime<T>, format: Option<&str>) -> Result<String>
+where
+    T::Offset: std::fmt::Display,
+{
+    let Some(format) = format else {
+        return Ok(time.to_rfc3339());
+    };
+
+    // `to_string` would panic on a bad specifier, writing it out reports it instead
+    let mut formatted = String::new();
+    write!(formatted, "{}", time.format(format))
+        .map_err(|_| Error::msg(format!("Function `now` received an invalid `format` `{}`", format)))?;
+    Ok(formatted)
+}
+
+fn optional_bool_arg(helper: &str, name: &str, args: &HashMap<String, Value>) -> Result<Option<bool>> {
+    match args.get(name) {
+        Some(Value::Bool(b)) => Ok(Some(*b)),
+        Some(_) => Err(Error::msg(format!("`{}` expected `{}` to be a boolean", helper, name))),
+        None => Ok(None),
+    }
+}
+
+fn string_value<'a>(helper: &str, value: &'a Value) -> Result<&'a str> {
+    value
+        .as_str()
+        .ok_or_else(|| Error::msg(format!("Filter `{}` was used on a value that isn't a string", helper)))
+}
+
+fn array_value<'a>(helper: &str, value: &'a Value) -> Result<&'a Vec<Value>> {
+    value
+        .as_array()
+        .ok_or_else(|| Error::msg(format!("Filter `{}` was used on a value that isn't an array", helper)))
+}
+
+// Strings render as-is, anything else as pretty JSON
+fn display_value(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => serde_json::to_string_pretty(other).unwrap_or_default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tera::Context;
+
+    fn render(template: &str, ctx: Value) -> Result<String> {
+        let mut tera = Tera::default();
+        tera.add_raw_template("test", template)?;
+        register_helpers(&mut tera, "openai/gpt-4o");
+        tera.render("test", &Context::from_value(ctx)?)
+    }
+
+    #[test]
+    fn test_tojson_filter() {
+        let ctx = json!({ "user": { "name": "Bob" } });
+
+        assert_eq!(
+            render("{{ user | tojson }}", ctx.clone()).unwrap(),
+            "{\n  \"name\": \"Bob\"\n}"
+        );
+        assert_eq!(
+            render("{{ user | tojson(pretty=false) }}", ctx).unwrap(),
+            "{\"name\":\"Bob\"}"
+        );
+    }
+
+    #[test]
+    fn test_truncate_tokens_filter() {
+        let ctx = json!({ "text": "one two three four five six" });
+
+        assert_eq!(
+            render("{{ text | truncate_tokens(n=3, end=\"...\") }}", ctx.clone()).unwrap(),
+            "one two three..."
+        );
+        assert_eq!(
+            render("{{ text | truncate_tokens(n=100, end=\"...\") }}", ctx.clone()).unwrap(),
+            "one two three four five six"
+        );
+        assert!(render("{{ text | truncate_tokens }}", ctx).is_err());
+    }
+
+    #[test]
+    fn test_bullet_list_and_xml_escape_filters() {
+        let ctx = json!({ "rules": ["Be brief", "Cite <sources>"] });
+
+        assert_eq!(
+            render("{{ rules | bullet_list }}", ctx.clone()).unwrap(),
+            "- Be brief\n- Cite <sources>"
+        );
+        assert_eq!(
+            render("{{ rules | last | xml_escape }}", ctx).unwrap(),
+            "Cite &lt;sources&gt;"
+        );
+    }
+
+    #[test]
+    fn test_examples_filter() {
+        let ctx = json!({ "shots": ["a", "b", "c"], "none": [] });
+
+        assert_eq!(
+            render("{{ shots | examples(limit=2) }}", ctx.clone()).unwrap(),
+            "<examples>\n<example>\na\n</example>\n<example>\nb\n</example>\n</examples>"
+        );
+        assert_eq!(render("{{ none | examples }}", ctx.clone()).unwrap(), "");
+        assert_eq!(render("{{ shots | examples(enabled=false) }}", ctx).unwrap(), "");
+    }
+
+    #[test]
+    fn test_now_function() {
+        assert_eq!(
+            render("{{ now(tz=\"Asia/Tokyo\", format=\"%Z\") }}", json!({})).unwrap(),
+            "JST"
+        );
+        assert!(render("{{ now(tz=\"Mars/Olympus\") }}", json!({})).is_err());
+        assert!(render("{{ now(format=\"%Q\") }}", json!({})).is_err());
+    }
+
+    #[test]
+    fn test_now_keeps_tera_arguments() {
+        let timestamp = render("{{ now(timestamp=true) }}", json!({})).unwrap();
+        assert!((timestamp.parse::<i64>().unwrap() - Utc::now().timestamp()).abs() < 5);
+
+        let utc = render("{{ now(utc=true) }}", json!({})).unwrap();
+        assert!(DateTime::parse_from_rfc3339(&utc).unwrap().offset().local_minus_utc() == 0);
+
+        let local = render("{{ now(utc=false) }}", json!({})).unwrap();
+        assert!(DateTime::parse_from_rfc3339(&local).is_ok());
+
+        assert!(render("{{ now(utc=true, tz=\"Asia/Tokyo\") }}", json!({})).is_err());
+        assert!(render("{{ now(utc=\"yes\") }}", json!({})).is_err());
+    }
+}
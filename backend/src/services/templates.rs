@@ -0,0 +1,269 @@
+use std::collections::{BTreeSet, HashMap};
+
+use tera::{ast::Node, Template, Tera};
+
+use crate::{
+    common::types::chat_request::ChatCompletionRequestMessage,
+    db::{snippets::SnippetRepository, types::snippet::SnippetRow},
+};
+
+use super::template_helpers::register_helpers;
+
+/// Names prompt templates are registered under next to the snippets, see `LlmServiceRequest`.
+/// Message templates are registered as `message_0`, `message_1` and so on.
+const RESERVED_TEMPLATE_NAMES: &[&str] = &["system_prompt", "user_prompt"];
+
+/// Whether a snippet under this key would clash with a prompt's own templates.
+pub fn is_reserved_template_name(key: &str) -> bool {
+    RESERVED_TEMPLATE_NAMES.contains(&key)
+        || key
+            .strip_prefix("message_")
+            .is_some_and(|i| !i.is_empty() && i.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// Every template of a prompt version: system, user and the content of each message template.
+/// Message templates that don't parse are left out, rendering reports them.
+pub fn prompt_templates(system: &str, user: Option<&str>, messages: Option<&str>) -> Vec<String> {
+    let messages = messages
+        .and_then(|m| serde_json::from_str::<Vec<ChatCompletionRequestMessage>>(m).ok())
+        .unwrap_or_default();
+
+    std::iter::once(system.to_string())
+        .chain(user.map(str::to_string))
+        .chain(messages.iter().filter_map(|m| m.content()))
+        .collect()
+}
+
+/// Loads just the snippets the templates pull in, following snippets that include other
+/// snippets, rather than the whole library.
+pub async fn load_snippet_dependencies(
+    templates: &[String],
+    repository: &SnippetRepository,
+) -> anyhow::Result<Vec<SnippetRow>> {
+    let mut pending = BTreeSet::new();
+    for (i, template) in templates.iter().enumerate() {
+        pending.extend(template_dependencies(&format!("template_{}", i), template)?);
+    }
+
+    let mut requested = BTreeSet::new();
+    let mut loaded = Vec::new();
+    while !pending.is_empty() {
+        let keys = pending.into_iter().collect::<Vec<_>>();
+        requested.extend(keys.iter().cloned());
+
+        pending = BTreeSet::new();
+        for snippet in repository.get_snippets_by_keys(&keys).await? {
+            pending.extend(
+                template_dependencies(&snippet.key, &snippet.content)?
+                    .into_iter()
+                    .filter(|key| !requested.contains(key)),
+            );
+            loaded.push(snippet);
+        }
+    }
+
+    Ok(loaded)
+}
+
+/// Builds a Tera instance with every snippet registered under its key, followed by the given
+/// templates. Everything is added in one go so snippets can import each other's macros
+/// regardless of order. `model_name` picks the tokenizer used by `truncate_tokens`.
+pub fn build_tera(
+    snippets: &[SnippetRow],
+    templates: &[(&str, &str)],
+    model_name: &str,
+) -> tera::Result<Tera> {
+    let mut tera = Tera::default();
+    register_helpers(&mut tera, model_name);
+
+    let all_templates = snippets
+        .iter()
+        .map(|s| (s.key.as_str(), s.content.as_str()))
+        .chain(templates.iter().copied());
+
+    tera.add_raw_templates(all_templates)?;
+
+    Ok(tera)
+}
+
+/// Returns the names of the templates a template pulls in directly through
+/// `include`, `import` or `extends`.
+pub fn template_dependencies(name: &str, content: &str) -> tera::Result<BTreeSet<String>> {
+    let template = Template::new(name, None, content)?;
+
+    let mut dependencies = BTreeSet::new();
+    collect_dependencies(&template.ast, &mut dependencies);
+
+    Ok(dependencies)
+}
+
+/// Returns every snippet key a template depends on, following snippets that include other snippets.
+pub fn resolve_snippet_dependencies(
+    content: &str,
+    snippets: &[SnippetRow],
+) -> tera::Result<BTreeSet<String>> {
+    let mut snippet_dependencies: HashMap<&str, BTreeSet<String>> = HashMap::new();
+    for snippet in snippets {
+        snippet_dependencies.insert(
+            snippet.key.as_str(),
+            template_dependencies(&snippet.key, &snippet.content)?,
+        );
+    }
+
+    let mut resolved = BTreeSet::new();
+    let mut pending: Vec<String> = template_dependencies("template", content)?
+        .into_iter()
+        .collect();
+
+    while let Some(key) = pending.pop() {
+        if !resolved.insert(key.clone()) {
+            continue;
+        }
+
+        if let Some(dependencies) = snippet_dependencies.get(key.as_str()) {
+            pending.extend(dependencies.iter().cloned());
+        }
+    }
+
+    Ok(resolved)
+}
+
+// The tera AST is doc(hidden) but it's the only reliable way to find includes inside
+// blocks, loops and conditionals
+fn collect_dependencies(nodes: &[Node], dependencies: &mut BTreeSet<String>) {
+    for node in nodes {
+        match node {
+            Node::Include(_, names, _) => dependencies.extend(names.iter().cloned()),
+            Node::ImportMacro(_, name, _) => {
+                dependencies.insert(name.clone());
+            }
+            Node::Extends(_, name) => {
+                dependencies.insert(name.clone());
+            }
+            Node::MacroDefinition(_, definition, _) => {
+                collect_dependencies(&definition.body, dependencies)
+            }
+            Node::FilterSection(_, section, _) => collect_dependencies(&section.body, dependencies),
+            Node::Block(_, block, _) => collect_dependencies(&block.body, dependencies),
+            Node::Forloop(_, forloop, _) => {
+                collect_dependencies(&forloop.body, dependencies);
+                if let Some(empty_body) = &forloop.empty_body {
+                    collect_dependencies(empty_body, dependencies);
+                }
+            }
+            Node::If(condition, _) => {
+                for (_, _, body) in &condition.conditions {
+                    collect_dependencies(body, dependencies);
+                }
+                if let Some((_, body)) = &condition.otherwise {
+                    collect_dependencies(body, dependencies);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use tera::Context;
+
+    fn create_test_snippet(key: &str, content: &str) -> SnippetRow {
+        SnippetRow {
+            id: 1,
+            key: key.to_string(),
+            description: None,
+            content: content.to_string(),
+            version_id: 1,
+            version_number: 1,
+            created_at: Utc::now().naive_utc(),
+            updated_at: Utc::now().naive_utc(),
+        }
+    }
+
+    #[test]
+    fn test_include_and_macro_snippets_render() {
+        let snippets = vec![
+            create_test_snippet("safety_preamble", "Be safe."),
+            create_test_snippet(
+                "format_macros",
+                "{% macro shout(text) %}{{ text | upper }}{% endmacro shout %}",
+            ),
+        ];
+
+        let tera = build_tera(
+            &snippets,
+            &[(
+                "system_prompt",
+                "{% import \"format_macros\" as fmt %}{% include \"safety_preamble\" %} {{ fmt::shout(text=name) }}",
+            )],
+            "gpt-4o",
+        )
+        .expect("templates should build");
+
+        let mut ctx = Context::new();
+        ctx.insert("name", "bob");
+
+        let rendered = tera.render("system_prompt", &ctx).unwrap();
+        assert_eq!(rendered, "Be safe. BOB");
+    }
+
+    #[test]
+    fn test_missing_snippet_fails_to_build() {
+        let result = build_tera(&[], &[("system_prompt", "{% import \"missing\" as m %}hi")], "gpt-4o");
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_reserved_template_names() {
+        assert!(is_reserved_template_name("system_prompt"));
+        assert!(is_reserved_template_name("user_prompt"));
+        assert!(is_reserved_template_name("message_3"));
+        assert!(!is_reserved_template_name("message_tone"));
+        assert!(!is_reserved_template_name("tone"));
+    }
+
+    #[tokio::test]
+    async fn test_load_snippet_dependencies_skips_unused_snippets() {
+        let pool = sqlx::SqlitePool::connect(":memory:").await.unwrap();
+        sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+        let repository = SnippetRepository::in_memory(pool).await.unwrap();
+
+        repository.create_snippet("tone", None, "{% include \"base_tone\" %}").await.unwrap();
+        repository.create_snippet("base_tone", None, "Be friendly.").await.unwrap();
+        repository.create_snippet("unused", None, "{% include \"missing\" %}").await.unwrap();
+
+        let templates = prompt_templates(
+            "Hi",
+            None,
+            Some(r#"[{"role": "system", "content": "{% include \"tone\" %}"}]"#),
+        );
+        let snippets = load_snippet_dependencies(&templates, &repository).await.unwrap();
+
+        let mut keys = snippets.iter().map(|s| s.key.as_str()).collect::<Vec<_>>();
+        keys.sort();
+        assert_eq!(keys, ["base_tone", "tone"]);
+    }
+
+    #[test]
+    fn test_resolve_snippet_dependencies_is_transitive() {
+        let snippets = vec![
+            create_test_snippet("tone", "{% include \"base_tone\" %}"),
+            create_test_snippet("base_tone", "Be friendly."),
+            create_test_snippet("unused", "Never included."),
+        ];
+
+        let dependencies = resolve_snippet_dependencies(
+            "{% if formal %}{% include \"tone\" %}{% endif %}",
+            &snippets,
+        )
+        .unwrap();
+
+        assert_eq!(
+            dependencies.into_iter().collect::<Vec<_>>(),
+            vec!["base_tone".to_string(), "tone".to_string()]
+        );
+    }
+}
@@ -0,0 +1,116 @@
+use serde::{Deserialize, Serialize};
+
+use crate::common::types::chat_request::ChatCompletionRequestMessage;
+
+use super::eval_assertion::EvalAssertion;
+
+pub const BUNDLE_FORMAT_VERSION: i64 = 1;
+
+/// A portable snapshot of prompts, the tools they use and their evals. Models are referenced
+/// by provider and name and everything else by key/name, so a bundle can move between instances.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromptBundle {
+    pub format_version: i64,
+    #[serde(default)]
+    pub tools: Vec<BundleTool>,
+    #[serde(default)]
+    pub prompts: Vec<BundlePrompt>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleTool {
+    pub name: String,
+    pub tool_name: String,
+    pub description: String,
+    pub parameters: serde_json::Value,
+    pub strict: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundlePrompt {
+    pub key: String,
+    /// `version_number` of the version that should be active
+    pub active_version: i64,
+    pub versions: Vec<BundlePromptVersion>,
+    #[serde(default)]
+    pub evals: Vec<BundleEval>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundlePromptVersion {
+    pub version_number: i64,
+    pub provider: String,
+    pub model: String,
+    pub system: String,
+    pub user: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub messages: Option<Vec<ChatCompletionRequestMessage>>,
+    pub max_tokens: i64,
+    pub temperature: f64,
+    pub json_mode: bool,
+    pub json_schema: Option<String>,
+    pub prompt_type: String,
+    pub is_chat: bool,
+    pub reasoning_effort: Option<String>,
+    /// Names of the tools this version has access to
+    #[serde(default)]
+    pub tools: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleEval {
+    pub name: String,
+    pub evaluation_type: String,
+    pub system_prompt_input: Option<String>,
+    pub user_prompt_input: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expected_output: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub assertions: Vec<EvalAssertion>,
+    /// Conversation of a multi-turn eval
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub messages: Vec<ChatCompletionRequestMessage>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BundleImportAction {
+    Created,
+    Updated,
+    Unchanged,
+    Skipped,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BundleImportReport {
+    pub dry_run: bool,
+    pub tools: Vec<BundleToolImport>,
+    pub prompts: Vec<BundlePromptImport>,
+    pub conflicts: Vec<BundleConflict>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BundleToolImport {
+    pub name: String,
+    pub action: BundleImportAction,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BundlePromptImport {
+    pub key: String,
+    /// `None` for prompts that would be created by a dry run or were skipped
+    pub prompt_id: Option<i64>,
+    pub action: BundleImportAction,
+    pub versions_created: i64,
+    /// Version number the bundle's active version has (or would have) on this instance
+    pub active_version_number: Option<i64>,
+    pub evals_created: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BundleConflict {
+    /// One of `prompt`, `model`, `tool` or `eval`
+    pub kind: String,
+    pub key: String,
+    pub message: String,
+}
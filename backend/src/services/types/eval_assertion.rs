@@ -0,0 +1,48 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// A check an automated eval runs against the model's response.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum EvalAssertion {
+    /// Whole response equals `expected`, or the eval's expected output when omitted
+    ExactMatch {
+        expected: Option<String>,
+        #[serde(default)]
+        trim: bool,
+    },
+    Contains {
+        value: String,
+        #[serde(default)]
+        ignore_case: bool,
+    },
+    Regex {
+        pattern: String,
+    },
+    /// Response parses as JSON valid against `schema`, or the prompt's JSON schema when omitted
+    JsonSchemaValid {
+        schema: Option<Value>,
+    },
+    /// Value at `path` (`$.a.b[0]` or a JSON pointer like `/a/b/0`) equals `expected`
+    JsonPathEquals {
+        path: String,
+        expected: Value,
+    },
+    /// At most `max` characters
+    MaxLength {
+        max: usize,
+    },
+    /// Some tool call has this name and, if given, arguments containing every field of `arguments`
+    ToolCall {
+        name: String,
+        arguments: Option<Value>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AssertionResult {
+    pub assertion: EvalAssertion,
+    pub passed: bool,
+    /// Why it failed, `None` when it passed
+    pub message: Option<String>,
+}
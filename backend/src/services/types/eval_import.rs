@@ -0,0 +1,67 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::eval_assertion::EvalAssertion;
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EvalImportFormat {
+    Csv,
+    Jsonl,
+}
+
+/// Which column (CSV) or field (JSONL) holds each part of an eval case. When nothing is
+/// mapped, columns are matched by name: `name`, `user`, `expected_output`, `assertions`,
+/// `system.<variable>` and `user.<variable>`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct EvalImportMapping {
+    pub name: Option<String>,
+    /// Template variable -> column
+    pub system_variables: BTreeMap<String, String>,
+    pub user_variables: BTreeMap<String, String>,
+    /// Plain text user message, for prompts without a user template
+    pub user_input: Option<String>,
+    pub expected_output: Option<String>,
+    /// JSON array of assertions
+    pub assertions: Option<String>,
+}
+
+impl EvalImportMapping {
+    pub fn is_empty(&self) -> bool {
+        self.name.is_none()
+            && self.system_variables.is_empty()
+            && self.user_variables.is_empty()
+            && self.user_input.is_none()
+            && self.expected_output.is_none()
+            && self.assertions.is_none()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EvalImportCase {
+    pub name: Option<String>,
+    pub system_prompt_input: Option<Value>,
+    /// A JSON object of user variables, or the user message as a string
+    pub user_prompt_input: Value,
+    pub expected_output: Option<String>,
+    pub assertions: Vec<EvalAssertion>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EvalImportRowError {
+    /// 1-based line in the uploaded data
+    pub row: u64,
+    pub message: String,
+}
+
+/// Nothing is imported when any row has an error.
+#[derive(Debug, Serialize)]
+pub struct EvalImportReport {
+    pub dry_run: bool,
+    pub imported: usize,
+    pub cases: Vec<EvalImportCase>,
+    pub errors: Vec<EvalImportRowError>,
+}
@@ -0,0 +1,39 @@
+use serde::Serialize;
+
+/// Progress of a running eval job, sent to SSE subscribers.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum EvalJobEvent {
+    /// One eval finished one round on one version and model. `run_ids` are the
+    /// `prompt_eval_run` rows it stored.
+    Case {
+        prompt_version_id: i64,
+        model_id: i64,
+        prompt_eval_id: i64,
+        round: i64,
+        run_ids: Vec<i64>,
+        error: Option<String>,
+    },
+    Progress {
+        status: String,
+        total: i64,
+        completed: i64,
+        failed: i64,
+    },
+    /// Last event of a job
+    Done {
+        status: String,
+        error: Option<String>,
+    },
+}
+
+impl EvalJobEvent {
+    /// SSE event name
+    pub fn name(&self) -> &'static str {
+        match self {
+            EvalJobEvent::Case { .. } => "case",
+            EvalJobEvent::Progress { .. } => "progress",
+            EvalJobEvent::Done { .. } => "done",
+        }
+    }
+}
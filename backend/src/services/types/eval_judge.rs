@@ -0,0 +1,9 @@
+use serde::{Deserialize, Serialize};
+
+/// What the judge has to answer with, enforced through the judge call's JSON schema.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JudgeVerdict {
+    /// 1-5, same scale as human reviews
+    pub score: i64,
+    pub rationale: String,
+}
@@ -0,0 +1,36 @@
+use serde::{Deserialize, Serialize};
+
+/// One prompt version run against one model.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EvalMatrixCell {
+    pub prompt_version_id: i64,
+    pub model_id: i64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EvalMatrixReport {
+    pub eval_job_id: i64,
+    pub prompt_id: i64,
+    pub status: String,
+    pub cells: Vec<EvalMatrixCellReport>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct EvalMatrixCellReport {
+    pub prompt_version_id: i64,
+    pub version_number: Option<i64>,
+    pub model_id: i64,
+    pub model_name: Option<String>,
+    pub runs: i64,
+    pub scored_runs: i64,
+    /// 1-5, over the runs that have a score
+    pub avg_score: Option<f64>,
+    /// Share of assertions passed, `None` without assertions
+    pub pass_rate: Option<f64>,
+    pub avg_latency_ms: Option<f64>,
+    pub input_tokens: i64,
+    pub output_tokens: i64,
+    pub reasoning_tokens: i64,
+    /// USD, `None` when none of the cell's calls were priced
+    pub cost: Option<f64>,
+}
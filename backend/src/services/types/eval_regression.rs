@@ -0,0 +1,53 @@
+use serde::Serialize;
+
+/// How the candidate version did on a case compared to the baseline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EvalCaseOutcome {
+    Win,
+    Loss,
+    Tie,
+    /// One of the versions has no score for the case
+    Unscored,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EvalRegressionReport {
+    pub prompt_id: i64,
+    pub baseline_version_id: i64,
+    pub candidate_version_id: i64,
+    /// Only the runs against this model when set
+    pub model_id: Option<i64>,
+    /// Cases run on both versions
+    pub compared_cases: usize,
+    /// Cases run on only one of the versions, left out of the comparison
+    pub unmatched_cases: usize,
+    pub wins: usize,
+    pub losses: usize,
+    pub ties: usize,
+    /// Mean of the per-case score deltas, candidate minus baseline
+    pub mean_score_delta: Option<f64>,
+    pub baseline_pass_rate: Option<f64>,
+    pub candidate_pass_rate: Option<f64>,
+    /// Two-sided sign test over wins and losses, `None` when every case tied
+    pub p_value: Option<f64>,
+    pub significant: bool,
+    /// Regressed cases first, worst first
+    pub cases: Vec<EvalCaseComparison>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EvalCaseComparison {
+    pub prompt_eval_id: i64,
+    pub prompt_eval_name: String,
+    pub outcome: EvalCaseOutcome,
+    /// Lower score or lower assertion pass rate than the baseline
+    pub regressed: bool,
+    pub baseline_score: Option<f64>,
+    pub candidate_score: Option<f64>,
+    pub score_delta: Option<f64>,
+    pub baseline_pass_rate: Option<f64>,
+    pub candidate_pass_rate: Option<f64>,
+    pub baseline_output: String,
+    pub candidate_output: String,
+}
@@ -0,0 +1,93 @@
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EvalSuiteCaseStatus {
+    Passed,
+    Failed,
+    /// The case couldn't be run, like a provider error
+    Errored,
+    /// Human-reviewed cases, which have nothing to check automatically
+    Skipped,
+}
+
+/// One eval case in one round of a suite run.
+#[derive(Debug, Clone, Serialize)]
+pub struct EvalSuiteCase {
+    pub prompt_eval_id: i64,
+    pub name: String,
+    pub round: i64,
+    pub status: EvalSuiteCaseStatus,
+    pub score: Option<i64>,
+    pub assertions_passed: usize,
+    pub assertions_total: usize,
+    /// Why the case failed or errored
+    pub failures: Vec<String>,
+    pub output: Option<String>,
+    pub latency_ms: Option<i64>,
+}
+
+impl EvalSuiteCase {
+    pub fn errored(prompt_eval_id: i64, name: String, round: i64, error: String) -> Self {
+        EvalSuiteCase {
+            prompt_eval_id,
+            name,
+            round,
+            status: EvalSuiteCaseStatus::Errored,
+            score: None,
+            assertions_passed: 0,
+            assertions_total: 0,
+            failures: vec![error],
+            output: None,
+            latency_ms: None,
+        }
+    }
+
+    /// A case passes when every assertion passed or, for cases graded only by a judge, when
+    /// its score is at least `min_score`. Cases with neither are skipped.
+    pub fn grade(mut self, min_score: i64) -> Self {
+        if self.status == EvalSuiteCaseStatus::Errored {
+            return self;
+        }
+
+        self.status = match (self.assertions_total, self.score) {
+            (0, None) => EvalSuiteCaseStatus::Skipped,
+            (0, Some(score)) if score < min_score => {
+                self.failures.push(format!("Score {} is below {}", score, min_score));
+                EvalSuiteCaseStatus::Failed
+            }
+            (0, Some(_)) => EvalSuiteCaseStatus::Passed,
+            (total, _) if self.assertions_passed < total => EvalSuiteCaseStatus::Failed,
+            _ => EvalSuiteCaseStatus::Passed,
+        };
+
+        self
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct EvalSuiteReport {
+    pub prompt_key: String,
+    pub prompt_id: i64,
+    pub prompt_version_id: i64,
+    pub version_number: i64,
+    pub total: usize,
+    pub passed: usize,
+    pub failed: usize,
+    pub errored: usize,
+    pub skipped: usize,
+    /// Passed cases over every case that wasn't skipped
+    pub pass_rate: Option<f64>,
+    /// 1-5, over the cases that have a score
+    pub avg_score: Option<f64>,
+    /// Thresholds the run fell below, empty when it passed
+    pub threshold_failures: Vec<String>,
+    pub cases: Vec<EvalSuiteCase>,
+}
+
+/// Minimums a suite run has to meet, unset ones aren't checked.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EvalThresholds {
+    pub min_pass_rate: Option<f64>,
+    pub min_score: Option<f64>,
+}
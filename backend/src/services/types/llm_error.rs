@@ -105,6 +105,18 @@ pub enum LlmError {
     AsyncOpenAiError(#[from] async_openai::error::OpenAIError),
 }
 
+impl LlmError {
+    /// Name of the variant, e.g. `RateLimit`, for labelling errors without their details
+    pub fn variant_name(&self) -> String {
+        let debug = format!("{:?}", self);
+        debug
+            .split(['(', ' ', '{'])
+            .next()
+            .unwrap_or_default()
+            .to_string()
+    }
+}
+
 impl From<openrouter_api::Error> for LlmError {
     fn from(err: openrouter_api::Error) -> Self {
         match err {
@@ -1,6 +1,6 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use tera::{Context, Tera};
+use tera::Context;
 
 use crate::{
     common::types::{
@@ -10,7 +10,8 @@ use crate::{
         },
         models::LlmApiProvider,
     },
-    db::types::prompt::PromptRowWithModel,
+    db::types::{prompt::PromptRowWithModel, snippet::SnippetRow},
+    services::{templates::build_tera, types::llm_error::LlmError},
 };
 
 #[derive(Debug, thiserror::Error)]
@@ -21,6 +22,8 @@ pub enum LlmServiceRequestError {
     TeraRenderError(tera::Error),
     #[error("Malformed input for Chat")]
     ChatMessagesInputError,
+    #[error("Malformed prompt message templates")]
+    MessageTemplatesError,
 }
 
 #[derive(Serialize, Clone, Debug)]
@@ -28,10 +31,72 @@ pub struct LlmServiceRequest {
     pub provider: LlmApiProvider,
     pub base_url: Option<String>,
     pub prompt_id: i64,
+    /// Prompt version the request was rendered from, logs can be searched by it
+    #[serde(skip)]
+    pub prompt_version_id: Option<i64>,
     pub model_id: i64,
     pub is_reasoning: bool,
     pub reasoning_effort: Option<String>,
     pub request: ChatCompletionRequest,
+    /// What the templates were rendered with, logged so a request can become an eval case
+    pub template_variables: TemplateVariables,
+    /// API key the request came in with, its cost is attributed to it
+    #[serde(skip)]
+    pub api_key_id: Option<i64>,
+    /// Labels the request's metrics
+    #[serde(skip)]
+    pub prompt_key: String,
+    /// Provider API key to use instead of the one in the environment
+    #[serde(skip)]
+    pub provider_api_key: Option<String>,
+}
+
+/// The caller's inputs to a prompt's templates.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TemplateVariables {
+    /// System context, `{}` when the system message isn't a JSON object
+    pub system: Value,
+    /// Last user message, parsed as JSON for `dynamic_both` prompts
+    pub user: Option<Value>,
+}
+
+impl TemplateVariables {
+    fn from_request(prompt: &PromptRowWithModel, request: &ChatCompletionRequest) -> Self {
+        let system = request
+            .messages
+            .iter()
+            .find(|msg| msg.is_system())
+            .and_then(|msg| serde_json::from_str::<Value>(&msg.system_content()).ok())
+            .filter(|v| v.is_object())
+            .unwrap_or(Value::Object(serde_json::Map::new()));
+
+        let user = request
+            .messages
+            .iter()
+            .rev()
+            .find(|msg| msg.is_user())
+            .map(|msg| {
+                let content = msg.user_content();
+                if prompt.prompt_type == "dynamic_both" {
+                    serde_json::from_str::<Value>(&content).unwrap_or(Value::String(content))
+                } else {
+                    Value::String(content)
+                }
+            });
+
+        TemplateVariables { system, user }
+    }
+}
+
+/// How long one attempt at a request took.
+#[derive(Debug, Clone, Copy)]
+pub struct RequestTiming {
+    pub started_at: chrono::NaiveDateTime,
+    pub duration_ms: i64,
+    /// Until the first streamed chunk, `None` for requests that aren't streamed
+    pub first_token_ms: Option<i64>,
+    /// 1 for the first try
+    pub attempt: i64,
 }
 
 impl LlmServiceRequest {
@@ -39,11 +104,106 @@ impl LlmServiceRequest {
         prompt: PromptRowWithModel,
         request: ChatCompletionRequest,
     ) -> Result<Self, LlmServiceRequestError> {
-        let current_user_prompt = prompt.user.unwrap_or("".to_string());
+        Self::new_with_snippets(prompt, request, &[])
+    }
+
+    /// Same as `new`, but with the given snippets available to the templates through
+    /// `include` and `import`.
+    #[tracing::instrument(name = "render_templates", skip_all, fields(llmkit.prompt.key = %prompt.key))]
+    pub fn new_with_snippets(
+        prompt: PromptRowWithModel,
+        request: ChatCompletionRequest,
+        snippets: &[SnippetRow],
+    ) -> Result<Self, LlmServiceRequestError> {
+        let template_variables = TemplateVariables::from_request(&prompt, &request);
+
+        let new_messages = match prompt.messages.as_deref() {
+            Some(templates) => Self::render_message_templates(&prompt, templates, &request, snippets)?,
+            None => Self::render_system_user_templates(&prompt, &request, snippets)?,
+        };
+
+        // Create a new request with the updated messages
+        let mut new_request = request.clone();
+        new_request.messages = new_messages;
+
+        // Create request with all properties and overrides
+        let mut service_request = LlmServiceRequest {
+            prompt_id: prompt.id,
+            prompt_version_id: Some(prompt.version_id),
+            model_id: prompt.model_id,
+            provider: prompt.provider_name.clone().into(),
+            base_url: prompt.provider_base_url,
+            is_reasoning: prompt.is_reasoning,
+            reasoning_effort: prompt.reasoning_effort.clone(),
+            request: new_request,
+            template_variables,
+            api_key_id: None,
+            prompt_key: prompt.key.clone(),
+            provider_api_key: None,
+        };
+
+        // Override input with inputs from Prompt table
+        // TODO: We should make the DB fields match the struct field types if possible
+        service_request.request.max_tokens = Some(prompt.max_tokens as u32);
+        service_request.request.temperature = Some(prompt.temperature as f32);
+        service_request.request.model = prompt.model_name.clone();
+
+        if prompt.json_mode && prompt.supports_json {
+            if prompt.supports_json_schema {
+                match prompt.json_schema {
+                    Some(js) => {
+                        let schmea = ChatCompletionRequestJsonSchema {
+                            name: "schema".to_string(),
+                            strict: true,
+                            schema: serde_json::from_str(&js).expect("Invalid JSON schema"),
+                        };
+
+                        service_request.request.response_format =
+                            Some(ChatCompletionRequestResponseFormat {
+                                format_type: "json_object".to_string(),
+                                json_schema: Some(schmea),
+                            });
+                    }
+                    None => {
+                        service_request.request.response_format =
+                            Some(ChatCompletionRequestResponseFormat {
+                                format_type: "json_object".to_string(),
+                                json_schema: None,
+                            });
+                    }
+                }
+            } else {
+                service_request.request.response_format =
+                    Some(ChatCompletionRequestResponseFormat {
+                        format_type: "json_object".to_string(),
+                        json_schema: None,
+                    });
+            }
+        }
+
+        if !prompt.supports_tools {
+            service_request.request.tools = None; // override to None
+        }
+
+        Ok(service_request)
+    }
 
-        let mut tera = Tera::default();
-        tera.add_raw_template("system_prompt", &prompt.system)?;
-        tera.add_raw_template("user_prompt", &current_user_prompt)?;
+    /// Renders the single system/user template pair against the request messages.
+    fn render_system_user_templates(
+        prompt: &PromptRowWithModel,
+        request: &ChatCompletionRequest,
+        snippets: &[SnippetRow],
+    ) -> Result<Vec<ChatCompletionRequestMessage>, LlmServiceRequestError> {
+        let current_user_prompt = prompt.user.clone().unwrap_or("".to_string());
+
+        let tera = build_tera(
+            snippets,
+            &[
+                ("system_prompt", &prompt.system),
+                ("user_prompt", &current_user_prompt),
+            ],
+            &prompt.model_name,
+        )?;
 
         // Always extract context from system message if available
         let system_context = request
@@ -65,16 +225,8 @@ impl LlmServiceRequest {
             .render("system_prompt", &system_ctx)
             .map_err(|e| LlmServiceRequestError::TeraRenderError(e))?;
 
-        if let Some(json_schema) = &prompt.json_schema {
-            // double check we are in JSON mode and json_schema wasn't passed somehow in error
-            if prompt.json_mode {
-                let json_schema_addition = format!(
-                    "Please respond in adherence to the following JSON Schema: {}",
-                    json_schema
-                );
-
-                rendered_system_prompt.push_str(&json_schema_addition);
-            }
+        if let Some(json_schema_addition) = json_schema_instruction(prompt) {
+            rendered_system_prompt.push_str(&json_schema_addition);
         }
 
         // If the message length is greater than or equal to two that means that we have atleast:
@@ -149,65 +301,137 @@ impl LlmServiceRequest {
             }
         };
 
-        // Create a new request with the updated messages
-        let mut new_request = request.clone();
-        new_request.messages = new_messages;
+        Ok(new_messages)
+    }
 
-        // Create request with all properties and overrides
-        let mut service_request = LlmServiceRequest {
-            prompt_id: prompt.id,
-            model_id: prompt.model_id,
-            provider: prompt.provider_name.clone().into(),
-            base_url: prompt.provider_base_url,
-            is_reasoning: prompt.is_reasoning,
-            reasoning_effort: prompt.reasoning_effort.clone(),
-            request: new_request,
-        };
+    /// Renders an ordered list of message templates (system, few-shot examples, final user).
+    /// Every template sees the system message context, plus the user message context for
+    /// `dynamic_both` prompts. The caller's conversation is appended after the templates.
+    fn render_message_templates(
+        prompt: &PromptRowWithModel,
+        templates: &str,
+        request: &ChatCompletionRequest,
+        snippets: &[SnippetRow],
+    ) -> Result<Vec<ChatCompletionRequestMessage>, LlmServiceRequestError> {
+        let templates = serde_json::from_str::<Vec<ChatCompletionRequestMessage>>(templates)
+            .map_err(|_| LlmServiceRequestError::MessageTemplatesError)?;
+
+        let names = (0..templates.len())
+            .map(|i| format!("message_{}", i))
+            .collect::<Vec<_>>();
+        let contents = templates
+            .iter()
+            .map(|t| t.content().unwrap_or_default())
+            .collect::<Vec<_>>();
+        let named_templates = names
+            .iter()
+            .map(String::as_str)
+            .zip(contents.iter().map(String::as_str))
+            .collect::<Vec<_>>();
 
-        // Override input with inputs from Prompt table
-        // TODO: We should make the DB fields match the struct field types if possible
-        service_request.request.max_tokens = Some(prompt.max_tokens as u32);
-        service_request.request.temperature = Some(prompt.temperature as f32);
-        service_request.request.model = prompt.model_name.clone();
+        let tera = build_tera(snippets, &named_templates, &prompt.model_name)?;
 
-        if prompt.json_mode && prompt.supports_json {
-            if prompt.supports_json_schema {
-                match prompt.json_schema {
-                    Some(js) => {
-                        let schmea = ChatCompletionRequestJsonSchema {
-                            name: "schema".to_string(),
-                            strict: true,
-                            schema: serde_json::from_str(&js).expect("Invalid JSON schema"),
-                        };
+        // Unlike the system/user mode, the system message doesn't count towards the conversation:
+        // a dynamic_both request is a system context plus a single user context
+        let conversation_len = request.messages.iter().filter(|msg| !msg.is_system()).count();
+        let uses_user_context = conversation_len <= 1 && prompt.prompt_type == "dynamic_both";
 
-                        service_request.request.response_format =
-                            Some(ChatCompletionRequestResponseFormat {
-                                format_type: "json_object".to_string(),
-                                json_schema: Some(schmea),
-                            });
-                    }
-                    None => {
-                        service_request.request.response_format =
-                            Some(ChatCompletionRequestResponseFormat {
-                                format_type: "json_object".to_string(),
-                                json_schema: None,
-                            });
+        let mut ctx = Context::new();
+        let system_context = request
+            .messages
+            .iter()
+            .find(|msg| msg.is_system())
+            .and_then(|msg| serde_json::from_str::<Value>(&msg.system_content()).ok());
+        if let Some(Value::Object(context)) = system_context {
+            for (k, v) in context {
+                ctx.insert(k, &v);
+            }
+        }
+
+        if uses_user_context {
+            let user_content = request
+                .messages
+                .iter()
+                .find(|msg| msg.is_user())
+                .map(|msg| msg.user_content())
+                .unwrap_or_default();
+            let user_context = serde_json::from_str::<Value>(&user_content)
+                .map_err(|_| LlmServiceRequestError::ChatMessagesInputError)?;
+
+            if let Value::Object(context) = user_context {
+                for (k, v) in context {
+                    ctx.insert(k, &v);
+                }
+            }
+        }
+
+        let mut messages = Vec::with_capacity(templates.len() + request.messages.len());
+        for (template, name) in templates.iter().zip(&names) {
+            let content = tera
+                .render(name, &ctx)
+                .map_err(LlmServiceRequestError::TeraRenderError)?;
+
+            messages.push(match template {
+                ChatCompletionRequestMessage::System { name, .. } => {
+                    ChatCompletionRequestMessage::System { content, name: name.clone() }
+                }
+                ChatCompletionRequestMessage::User { name, .. } => {
+                    ChatCompletionRequestMessage::User { content, name: name.clone() }
+                }
+                ChatCompletionRequestMessage::Assistant { content: template_content, tool_calls, name } => {
+                    ChatCompletionRequestMessage::Assistant {
+                        content: template_content.as_ref().map(|_| content),
+                        tool_calls: tool_calls.clone(),
+                        name: name.clone(),
                     }
                 }
-            } else {
-                service_request.request.response_format =
-                    Some(ChatCompletionRequestResponseFormat {
-                        format_type: "json_object".to_string(),
-                        json_schema: None,
-                    });
+                ChatCompletionRequestMessage::Tool { tool_call_id, .. } => {
+                    ChatCompletionRequestMessage::Tool { content, tool_call_id: tool_call_id.clone() }
+                }
+            });
+        }
+
+        // Same as the single template mode, the schema instruction goes on the system message
+        if let Some(json_schema_addition) = json_schema_instruction(prompt) {
+            match messages.iter_mut().find(|msg| msg.is_system()) {
+                Some(ChatCompletionRequestMessage::System { content, .. }) => {
+                    content.push_str(&json_schema_addition)
+                }
+                _ => messages.insert(
+                    0,
+                    ChatCompletionRequestMessage::System {
+                        content: json_schema_addition,
+                        name: None,
+                    },
+                ),
             }
         }
 
-        if !prompt.supports_tools {
-            service_request.request.tools = None; // override to None
+        // For dynamic_both the single user message only carried the template context
+        if !uses_user_context {
+            messages.extend(request.messages.iter().filter(|msg| !msg.is_system()).cloned());
         }
 
-        Ok(service_request)
+        Ok(messages)
+    }
+
+    /// The provider's API key, from `env_var` unless the request carries its own.
+    pub fn provider_api_key(&self, env_var: &str) -> Result<String, LlmError> {
+        match &self.provider_api_key {
+            Some(key) => Ok(key.clone()),
+            None => std::env::var(env_var).map_err(|_| LlmError::InvalidConfig(format!("Missing {}", env_var))),
+        }
+    }
+}
+
+fn json_schema_instruction(prompt: &PromptRowWithModel) -> Option<String> {
+    // double check we are in JSON mode and json_schema wasn't passed somehow in error
+    match &prompt.json_schema {
+        Some(json_schema) if prompt.json_mode => Some(format!(
+            "Please respond in adherence to the following JSON Schema: {}",
+            json_schema
+        )),
+        _ => None,
     }
 }
 
@@ -252,6 +476,15 @@ mod tests {
             user_diff: None,
             created_at: NaiveDateTime::default(),
             updated_at: NaiveDateTime::default(),
+            messages: None,
+            messages_diff: None,
+            read_only: false,
+            change_message: None,
+            created_by: None,
+            is_draft: false,
+            approved_by: None,
+            approved_at: None,
+            requires_approval: false,
         }
     }
 
@@ -268,6 +501,7 @@ mod tests {
             transforms: None,
             max_tokens: Some(2500),
             temperature: Some(0.7),
+            llmkit: None,
         }
     }
     
@@ -302,6 +536,7 @@ mod tests {
             transforms: None,
             max_tokens: Some(2500),
             temperature: Some(0.7),
+            llmkit: None,
         }
     }
 
@@ -1249,4 +1484,111 @@ mod tests {
         assert_eq!(response_format.format_type, "json_object");
         assert!(response_format.json_schema.is_some());
     }
+
+    #[test]
+    fn test_new_with_message_templates() {
+        // Few-shot prompt: system, an example exchange and the final user template
+        let mut prompt = create_test_prompt("unused", None, "dynamic_both");
+        prompt.messages = Some(
+            serde_json::to_string(&vec![
+                ChatCompletionRequestMessage::System {
+                    content: "Classify {{ domain }} tickets.".to_string(),
+                    name: None,
+                },
+                ChatCompletionRequestMessage::User {
+                    content: "My card was charged twice".to_string(),
+                    name: None,
+                },
+                ChatCompletionRequestMessage::Assistant {
+                    content: Some("billing".to_string()),
+                    tool_calls: None,
+                    name: None,
+                },
+                ChatCompletionRequestMessage::User {
+                    content: "{{ ticket }}".to_string(),
+                    name: None,
+                },
+            ])
+            .unwrap(),
+        );
+
+        let messages = vec![
+            ChatCompletionRequestMessage::System {
+                content: r#"{"domain": "support"}"#.to_string(),
+                name: None,
+            },
+            ChatCompletionRequestMessage::User {
+                content: r#"{"ticket": "The app crashes on login"}"#.to_string(),
+                name: None,
+            },
+        ];
+
+        let service_request = LlmServiceRequest::new(prompt, create_chat_request(messages)).unwrap();
+        let messages = &service_request.request.messages;
+
+        assert_eq!(messages.len(), 4);
+        assert_eq!(messages[0].content(), Some("Classify support tickets.".to_string()));
+        assert_eq!(messages[2].content(), Some("billing".to_string()));
+        assert!(messages[3].is_user());
+        assert_eq!(messages[3].content(), Some("The app crashes on login".to_string()));
+    }
+
+    #[test]
+    fn test_new_with_message_templates_appends_conversation() {
+        // For static prompts the caller's messages follow the rendered templates
+        let mut prompt = create_test_prompt("unused", None, "static");
+        prompt.json_mode = true;
+        prompt.json_schema = Some(r#"{"type": "object"}"#.to_string());
+        prompt.messages = Some(
+            r#"[{"role": "user", "content": "Example"}, {"role": "assistant", "content": "{}", "tool_calls": null, "name": null}]"#
+                .to_string(),
+        );
+
+        let messages = vec![ChatCompletionRequestMessage::User {
+            content: "Real question".to_string(),
+            name: None,
+        }];
+
+        let service_request = LlmServiceRequest::new(prompt, create_chat_request(messages)).unwrap();
+        let messages = &service_request.request.messages;
+
+        // No system template, so the schema instruction gets its own system message
+        assert_eq!(messages.len(), 4);
+        assert!(messages[0].is_system());
+        assert!(messages[0].content().unwrap().contains("JSON Schema"));
+        assert_eq!(messages[3].content(), Some("Real question".to_string()));
+    }
+
+    #[test]
+    fn test_new_with_malformed_message_templates() {
+        let mut prompt = create_test_prompt("unused", None, "static");
+        prompt.messages = Some("not json".to_string());
+
+        let messages = vec![ChatCompletionRequestMessage::User {
+            content: "Hello".to_string(),
+            name: None,
+        }];
+
+        let result = LlmServiceRequest::new(prompt, create_chat_request(messages));
+        assert!(matches!(result, Err(LlmServiceRequestError::MessageTemplatesError)));
+    }
+
+    #[test]
+    fn test_template_variables_are_kept() {
+        let prompt = create_test_prompt("Hi {{ name }}", Some("Say {{ word }}"), "dynamic_both");
+        let messages = vec![
+            ChatCompletionRequestMessage::System {
+                content: r#"{"name": "Ada"}"#.to_string(),
+                name: None,
+            },
+            ChatCompletionRequestMessage::User {
+                content: r#"{"word": "hello"}"#.to_string(),
+                name: None,
+            },
+        ];
+
+        let service_request = LlmServiceRequest::new(prompt, create_chat_request(messages)).unwrap();
+        assert_eq!(service_request.template_variables.system, serde_json::json!({ "name": "Ada" }));
+        assert_eq!(service_request.template_variables.user, Some(serde_json::json!({ "word": "hello" })));
+    }
 }
@@ -1,2 +1,13 @@
 pub mod llm_service;
 pub mod llm_error;
+pub mod bundle;
+pub mod prompt_sync;
+pub mod prompt_diff;
+pub mod eval_assertion;
+pub mod eval_judge;
+pub mod eval_job;
+pub mod eval_matrix;
+pub mod eval_import;
+pub mod eval_regression;
+pub mod eval_suite;
+pub mod pricing;
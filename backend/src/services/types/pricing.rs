@@ -0,0 +1,31 @@
+use crate::common::types::chat_response::LlmServiceChatCompletionResponseUsage;
+
+/// Tokens a request was billed for, as reported by the provider.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct TokenUsage {
+    pub input_tokens: Option<i64>,
+    /// Part of the input tokens served from the provider's prompt cache
+    pub cached_input_tokens: Option<i64>,
+    pub output_tokens: Option<i64>,
+    /// Part of the output tokens spent reasoning
+    pub reasoning_tokens: Option<i64>,
+}
+
+impl From<&LlmServiceChatCompletionResponseUsage> for TokenUsage {
+    fn from(usage: &LlmServiceChatCompletionResponseUsage) -> Self {
+        TokenUsage {
+            input_tokens: Some(usage.prompt_tokens as i64),
+            cached_input_tokens: usage
+                .prompt_tokens_details
+                .as_ref()
+                .and_then(|details| details.cached_tokens)
+                .map(|tokens| tokens as i64),
+            output_tokens: Some(usage.completion_tokens as i64),
+            reasoning_tokens: usage
+                .completion_tokens_details
+                .as_ref()
+                .and_then(|details| details.reasoning_tokens)
+                .map(|tokens| tokens as i64),
+        }
+    }
+}
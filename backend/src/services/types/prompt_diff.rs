@@ -0,0 +1,89 @@
+use serde::Serialize;
+use serde_json::Value;
+
+/// Everything that changed between two versions of a prompt.
+#[derive(Debug, Serialize)]
+pub struct PromptVersionDiff {
+    pub prompt_id: i64,
+    pub from_version_id: i64,
+    pub from_version_number: i64,
+    pub to_version_id: i64,
+    pub to_version_number: i64,
+    /// Templates, message templates and the raw JSON schema, only those that changed
+    pub text: Vec<TextDiff>,
+    /// Structural changes to the JSON schema, keyed by JSON pointer
+    pub json_schema: Vec<JsonChange>,
+    pub config: Vec<FieldChange>,
+    pub tools: ToolsDiff,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TextDiff {
+    /// `system`, `user`, `messages` or `json_schema`
+    pub field: String,
+    /// The whole diff in unified format, ready for `patch` or a diff viewer
+    pub unified: String,
+    pub hunks: Vec<DiffHunk>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DiffHunk {
+    /// e.g. `@@ -3,7 +3,8 @@`
+    pub header: String,
+    pub lines: Vec<DiffLine>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DiffLine {
+    /// `equal`, `insert` or `delete`
+    pub tag: &'static str,
+    /// 1-based line numbers, `None` on the side the line doesn't exist on
+    pub old_line: Option<usize>,
+    pub new_line: Option<usize>,
+    pub content: String,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct JsonChange {
+    pub path: String,
+    /// `added`, `removed` or `changed`
+    pub kind: &'static str,
+    pub old: Option<Value>,
+    pub new: Option<Value>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FieldChange {
+    pub field: String,
+    pub old: Value,
+    pub new: Value,
+}
+
+/// Tools are matched by `tool_name`, the function name the model sees, so swapping one tool
+/// for another with the same function shows up as a change to its definition.
+#[derive(Debug, Default, Serialize)]
+pub struct ToolsDiff {
+    pub added: Vec<ToolDefinition>,
+    pub removed: Vec<ToolDefinition>,
+    pub changed: Vec<ToolChange>,
+    /// `tool_name` of the tools that are identical in both versions
+    pub unchanged: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ToolDefinition {
+    pub name: String,
+    pub tool_name: String,
+    pub description: String,
+    pub parameters: Value,
+    pub strict: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ToolChange {
+    pub tool_name: String,
+    /// `name`, `description` and `strict`
+    pub fields: Vec<FieldChange>,
+    /// Structural changes to the parameters schema, keyed by JSON pointer
+    pub parameters: Vec<JsonChange>,
+}
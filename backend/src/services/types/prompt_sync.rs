@@ -0,0 +1,29 @@
+use serde::Deserialize;
+
+/// `prompt.yaml` of a prompt directory. Templates and the JSON schema live in their own files
+/// next to the manifest so they diff cleanly in review.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PromptManifest {
+    /// Defaults to the directory name
+    pub key: Option<String>,
+    pub provider: String,
+    pub model: String,
+    pub max_tokens: i64,
+    pub temperature: f64,
+    pub prompt_type: String,
+    #[serde(default)]
+    pub is_chat: bool,
+    #[serde(default)]
+    pub json_mode: bool,
+    pub reasoning_effort: Option<String>,
+    /// Files relative to the manifest
+    pub system_file: Option<String>,
+    pub user_file: Option<String>,
+    /// JSON or YAML list of message templates, used instead of the system/user templates
+    pub messages_file: Option<String>,
+    pub json_schema_file: Option<String>,
+    /// Names of existing tools the prompt has access to
+    #[serde(default)]
+    pub tools: Vec<String>,
+}